@@ -0,0 +1,71 @@
+//! Time-to-depth of the Lazy SMP search on Reversi Mini, for an increasing number of threads.
+//!
+//! Run with `cargo bench --bench lazy_smp [depth] [transposition entries]`.
+
+use ai_algos::evaluators;
+use ai_algos::interface::rulesets::RuleSetTrait;
+use ai_algos::policies::minimax;
+use ai_algos::rulesets::reversi;
+use ai_algos::rulesets::reversi::BaseVariant;
+use std::env;
+use std::thread;
+
+const DEFAULT_DEPTH: u8 = 10;
+/// Bounds of the default number of transposition entries
+const MIN_TRANSPOSITION_CAPACITY: usize = 1 << 10;
+const MAX_TRANSPOSITION_CAPACITY: usize = 1 << 20;
+
+/// Returns a number of transposition entries in the order of the positions searched to the
+/// depth, each ply leaving about a quarter of the cells as placements and alpha-beta pruning
+/// searching about the square root of the tree.
+fn transposition_capacity(depth: u8) -> usize {
+    let branching = (reversi::Mini::CELL_COUNT / 4) as f64;
+    let positions = branching.powf(depth as f64 / 2.0);
+    (positions as usize).clamp(MIN_TRANSPOSITION_CAPACITY, MAX_TRANSPOSITION_CAPACITY)
+}
+
+fn main() {
+    let mut arguments = env::args().skip(1).filter(|argument| argument != "--bench");
+    let depth = arguments
+        .next()
+        .and_then(|argument| argument.parse().ok())
+        .unwrap_or(DEFAULT_DEPTH);
+    let capacity = arguments
+        .next()
+        .and_then(|argument| argument.parse().ok())
+        .unwrap_or_else(|| transposition_capacity(depth));
+    let cores = thread::available_parallelism().map_or(1, |count| count.get());
+    let ruleset = reversi::Reversi::<reversi::Mini>::new();
+    let state = ruleset.initial_state();
+    let evaluator = evaluators::ReversiEvaluator::new();
+    println!(
+        "Reversi Mini, depth {}, {} transposition entries, {} cores",
+        depth, capacity, cores
+    );
+    println!(
+        "{:>8} {:>12} {:>12} {:>8}",
+        "threads", "time (ms)", "nodes", "speedup"
+    );
+    let mut reference = None;
+    let mut threads = 1;
+    while threads <= cores.max(2) {
+        let mut search = minimax::LazySmp::new(
+            &ruleset,
+            evaluator.clone(),
+            minimax::SearchLimits::depth(depth),
+        );
+        search.set_threads(threads);
+        search.set_transpositions(capacity);
+        let result = search.search(&state);
+        let elapsed = result.elapsed.as_secs_f64();
+        let reference = *reference.get_or_insert(elapsed);
+        println!(
+            "{:>8} {:>12.1} {:>12} {:>8.2}",
+            threads,
+            elapsed * 1000.0,
+            result.statistics.nodes,
+            reference / elapsed,
+        );
+        threads *= 2;
+    }
+}
@@ -0,0 +1,103 @@
+//! Playing strength of the parallel MCTS on Reversi Mini for an increasing number of threads,
+//! each parallelism playing against a single-threaded search with the same time per ply.
+//!
+//! Run with `cargo bench --bench mcts_parallelism [milliseconds per ply] [games]`.
+
+use ai_algos::interface::rulesets;
+use ai_algos::interface::rulesets::Deterministic;
+use ai_algos::interface::rulesets::RuleSetTrait;
+use ai_algos::interface::rulesets::TurnByTurn;
+use ai_algos::policies::mcts;
+use ai_algos::policies::mcts::puct;
+use ai_algos::rulesets::reversi;
+use std::env;
+use std::error;
+use std::thread;
+use std::time;
+
+const DEFAULT_MILLISECONDS: u64 = 20;
+const DEFAULT_GAMES: usize = 10;
+
+type Ruleset = reversi::Reversi<reversi::Mini>;
+
+fn main() -> Result<(), Box<dyn error::Error>> {
+    let mut arguments = env::args()
+        .skip(1)
+        .filter_map(|argument| argument.parse::<u64>().ok());
+    let milliseconds = arguments.next().unwrap_or(DEFAULT_MILLISECONDS);
+    let games = arguments
+        .next()
+        .map_or(DEFAULT_GAMES, |games| games as usize);
+    let time_per_ply = time::Duration::from_millis(milliseconds);
+    let cores = thread::available_parallelism().map_or(1, |count| count.get());
+    println!(
+        "Reversi Mini, {} ms per ply, {} games against a single thread, {} cores",
+        milliseconds, games, cores
+    );
+    println!("{:>8} {:>8} {:>8} {:>8}", "threads", "tree", "leaf", "root");
+    let mut threads = 1;
+    while threads <= cores.max(2) {
+        let parallelisms = [
+            puct::Parallelism::Tree,
+            puct::Parallelism::Leaf,
+            puct::Parallelism::Root { trees: threads },
+        ];
+        let mut scores = Vec::new();
+        for parallelism in parallelisms.iter() {
+            let mut score = 0.0;
+            for game in 0..games {
+                // Both sides take the first ply in turn
+                let player = (game % 2) as rulesets::Player;
+                score += play_game(*parallelism, threads, player, time_per_ply)?;
+            }
+            scores.push(score / games as f32);
+        }
+        println!(
+            "{:>8} {:>8.2} {:>8.2} {:>8.2}",
+            threads, scores[0], scores[1], scores[2]
+        );
+        threads *= 2;
+    }
+    Ok(())
+}
+
+/// Plays a game between the parallel search, playing as the given player, and a single-threaded
+/// one, and returns the score of the parallel search.
+fn play_game(
+    parallelism: puct::Parallelism,
+    threads: usize,
+    player: rulesets::Player,
+    time_per_ply: time::Duration,
+) -> Result<f32, Box<dyn error::Error>> {
+    let ruleset = Ruleset::new();
+    let mut parallel = puct::Orchestrator::new(ruleset.clone());
+    parallel.start(1, threads)?;
+    parallel.set_parallelism(parallelism)?;
+    let mut reference = puct::Orchestrator::new(ruleset.clone());
+    reference.start(1, 1)?;
+    let mut state = ruleset.initial_state();
+    let status = loop {
+        let status = ruleset.status(&state);
+        if status != rulesets::Status::Ongoing {
+            break status;
+        }
+        let (searcher, simulations) = if ruleset.current_player(&state) == player {
+            (&mut parallel, threads)
+        } else {
+            (&mut reference, 1)
+        };
+        searcher.set_state(state.clone())?;
+        searcher.search(mcts::Budget::time(time_per_ply), 1, simulations)?;
+        let considerations = searcher.ply_considerations()?.unwrap();
+        state = ruleset.play(&state, &considerations[0].ply)?;
+    };
+    parallel.stop()?;
+    reference.stop()?;
+    let score = match status.player_pov(player) {
+        rulesets::PlayerStatus::Win => 1.0,
+        rulesets::PlayerStatus::Draw => 0.5,
+        rulesets::PlayerStatus::Loss => 0.0,
+        rulesets::PlayerStatus::Ongoing => unreachable!(),
+    };
+    Ok(score)
+}
@@ -0,0 +1,88 @@
+use crate::interface::ai;
+use crate::interface::rulesets;
+use crate::tools::plies;
+use std::error;
+
+/// Plays the ply leading to the best evaluated state, a winning ply if any.
+pub struct Greedy<'a, RuleSet, Evaluator>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    Evaluator: ai::Evaluator<RuleSet>,
+{
+    ruleset: &'a RuleSet,
+    evaluator: Evaluator,
+}
+
+impl<'a, RuleSet, Evaluator> Greedy<'a, RuleSet, Evaluator>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    Evaluator: ai::Evaluator<RuleSet>,
+{
+    pub fn new(ruleset: &'a RuleSet, evaluator: Evaluator) -> Greedy<'a, RuleSet, Evaluator> {
+        Greedy { ruleset, evaluator }
+    }
+
+    fn score(&self, player: rulesets::Player, state: &RuleSet::State) -> f32 {
+        match self.ruleset.status(state) {
+            rulesets::Status::Win { player: winner } if winner == player => 1.0,
+            rulesets::Status::Win { .. } => -1.0,
+            rulesets::Status::Draw => 0.0,
+            rulesets::Status::Ongoing if self.ruleset.current_player(state) == player => {
+                self.evaluator.evaluate(state)
+            }
+            rulesets::Status::Ongoing => -self.evaluator.evaluate(state),
+        }
+    }
+}
+
+impl<'a, RuleSet, Evaluator> ai::Agent<RuleSet> for Greedy<'a, RuleSet, Evaluator>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    Evaluator: ai::Evaluator<RuleSet>,
+{
+    fn play(&mut self, state: &RuleSet::State) -> Result<RuleSet::Ply, Box<dyn error::Error>> {
+        let player = self.ruleset.current_player(state);
+        let mut best = None;
+        for ply in plies::BasicIterator::new(self.ruleset, state) {
+            let resulting_state = self.ruleset.play(state, &ply)?;
+            let score = self.score(player, &resulting_state);
+            match best {
+                Some((_, best_score)) if best_score >= score => (),
+                _ => best = Some((ply, score)),
+            }
+        }
+        match best {
+            Some((ply, _)) => Ok(ply),
+            None => Err("no ply available".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluators;
+    use crate::interface::ai::Agent;
+    use crate::rulesets::connectn;
+
+    macro_rules! play_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<(), Box<dyn error::Error>> {
+                    let (p1_indices, p2_indices, current_player, expected_index) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let mut agent = Greedy::new(&ruleset, evaluators::ThreatEvaluator::new(&ruleset));
+                    let state = connectn::TicTacToeState::from_indices(&p1_indices, &p2_indices, current_player);
+                    assert_eq!(agent.play(&state)?, connectn::Ply::new(expected_index));
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    play_tests! {
+        winning_move: ([0, 4], [1, 2], 0, 8),
+        blocking_move: ([0, 4], [1], 1, 8),
+    }
+}
@@ -1,5 +1,7 @@
+mod greedy;
 mod random;
 mod stochastic;
 
+pub use greedy::Greedy;
 pub use random::Random;
 pub use stochastic::Stochastic;
@@ -0,0 +1,99 @@
+use crate::interface::ai;
+use crate::rulesets::connectn;
+use crate::utils::bitarray;
+
+/// Weight of a run missing one, two or three pegs to be completed
+const THREAT_WEIGHTS: [f32; 3] = [1.0, 0.1, 0.01];
+/// The current player moves first, so their threats are worth more than their opponent's
+const MOVER_BONUS: f32 = 1.5;
+
+/// Counts the open threats of both players: the runs of cells partially filled by one player and
+/// not blocked by the other one.
+#[derive(Clone)]
+pub struct ThreatEvaluator<Variant: connectn::BaseVariant> {
+    strips: Vec<bitarray::BitArray<Variant::ArraySettings>>,
+}
+
+impl<Variant: connectn::BaseVariant> ThreatEvaluator<Variant> {
+    pub fn new(ruleset: &connectn::RuleSet<Variant>) -> ThreatEvaluator<Variant> {
+        ThreatEvaluator {
+            strips: ruleset.strips().to_vec(),
+        }
+    }
+
+    /// Returns the weighted threat counts of both players.
+    pub fn threats(&self, state: &connectn::State<Variant>) -> [f32; 2] {
+        let mut result = [0.0; 2];
+        for strip in &self.strips {
+            let counts = [
+                (&state.grids[0] & strip).count_ones() as usize,
+                (&state.grids[1] & strip).count_ones() as usize,
+            ];
+            for player in 0..2 {
+                if counts[1 - player] != 0 || counts[player] == 0 {
+                    continue;
+                }
+                let missing = Variant::RUN_COUNT - counts[player];
+                if missing >= 1 && missing <= THREAT_WEIGHTS.len() {
+                    result[player] += THREAT_WEIGHTS[missing - 1];
+                }
+            }
+        }
+        result
+    }
+}
+
+impl<Variant: connectn::BaseVariant> ai::Evaluator<connectn::RuleSet<Variant>>
+    for ThreatEvaluator<Variant>
+{
+    fn evaluate(&self, state: &connectn::State<Variant>) -> f32 {
+        let threats = self.threats(state);
+        let player = state.current_player as usize;
+        (threats[player] * MOVER_BONUS - threats[1 - player]).tanh()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::ai::Evaluator;
+
+    macro_rules! evaluate_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (p1_indices, p2_indices, current_player, expected_threats) = $value;
+                    let ruleset = connectn::Gomoku::new();
+                    let evaluator = ThreatEvaluator::new(&ruleset);
+                    let state = connectn::GomokuState::from_indices(&p1_indices, &p2_indices, current_player);
+                    let threats = evaluator.threats(&state);
+                    for player in 0..2 {
+                        assert!((threats[player] - expected_threats[player]).abs() < 1e-6);
+                    }
+                }
+            )*
+        }
+    }
+
+    evaluate_tests! {
+        empty: ([], [], 0, [0.0, 0.0]),
+        // Corner peg: one horizontal, one vertical and one diagonal run missing four pegs
+        single_corner: ([0], [], 1, [0.0, 0.0]),
+        // The four pegs also partially fill the runs starting on the second and third cells
+        open_four: ([0, 1, 2, 3], [], 1, [1.11, 0.0]),
+        blocked_four: ([0, 1, 2, 3], [4], 1, [0.0, 0.0]),
+        open_two: ([112, 113], [], 1, [0.04, 0.0]),
+        both_players: ([0, 1, 2, 3], [112, 113], 1, [1.11, 0.04]),
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let ruleset = connectn::TicTacToe::new();
+        let evaluator = ThreatEvaluator::new(&ruleset);
+        let state = connectn::TicTacToeState::from_indices(&[0, 4], &[1], 1);
+        assert!(evaluator.evaluate(&state) < 0.0);
+        let state = connectn::TicTacToeState::from_indices(&[0, 4], &[1, 8], 0);
+        assert!(evaluator.evaluate(&state) > 0.0);
+    }
+}
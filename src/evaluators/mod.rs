@@ -0,0 +1,7 @@
+//! Heuristic evaluators of ruleset states
+
+mod connectn;
+mod reversi;
+
+pub use self::connectn::ThreatEvaluator;
+pub use self::reversi::ReversiEvaluator;
@@ -0,0 +1,250 @@
+use crate::interface::ai;
+use crate::rulesets::reversi;
+use crate::utils::bitarray;
+
+const CORNER_WEIGHT: f32 = 1.0;
+/// Cells diagonally adjacent to a corner, which give the corner away
+const X_SQUARE_WEIGHT: f32 = -0.5;
+/// Edge cells adjacent to a corner
+const C_SQUARE_WEIGHT: f32 = -0.2;
+const EDGE_WEIGHT: f32 = 0.1;
+
+const POSITIONAL_SHARE: f32 = 0.3;
+const MOBILITY_SHARE: f32 = 0.3;
+const FRONTIER_SHARE: f32 = 0.15;
+const STABILITY_SHARE: f32 = 0.25;
+
+type Mask<Variant> = bitarray::BitArray<<Variant as reversi::BaseVariant>::ArraySettings>;
+
+/// Blends classic Reversi heuristics:
+///
+/// * positional weights favouring corners and edges and penalizing the cells next to corners,
+/// * mobility, the number of placements available to each player,
+/// * frontier pegs, adjacent to empty cells and thus likely to be reversed,
+/// * stable pegs, which cannot be reversed anymore, here the pegs running along the edges from
+///   an owned corner.
+#[derive(Clone)]
+pub struct ReversiEvaluator<Variant: reversi::BaseVariant> {
+    full: Mask<Variant>,
+    positional_masks: Vec<(f32, Mask<Variant>)>,
+    neighbours: Vec<Mask<Variant>>,
+    /// Cells met walking away from each cell in each direction, for the rays of length 2 or more
+    rays: Vec<Vec<Vec<usize>>>,
+    corners: Vec<(usize, [isize; 2])>,
+}
+
+fn balance(mine: f32, theirs: f32) -> f32 {
+    if mine + theirs == 0.0 {
+        return 0.0;
+    }
+    (mine - theirs) / (mine + theirs)
+}
+
+impl<Variant: reversi::BaseVariant> ReversiEvaluator<Variant> {
+    pub fn new() -> ReversiEvaluator<Variant> {
+        let size = Variant::GRID_SIZE as isize;
+        let last = size - 1;
+        let distance_to_edge = |coordinate: isize| coordinate.min(last - coordinate);
+        let mut cells = vec![Vec::new(); 4];
+        let mut neighbours = Vec::new();
+        let mut rays = Vec::new();
+        for index in 0..Variant::CELL_COUNT {
+            let (row, column) = (index as isize / size, index as isize % size);
+            let (row_distance, column_distance) = (distance_to_edge(row), distance_to_edge(column));
+            let class = match (row_distance, column_distance) {
+                (0, 0) => Some(0),
+                (1, 1) => Some(1),
+                (0, 1) | (1, 0) => Some(2),
+                (0, _) | (_, 0) => Some(3),
+                _ => None,
+            };
+            if let Some(class) = class {
+                cells[class].push(index);
+            }
+            let mut cell_neighbours = Vec::new();
+            let mut cell_rays = Vec::new();
+            for row_step in -1..=1 {
+                for column_step in -1..=1 {
+                    if (row_step, column_step) == (0, 0) {
+                        continue;
+                    }
+                    let mut ray = Vec::new();
+                    let (mut ray_row, mut ray_column) = (row + row_step, column + column_step);
+                    while (0..size).contains(&ray_row) && (0..size).contains(&ray_column) {
+                        ray.push((ray_row * size + ray_column) as usize);
+                        ray_row += row_step;
+                        ray_column += column_step;
+                    }
+                    if let Some(neighbour) = ray.first() {
+                        cell_neighbours.push(*neighbour);
+                    }
+                    if ray.len() >= 2 {
+                        cell_rays.push(ray);
+                    }
+                }
+            }
+            neighbours.push(Mask::<Variant>::from_indices(&cell_neighbours));
+            rays.push(cell_rays);
+        }
+        let weights = [CORNER_WEIGHT, X_SQUARE_WEIGHT, C_SQUARE_WEIGHT, EDGE_WEIGHT];
+        let positional_masks = weights
+            .iter()
+            .zip(cells.iter())
+            .map(|(weight, indices)| (*weight, Mask::<Variant>::from_indices(indices)))
+            .collect();
+        let full = Mask::<Variant>::from_indices(&(0..Variant::CELL_COUNT).collect::<Vec<_>>());
+        let corners = vec![
+            (0, [1, size]),
+            (last as usize, [-1, size]),
+            ((size * last) as usize, [1, -size]),
+            ((size * size - 1) as usize, [-1, -size]),
+        ];
+        ReversiEvaluator {
+            full,
+            positional_masks,
+            neighbours,
+            rays,
+            corners,
+        }
+    }
+
+    fn positional(&self, mine: &Mask<Variant>, theirs: &Mask<Variant>) -> f32 {
+        let score = self
+            .positional_masks
+            .iter()
+            .map(|(weight, mask)| {
+                let difference =
+                    (mine & mask).count_ones() as f32 - (theirs & mask).count_ones() as f32;
+                weight * difference
+            })
+            .sum::<f32>();
+        (score / 2.0).tanh()
+    }
+
+    /// Counts the empty cells on which a placement reverses at least one opponent peg.
+    fn placement_count(&self, player: &Mask<Variant>, opponent: &Mask<Variant>) -> f32 {
+        let reverses = |ray: &Vec<usize>| match ray.iter().position(|cell| !opponent.isset(*cell)) {
+            Some(distance) => distance > 0 && player.isset(ray[distance]),
+            None => false,
+        };
+        (0..Variant::CELL_COUNT)
+            .filter(|index| !player.isset(*index) && !opponent.isset(*index))
+            .filter(|index| self.rays[*index].iter().any(reverses))
+            .count() as f32
+    }
+
+    fn mobility(&self, mine: &Mask<Variant>, theirs: &Mask<Variant>) -> f32 {
+        balance(
+            self.placement_count(mine, theirs),
+            self.placement_count(theirs, mine),
+        )
+    }
+
+    fn frontier(&self, mine: &Mask<Variant>, theirs: &Mask<Variant>) -> f32 {
+        let empty = &self.full ^ &(mine | theirs);
+        let mut next_to_empty = Mask::<Variant>::zero();
+        for index in 0..Variant::CELL_COUNT {
+            if empty.isset(index) {
+                next_to_empty = next_to_empty | &self.neighbours[index];
+            }
+        }
+        // Frontier pegs are a weakness
+        balance(
+            (theirs & &next_to_empty).count_ones() as f32,
+            (mine & &next_to_empty).count_ones() as f32,
+        )
+    }
+
+    fn stable_count(&self, pegs: &Mask<Variant>) -> f32 {
+        let mut stable = Mask::<Variant>::zero();
+        for (corner, steps) in &self.corners {
+            for step in steps {
+                let mut index = *corner as isize;
+                for _ in 0..Variant::GRID_SIZE {
+                    if !pegs.isset(index as usize) {
+                        break;
+                    }
+                    stable.set(index as usize);
+                    index += step;
+                }
+            }
+        }
+        stable.count_ones() as f32
+    }
+
+    fn stability(&self, mine: &Mask<Variant>, theirs: &Mask<Variant>) -> f32 {
+        balance(self.stable_count(mine), self.stable_count(theirs))
+    }
+}
+
+impl<Variant: reversi::BaseVariant> Default for ReversiEvaluator<Variant> {
+    fn default() -> ReversiEvaluator<Variant> {
+        Self::new()
+    }
+}
+
+impl<Variant: reversi::BaseVariant> ai::Evaluator<reversi::Reversi<Variant>>
+    for ReversiEvaluator<Variant>
+{
+    fn evaluate(&self, state: &reversi::State<Variant>) -> f32 {
+        let player = state.current_player as usize;
+        let (mine, theirs) = (&state.grids[player], &state.grids[1 - player]);
+        POSITIONAL_SHARE * self.positional(mine, theirs)
+            + MOBILITY_SHARE * self.mobility(mine, theirs)
+            + FRONTIER_SHARE * self.frontier(mine, theirs)
+            + STABILITY_SHARE * self.stability(mine, theirs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::ai::Evaluator;
+    use crate::interface::rulesets::RuleSetTrait;
+
+    type ClassicState = reversi::State<reversi::Classic>;
+
+    #[test]
+    fn test_initial_state() {
+        let ruleset = reversi::Reversi::<reversi::Classic>::new();
+        let evaluator = ReversiEvaluator::new();
+        assert_eq!(evaluator.evaluate(&ruleset.initial_state()), 0.0);
+    }
+
+    #[test]
+    fn test_symmetric_evaluation() {
+        let evaluator = ReversiEvaluator::<reversi::Classic>::new();
+        let state = ClassicState::from_indices(&[0, 9, 27, 36], &[1, 28, 35, 44], 0);
+        let swapped = ClassicState::from_indices(&[1, 28, 35, 44], &[0, 9, 27, 36], 1);
+        assert_eq!(evaluator.evaluate(&state), evaluator.evaluate(&swapped));
+        let opponent_view = ClassicState::from_indices(&[0, 9, 27, 36], &[1, 28, 35, 44], 1);
+        assert!((evaluator.evaluate(&state) + evaluator.evaluate(&opponent_view)).abs() < 1e-6);
+    }
+
+    macro_rules! feature_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (p1_indices, p2_indices, feature, expected): (&[usize], &[usize], fn(&ReversiEvaluator<reversi::Classic>, &Mask<reversi::Classic>, &Mask<reversi::Classic>) -> f32, f32) = $value;
+                    let evaluator = ReversiEvaluator::new();
+                    let state = ClassicState::from_indices(p1_indices, p2_indices, 0);
+                    let value = feature(&evaluator, &state.grids[0], &state.grids[1]);
+                    assert!((value - expected).abs() < 1e-6, "{}", value);
+                }
+            )*
+        }
+    }
+
+    feature_tests! {
+        corner_stability: (&[0, 1, 2, 8], &[3, 16], ReversiEvaluator::stability, 1.0),
+        shared_stability: (&[0, 1], &[63], ReversiEvaluator::stability, 1.0 / 3.0),
+        no_stability: (&[1, 2], &[27], ReversiEvaluator::stability, 0.0),
+        // The pegs of the first player are all surrounded
+        inner_frontier: (&[27], &[18, 19, 20, 26, 28, 34, 35, 36], ReversiEvaluator::frontier, 1.0),
+        // Each player can only reverse the single peg of the other
+        balanced_mobility: (&[27], &[28], ReversiEvaluator::mobility, 0.0),
+        blocked_mobility: (&[0, 1], &[2], ReversiEvaluator::mobility, 1.0),
+        corner_position: (&[0], &[9], ReversiEvaluator::positional, (1.5f32 / 2.0).tanh()),
+    }
+}
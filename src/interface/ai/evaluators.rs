@@ -0,0 +1,17 @@
+use crate::interface::rulesets;
+
+/// Heuristic value of an ongoing state, from the point of view of its current player, within
+/// [-1, 1].
+pub trait Evaluator<RuleSet: rulesets::RuleSetTrait> {
+    fn evaluate(&self, state: &RuleSet::State) -> f32;
+}
+
+impl<RuleSet, Function> Evaluator<RuleSet> for Function
+where
+    RuleSet: rulesets::RuleSetTrait,
+    Function: Fn(&RuleSet::State) -> f32,
+{
+    fn evaluate(&self, state: &RuleSet::State) -> f32 {
+        self(state)
+    }
+}
@@ -1,6 +1,6 @@
 use crate::interface::rulesets;
 
-#[derive(Debug)]
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct GameLog<RuleSet: rulesets::RuleSetTrait> {
     pub history: Vec<(RuleSet::State, RuleSet::Ply)>,
     pub status: rulesets::Status,
@@ -1,4 +1,5 @@
 mod agents;
+mod evaluators;
 mod game_log;
 mod ply_considerations;
 mod policies;
@@ -7,6 +8,7 @@ mod qvalue;
 
 pub use agents::Agent;
 pub use agents::Learner;
+pub use evaluators::Evaluator;
 pub use game_log::GameLog;
 pub use ply_considerations::PlyConsideration;
 pub use policies::Policy;
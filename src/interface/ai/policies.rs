@@ -14,6 +14,14 @@ pub trait Policy<RuleSet: rulesets::RuleSetTrait> {
         &mut self,
         state: &RuleSet::State,
     ) -> Result<Prediction<RuleSet>, Box<dyn error::Error>>;
+
+    /// Predicts several states at once, which networks can do in a single run.
+    fn predict_batch(
+        &mut self,
+        states: &[RuleSet::State],
+    ) -> Result<Vec<Prediction<RuleSet>>, Box<dyn error::Error>> {
+        states.iter().map(|state| self.predict(state)).collect()
+    }
 }
 
 pub trait Teachable<RuleSet: rulesets::RuleSetTrait>: Policy<RuleSet> {
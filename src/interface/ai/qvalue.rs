@@ -3,4 +3,12 @@ use std::error;
 
 pub trait QValue<RuleSet: rulesets::RuleSetTrait> {
     fn evaluate(&mut self, state: &RuleSet::State) -> Result<f32, Box<dyn error::Error>>;
+
+    /// Evaluates several states at once, which networks can do in a single run.
+    fn evaluate_batch(
+        &mut self,
+        states: &[RuleSet::State],
+    ) -> Result<Vec<f32>, Box<dyn error::Error>> {
+        states.iter().map(|state| self.evaluate(state)).collect()
+    }
 }
@@ -1,3 +1,6 @@
+use std::error;
+use std::fmt;
+
 mod ply;
 mod ply_iterator;
 mod ruleset;
@@ -23,4 +26,12 @@ pub struct PlayError {
     pub field: &'static str,
 }
 
+impl fmt::Display for PlayError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} ({})", self.message, self.field)
+    }
+}
+
+impl error::Error for PlayError {}
+
 pub type Player = u8;
@@ -1,8 +1,10 @@
 pub mod agents;
+pub mod evaluators;
 pub mod interface;
 pub mod playground;
 pub mod policies;
+pub mod records;
 pub mod rulesets;
 mod tests;
-mod tools;
+pub mod tools;
 mod utils;
@@ -0,0 +1,65 @@
+use super::solver;
+use crate::interface::ai;
+use crate::rulesets::reversi;
+use std::error;
+
+/// Plays perfectly once few cells are left empty, and lets another agent play until then.
+pub struct EndgameAgent<Variant, Fallback>
+where
+    Variant: reversi::BaseVariant,
+    Fallback: ai::Agent<reversi::Reversi<Variant>>,
+{
+    solver: solver::EndgameSolver<Variant>,
+    empties: u32,
+    fallback: Fallback,
+}
+
+impl<Variant, Fallback> EndgameAgent<Variant, Fallback>
+where
+    Variant: reversi::BaseVariant,
+    Fallback: ai::Agent<reversi::Reversi<Variant>>,
+{
+    /// Creates an agent solving the states with at most the given number of empty cells.
+    pub fn new(empties: u32, fallback: Fallback) -> EndgameAgent<Variant, Fallback> {
+        EndgameAgent {
+            solver: solver::EndgameSolver::new(),
+            empties,
+            fallback,
+        }
+    }
+}
+
+impl<Variant, Fallback> ai::Agent<reversi::Reversi<Variant>> for EndgameAgent<Variant, Fallback>
+where
+    Variant: reversi::BaseVariant,
+    Fallback: ai::Agent<reversi::Reversi<Variant>>,
+{
+    fn play(
+        &mut self,
+        state: &reversi::State<Variant>,
+    ) -> Result<reversi::Ply<Variant>, Box<dyn error::Error>> {
+        if self.solver.empty_count(state) <= self.empties {
+            return Ok(self.solver.solve(state)?.ply);
+        }
+        self.fallback.play(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents;
+    use crate::interface::rulesets::RuleSetTrait;
+
+    #[test]
+    fn test_switch() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = reversi::Reversi::<reversi::Micro>::new();
+        let state = ruleset.initial_state();
+        let mut agent = EndgameAgent::new(12, agents::Random::new(&ruleset));
+        let expected = solver::EndgameSolver::new().solve(&state)?.ply;
+        assert_eq!(ai::Agent::play(&mut agent, &state)?, expected);
+        let mut agent = EndgameAgent::new(11, agents::Random::new(&ruleset));
+        assert!(ai::Agent::play(&mut agent, &state).is_ok());
+        Ok(())
+    }
+}
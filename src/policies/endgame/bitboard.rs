@@ -0,0 +1,232 @@
+use crate::rulesets::reversi;
+
+/// Shift of the pegs one cell away in a direction, along with the mask of the cells they may
+/// land on without wrapping around the grid.
+#[derive(Clone, Copy)]
+struct Direction {
+    amount: u32,
+    forward: bool,
+    mask: u64,
+}
+
+impl Direction {
+    fn shift(&self, bits: u64) -> u64 {
+        if self.forward {
+            (bits << self.amount) & self.mask
+        } else {
+            (bits >> self.amount) & self.mask
+        }
+    }
+}
+
+/// Reversi pegs as 64-bit boards, from the point of view of the player to move, for grids of up
+/// to 64 cells.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Bitboard {
+    pub player: u64,
+    pub opponent: u64,
+}
+
+impl Bitboard {
+    pub fn empty_cells(&self, geometry: &Geometry) -> u64 {
+        geometry.full & !(self.player | self.opponent)
+    }
+
+    /// Returns the board after a placement on the cell, reversing the given pegs.
+    pub fn play(&self, cell: u32, flips: u64) -> Bitboard {
+        Bitboard {
+            player: self.opponent & !flips,
+            opponent: self.player | flips | (1 << cell),
+        }
+    }
+
+    pub fn pass(&self) -> Bitboard {
+        Bitboard {
+            player: self.opponent,
+            opponent: self.player,
+        }
+    }
+
+    /// Returns the difference between the peg counts of the player and their opponent.
+    pub fn differential(&self) -> i32 {
+        self.player.count_ones() as i32 - self.opponent.count_ones() as i32
+    }
+}
+
+/// Masks of a grid size, used to move and reverse pegs on bitboards.
+pub struct Geometry {
+    pub full: u64,
+    /// Masks of the four quarters of the grid, used for parity ordering
+    pub quadrants: [u64; 4],
+    directions: [Direction; 8],
+    max_run: usize,
+}
+
+impl Geometry {
+    pub fn new(size: usize) -> Geometry {
+        assert!(size * size <= 64, "bitboards hold up to 64 cells");
+        let full = if size * size == 64 {
+            u64::MAX
+        } else {
+            (1 << (size * size)) - 1
+        };
+        let column =
+            |index: usize| (0..size).fold(0, |mask, row| mask | 1 << (row * size + index)) & full;
+        let no_first_column = full & !column(0);
+        let no_last_column = full & !column(size - 1);
+        let size = size as u32;
+        let direction = |amount, forward, mask| Direction {
+            amount,
+            forward,
+            mask,
+        };
+        let directions = [
+            direction(1, true, no_first_column),
+            direction(1, false, no_last_column),
+            direction(size, true, full),
+            direction(size, false, full),
+            direction(size + 1, true, no_first_column),
+            direction(size + 1, false, no_last_column),
+            direction(size - 1, true, no_last_column),
+            direction(size - 1, false, no_first_column),
+        ];
+        let half = size / 2;
+        let mut quadrants = [0; 4];
+        for row in 0..size {
+            for column in 0..size {
+                let quadrant = (row >= half) as usize * 2 + (column >= half) as usize;
+                quadrants[quadrant] |= 1 << (row * size + column);
+            }
+        }
+        Geometry {
+            full,
+            quadrants,
+            directions,
+            max_run: size as usize - 2,
+        }
+    }
+
+    /// Returns the cells where the player to move can place a peg.
+    pub fn moves(&self, board: &Bitboard) -> u64 {
+        let empty = board.empty_cells(self);
+        let mut result = 0;
+        for direction in &self.directions {
+            let mut run = direction.shift(board.player) & board.opponent;
+            for _ in 1..self.max_run {
+                run |= direction.shift(run) & board.opponent;
+            }
+            result |= direction.shift(run) & empty;
+        }
+        result
+    }
+
+    /// Returns the opponent pegs reversed by a placement on the given cell.
+    pub fn flips(&self, board: &Bitboard, cell: u32) -> u64 {
+        let mut result = 0;
+        for direction in &self.directions {
+            let mut run = 0;
+            let mut current = direction.shift(1 << cell);
+            while current & board.opponent != 0 {
+                run |= current;
+                current = direction.shift(current);
+            }
+            if current & board.player != 0 {
+                result |= run;
+            }
+        }
+        result
+    }
+}
+
+/// Converts a state to bitboards, from the point of view of its current player.
+pub fn to_bitboard<Variant: reversi::BaseVariant>(state: &reversi::State<Variant>) -> Bitboard {
+    let player = state.current_player as usize;
+    let to_bits = |grid: &reversi::State<Variant>, player: usize| {
+        (0..Variant::CELL_COUNT)
+            .filter(|index| grid.grids[player].isset(*index))
+            .fold(0, |bits, index| bits | 1 << index)
+    };
+    Bitboard {
+        player: to_bits(state, player),
+        opponent: to_bits(state, 1 - player),
+    }
+}
+
+/// Converts the cell of a placement, if any, to a ply.
+pub fn to_ply<Variant: reversi::BaseVariant>(cell: Option<u32>) -> reversi::Ply<Variant> {
+    match cell {
+        Some(cell) => reversi::Ply::Place(cell as usize),
+        None => reversi::Ply::Pass,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::rulesets::Deterministic;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::tools::plies;
+
+    /// Plays a game, cycling through the legal plies, checking the placements and
+    /// reversals of the bitboards against the ruleset on the way.
+    fn check_game<Variant: reversi::BaseVariant>() {
+        let ruleset = reversi::Reversi::<Variant>::new();
+        let geometry = Geometry::new(Variant::GRID_SIZE);
+        let mut state = ruleset.initial_state();
+        let mut turn = 0;
+        loop {
+            let board = to_bitboard(&state);
+            let legal_plies = plies::BasicIterator::new(&ruleset, &state).collect::<Vec<_>>();
+            let moves = geometry.moves(&board);
+            let expected_moves = legal_plies
+                .iter()
+                .filter_map(|ply| match ply {
+                    reversi::Ply::Place(index) => Some(1 << index),
+                    _ => None,
+                })
+                .fold(0, |bits, bit| bits | bit);
+            assert_eq!(moves, expected_moves);
+            let ply = match legal_plies.get(turn % legal_plies.len().max(1)) {
+                Some(ply) => *ply,
+                None => break,
+            };
+            let next_state = ruleset.play(&state, &ply).unwrap();
+            let next_board = match ply {
+                reversi::Ply::Place(index) => {
+                    board.play(index as u32, geometry.flips(&board, index as u32))
+                }
+                _ => board.pass(),
+            };
+            assert_eq!(next_board, to_bitboard(&next_state));
+            state = next_state;
+            turn += 1;
+        }
+    }
+
+    #[test]
+    fn test_micro_game() {
+        check_game::<reversi::Micro>();
+    }
+
+    #[test]
+    fn test_mini_game() {
+        check_game::<reversi::Mini>();
+    }
+
+    #[test]
+    fn test_classic_game() {
+        check_game::<reversi::Classic>();
+    }
+
+    #[test]
+    fn test_corner_flips() {
+        let geometry = Geometry::new(8);
+        // Pegs on the last column must not wrap around to the first one
+        let board = Bitboard {
+            player: 1 << 16,
+            opponent: 1 << 15 | 1 << 8,
+        };
+        assert_eq!(geometry.flips(&board, 0), 1 << 8);
+        assert_eq!(geometry.moves(&board) & (1 << 14), 0);
+    }
+}
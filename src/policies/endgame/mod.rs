@@ -0,0 +1,7 @@
+mod agent;
+mod bitboard;
+mod solver;
+
+pub use agent::EndgameAgent;
+pub use solver::EndgameSolver;
+pub use solver::Solution;
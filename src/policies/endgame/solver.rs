@@ -0,0 +1,296 @@
+use super::bitboard;
+use crate::interface::ai;
+use crate::rulesets::reversi;
+use std::error;
+use std::marker;
+
+/// Number of empty cells from which plies are sorted by the mobility they leave to the opponent
+const FASTEST_FIRST_EMPTIES: u32 = 7;
+/// Bound above any disc differential
+const INFINITY: i32 = 65;
+
+type PlyScores<Variant> = Vec<(reversi::Ply<Variant>, i32)>;
+/// Boards following a placement on a cell, `None` standing for a pass
+type Children = Vec<(Option<u32>, bitboard::Bitboard)>;
+
+/// Result of an endgame search.
+#[derive(Debug)]
+pub struct Solution<Variant: reversi::BaseVariant> {
+    /// Final difference between the peg counts of the current player and their opponent
+    pub score: i32,
+    pub ply: reversi::Ply<Variant>,
+    pub nodes: u64,
+}
+
+/// Exact endgame solver for Reversi, finding the final disc differential under perfect play.
+///
+/// It runs an alpha-beta search on 64-bit boards. Close to the end of the game, plies in regions
+/// with an odd number of empty cells are tried first, as their last placement is likely to be
+/// left to the current player. Earlier, plies leaving the fewest placements to the opponent are
+/// tried first, which cuts the tree fastest.
+pub struct EndgameSolver<Variant: reversi::BaseVariant> {
+    geometry: bitboard::Geometry,
+    nodes: u64,
+    variant: marker::PhantomData<Variant>,
+}
+
+impl<Variant: reversi::BaseVariant> EndgameSolver<Variant> {
+    pub fn new() -> EndgameSolver<Variant> {
+        EndgameSolver {
+            geometry: bitboard::Geometry::new(Variant::GRID_SIZE),
+            nodes: 0,
+            variant: marker::PhantomData,
+        }
+    }
+
+    pub fn empty_count(&self, state: &reversi::State<Variant>) -> u32 {
+        let board = bitboard::to_bitboard(state);
+        board.empty_cells(&self.geometry).count_ones()
+    }
+
+    /// Finds the best ply of the state, along with its exact score.
+    pub fn solve(
+        &mut self,
+        state: &reversi::State<Variant>,
+    ) -> Result<Solution<Variant>, Box<dyn error::Error>> {
+        self.nodes = 0;
+        let board = bitboard::to_bitboard(state);
+        let mut best = None;
+        let mut alpha = -INFINITY;
+        for (cell, child) in self.children(&board)? {
+            let score = -self.negamax(&child, -INFINITY, -alpha);
+            if score > alpha {
+                alpha = score;
+                best = Some((score, cell));
+            }
+        }
+        let (score, cell) = best.unwrap();
+        Ok(Solution {
+            score,
+            ply: bitboard::to_ply(cell),
+            nodes: self.nodes,
+        })
+    }
+
+    /// Returns the exact score of each ply, from the point of view of the current player.
+    ///
+    /// Each ply is searched with a full window, which makes it slower than `solve`.
+    pub fn ply_scores(
+        &mut self,
+        state: &reversi::State<Variant>,
+    ) -> Result<PlyScores<Variant>, Box<dyn error::Error>> {
+        self.nodes = 0;
+        let board = bitboard::to_bitboard(state);
+        Ok(self
+            .children(&board)?
+            .into_iter()
+            .map(|(cell, child)| {
+                let score = -self.negamax(&child, -INFINITY, INFINITY);
+                (bitboard::to_ply(cell), score)
+            })
+            .collect())
+    }
+
+    /// Returns the boards following the root.
+    fn children(&self, board: &bitboard::Bitboard) -> Result<Children, Box<dyn error::Error>> {
+        if self.geometry.moves(board) != 0 {
+            return Ok(self
+                .ordered_moves(board)
+                .into_iter()
+                .map(|(cell, child)| (Some(cell), child))
+                .collect());
+        }
+        if self.geometry.moves(&board.pass()) == 0 {
+            return Err("no ply to play on a finished game".into());
+        }
+        Ok(vec![(None, board.pass())])
+    }
+
+    fn negamax(&mut self, board: &bitboard::Bitboard, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        if self.geometry.moves(board) == 0 {
+            let passed = board.pass();
+            if self.geometry.moves(&passed) == 0 {
+                return board.differential();
+            }
+            return -self.negamax(&passed, -beta, -alpha);
+        }
+        let mut best = -INFINITY;
+        for (_, child) in self.ordered_moves(board) {
+            let score = -self.negamax(&child, -beta, -alpha);
+            if score > best {
+                best = score;
+                if score > alpha {
+                    alpha = score;
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// Returns the placements of the board along with their resulting boards, in search order.
+    fn ordered_moves(&self, board: &bitboard::Bitboard) -> Vec<(u32, bitboard::Bitboard)> {
+        let empty = board.empty_cells(&self.geometry);
+        let odd_regions = self
+            .geometry
+            .quadrants
+            .iter()
+            .filter(|quadrant| (*quadrant & empty).count_ones() % 2 == 1)
+            .fold(0, |mask, quadrant| mask | quadrant);
+        let mut moves = self.geometry.moves(board);
+        let mut result = Vec::with_capacity(moves.count_ones() as usize);
+        while moves != 0 {
+            let cell = moves.trailing_zeros();
+            moves &= moves - 1;
+            let child = board.play(cell, self.geometry.flips(board, cell));
+            result.push((cell, child));
+        }
+        let even_region = |cell: u32| odd_regions & (1 << cell) == 0;
+        if empty.count_ones() >= FASTEST_FIRST_EMPTIES {
+            result.sort_by_cached_key(|(cell, child)| {
+                (self.geometry.moves(child).count_ones(), even_region(*cell))
+            });
+        } else {
+            result.sort_by_key(|(cell, _)| even_region(*cell));
+        }
+        result
+    }
+}
+
+impl<Variant: reversi::BaseVariant> Default for EndgameSolver<Variant> {
+    fn default() -> EndgameSolver<Variant> {
+        Self::new()
+    }
+}
+
+impl<Variant: reversi::BaseVariant> ai::Policy<reversi::Reversi<Variant>>
+    for EndgameSolver<Variant>
+{
+    /// Gives the exact differential, scaled to [-1, 1], as value and spreads the probabilities
+    /// evenly over the optimal plies, so that predictions can be used as training labels.
+    fn predict(
+        &mut self,
+        state: &reversi::State<Variant>,
+    ) -> Result<ai::Prediction<reversi::Reversi<Variant>>, Box<dyn error::Error>> {
+        let ply_scores = self.ply_scores(state)?;
+        let best = ply_scores.iter().map(|(_, score)| *score).max().unwrap();
+        let optimal_plies = ply_scores
+            .into_iter()
+            .filter(|(_, score)| *score == best)
+            .map(|(ply, _)| ply)
+            .collect::<Vec<_>>();
+        let probability = 1.0 / optimal_plies.len() as f32;
+        Ok(ai::Prediction {
+            value: best as f32 / Variant::CELL_COUNT as f32,
+            probabilities: optimal_plies
+                .into_iter()
+                .map(|ply| (ply, probability))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::rulesets;
+    use crate::interface::rulesets::Deterministic;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::tools::plies;
+
+    /// Plays a game, cycling through the legal plies, until the given number of cells is left.
+    fn late_state<Variant: reversi::BaseVariant>(
+        ruleset: &reversi::Reversi<Variant>,
+        empties: usize,
+    ) -> reversi::State<Variant> {
+        let mut state = ruleset.initial_state();
+        let mut turn = 0;
+        while Variant::CELL_COUNT
+            - state.grids[0].count_ones() as usize
+            - state.grids[1].count_ones() as usize
+            > empties
+        {
+            let legal_plies = plies::BasicIterator::new(ruleset, &state).collect::<Vec<_>>();
+            state = ruleset
+                .play(&state, &legal_plies[turn % legal_plies.len()])
+                .unwrap();
+            turn += 1;
+        }
+        state
+    }
+
+    /// Plain negamax on the ruleset, as reference.
+    fn reference_score<Variant: reversi::BaseVariant>(
+        ruleset: &reversi::Reversi<Variant>,
+        state: &reversi::State<Variant>,
+    ) -> i32 {
+        if ruleset.status(state) != rulesets::Status::Ongoing {
+            let player = state.current_player as usize;
+            return state.grids[player].count_ones() as i32
+                - state.grids[1 - player].count_ones() as i32;
+        }
+        plies::BasicIterator::new(ruleset, state)
+            .map(|ply| -reference_score(ruleset, &ruleset.play(state, &ply).unwrap()))
+            .max()
+            .unwrap()
+    }
+
+    macro_rules! score_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<(), Box<dyn error::Error>> {
+                    let (ruleset, empties) = $value;
+                    let state = late_state(&ruleset, empties);
+                    let mut solver = EndgameSolver::new();
+                    let expected = reference_score(&ruleset, &state);
+                    let solution = solver.solve(&state)?;
+                    assert_eq!(solution.score, expected);
+                    let next_state = ruleset.play(&state, &solution.ply)?;
+                    assert_eq!(reference_score(&ruleset, &next_state), -expected);
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    score_tests! {
+        micro: (reversi::Reversi::<reversi::Micro>::new(), 8),
+        mini: (reversi::Reversi::<reversi::Mini>::new(), 6),
+        classic: (reversi::Reversi::<reversi::Classic>::new(), 6),
+    }
+
+    #[test]
+    fn test_micro_initial_state() -> Result<(), Box<dyn error::Error>> {
+        // Micro is a loss for the first player
+        let ruleset = reversi::Reversi::<reversi::Micro>::new();
+        let mut solver = EndgameSolver::new();
+        assert!(solver.solve(&ruleset.initial_state())?.score < 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_prediction() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = reversi::Reversi::<reversi::Classic>::new();
+        let state = late_state(&ruleset, 10);
+        let mut solver = EndgameSolver::new();
+        let solution = solver.solve(&state)?;
+        let prediction = ai::Policy::predict(&mut solver, &state)?;
+        assert_eq!(prediction.value, solution.score as f32 / 64.0);
+        assert!(prediction
+            .probabilities
+            .iter()
+            .any(|(ply, _)| *ply == solution.ply));
+        Ok(())
+    }
+
+    #[test]
+    fn test_finished_game() {
+        let state = reversi::State::<reversi::Micro>::from_indices(&[0, 1, 2, 3], &[], 1);
+        let mut solver = EndgameSolver::new();
+        assert!(solver.solve(&state).is_err());
+    }
+}
@@ -1,27 +1,44 @@
 use super::analysis;
 use super::backpropagation;
+use super::budget;
 use super::edges;
 use super::expansion;
 use super::nodes;
+use super::rave;
+use super::reuse;
+use super::rollout_policy;
 use super::selection;
 use super::simulation;
+use super::stopping;
+use super::transpositions;
+use super::tree_policy;
+use super::virtual_loss;
 use crate::interface::ai;
 use crate::interface::rulesets;
 use crate::interface::rulesets::StateTrait;
+use crate::tools::symmetries;
 use petgraph::graph;
+use petgraph::visit::EdgeRef;
 use rand::rngs;
+use rand::SeedableRng;
+use std::error;
 use std::hash;
 
 pub struct MCTS<RuleSet>
 where
     RuleSet: rulesets::HasStatesWithSymmetries + rulesets::Deterministic + rulesets::TurnByTurn,
     RuleSet::Ply: Eq + Ord + hash::Hash,
-    RuleSet::State: Eq,
+    RuleSet::State: Eq + Ord + hash::Hash,
 {
     ruleset: RuleSet,
-    tree: graph::Graph<nodes::Node<RuleSet::State>, edges::Edge<RuleSet::Ply>>,
-    rng: rngs::ThreadRng,
+    tree: edges::Tree<RuleSet>,
+    rng: rngs::StdRng,
     root: Option<graph::NodeIndex<u32>>,
+    budget: budget::Budget,
+    tree_policy: Box<dyn tree_policy::TreePolicy<RuleSet::State>>,
+    amaf_update: Option<rave::AmafUpdate<RuleSet>>,
+    rollout_policy: Box<dyn rollout_policy::RolloutPolicy<RuleSet>>,
+    transpositions: Option<transpositions::Transpositions<RuleSet>>,
     pub expansion_count: usize,
     pub simulation_count: usize,
 }
@@ -30,49 +47,191 @@ impl<RuleSet> MCTS<RuleSet>
 where
     RuleSet: rulesets::HasStatesWithSymmetries + rulesets::Deterministic + rulesets::TurnByTurn,
     RuleSet::Ply: Eq + Ord + hash::Hash,
-    RuleSet::State: Eq,
+    RuleSet::State: Eq + Ord + hash::Hash,
 {
     pub fn new(ruleset: RuleSet) -> MCTS<RuleSet> {
         MCTS {
             ruleset,
             tree: graph::Graph::new(),
-            rng: rand::thread_rng(),
+            rng: rngs::StdRng::from_entropy(),
             root: None,
+            budget: budget::Budget::default(),
+            tree_policy: Box::new(tree_policy::UCB1::default()),
+            amaf_update: None,
+            rollout_policy: Box::new(rollout_policy::Uniform::new()),
+            transpositions: None,
             expansion_count: 0,
             simulation_count: 0,
         }
     }
 
+    pub fn set_budget(&mut self, budget: budget::Budget) {
+        self.budget = budget;
+    }
+
+    /// Seeds the generator drawing the simulations, which is otherwise seeded from the system.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = rngs::StdRng::seed_from_u64(seed);
+    }
+
+    /// Sets the policy selecting the children to descend into, UCB1 by default.
+    pub fn set_tree_policy<Policy>(&mut self, policy: Policy)
+    where
+        Policy: tree_policy::TreePolicy<RuleSet::State> + 'static,
+    {
+        self.tree_policy = Box::new(policy);
+        self.amaf_update = None;
+    }
+
+    /// Sets a policy selecting the children to descend into from their all-moves-as-first
+    /// statistics, such as `Rave`, which the search then gathers.
+    pub fn set_amaf_tree_policy<Policy>(&mut self, policy: Policy)
+    where
+        RuleSet: rulesets::EncodableState,
+        Policy: tree_policy::TreePolicy<RuleSet::State> + 'static,
+    {
+        self.tree_policy = Box::new(policy);
+        self.amaf_update = Some(rave::update_amaf::<RuleSet>);
+    }
+
+    /// Sets the policy playing the simulations, uniformly random plies by default.
+    pub fn set_rollout_policy<Policy>(&mut self, policy: Policy)
+    where
+        Policy: rollout_policy::RolloutPolicy<RuleSet> + 'static,
+    {
+        self.rollout_policy = Box::new(policy);
+    }
+
+    /// Sets whether plies reaching a state already in the tree lead to its node, merging
+    /// transpositions so that their statistics are shared, which is disabled by default.
+    pub fn set_transpositions(&mut self, enabled: bool) {
+        self.transpositions = if enabled {
+            let mut transpositions = transpositions::Transpositions::new();
+            transpositions.rebuild(&self.ruleset, &self.tree);
+            Some(transpositions)
+        } else {
+            None
+        };
+    }
+
+    /// Searches the state until the budget is spent or, with early stopping, until the decision
+    /// is settled.
+    pub fn search(&mut self, state: &RuleSet::State) -> Result<(), Box<dyn error::Error>> {
+        self.set_state(state.clone());
+        if self.ruleset.status(state) != rulesets::Status::Ongoing {
+            return Ok(());
+        }
+        let root = self.root.unwrap();
+        let mut tracker = budget::Tracker::new(self.budget);
+        while !stopping::should_stop(&self.tree, root, &tracker) {
+            self.iterate()?;
+            tracker.add_iterations(1);
+        }
+        Ok(())
+    }
+
+    /// Returns the plies of the root along with the visits of their nodes.
+    ///
+    /// Plies equivalent to another one by symmetry are not expanded, the visits of the expanded
+    /// ply being spread evenly over its equivalent plies.
+    pub fn root_visits(&self) -> Vec<(RuleSet::Ply, f32)> {
+        let root = match self.root {
+            Some(root) => root,
+            None => return Vec::new(),
+        };
+        let root_weight = self.tree.node_weight(root).unwrap();
+        let invariant_symmetries =
+            symmetries::invariant_symmetries(&self.ruleset, &root_weight.state);
+        self.tree
+            .edges(root)
+            .flat_map(|edge| {
+                let node_weight = self.tree.node_weight(edge.target()).unwrap();
+                let plies = symmetries::equivalent_plies(
+                    &self.ruleset,
+                    &invariant_symmetries,
+                    &edge.weight().ply,
+                );
+                let visits = node_weight.visits / plies.len() as f32;
+                plies.into_iter().map(move |ply| (ply, visits))
+            })
+            .collect()
+    }
+
+    /// Sets the state to search from, reusing the subtree of the current root when the state
+    /// follows it by a ply of each player at most.
     pub fn set_state(&mut self, state: RuleSet::State) {
-        let status = self.ruleset.status(&state);
-        let current_player = self.ruleset.current_player(&state);
-        let index = self
-            .tree
-            .add_node(nodes::Node::new(state, status, current_player));
-        self.root = Some(index);
+        if let Some(root) = self.root {
+            self.root = reuse::advance_root(
+                &mut self.tree,
+                &self.ruleset,
+                root,
+                &state,
+                reuse::MAX_ADVANCE_DEPTH,
+            );
+            if self.root.is_none() {
+                self.tree.clear();
+            }
+        }
+        if self.root.is_none() {
+            let status = self.ruleset.status(&state);
+            let current_player = self.ruleset.current_player(&state);
+            let index = self
+                .tree
+                .add_node(nodes::Node::new(state, status, current_player));
+            self.root = Some(index);
+        }
+        // Reusing the tree moves its nodes around
+        if let Some(transpositions) = self.transpositions.as_mut() {
+            transpositions.rebuild(&self.ruleset, &self.tree);
+        }
     }
 
-    pub fn iterate(&mut self) {
+    pub fn iterate(&mut self) -> Result<(), Box<dyn error::Error>> {
         let node = match self.root {
             Some(node) => node,
             None => {
-                return;
+                return Ok(());
             }
         };
-        let mut selected = selection::select(&self.tree, node);
-        let (mut status, expanded) =
-            expansion::expand::<RuleSet>(&mut self.tree, &self.ruleset, selected);
+        // Iterations run one at a time, none being in progress during the selection
+        let mut path = selection::select(
+            &self.tree,
+            node,
+            self.tree_policy.as_ref(),
+            virtual_loss::VirtualLoss::None,
+        );
+        let selected = *path.last().unwrap();
+        let (mut status, expanded) = expansion::expand::<RuleSet>(
+            &mut self.tree,
+            &self.ruleset,
+            selected,
+            self.transpositions.as_mut(),
+        );
         if expanded {
             self.expansion_count += 1;
         }
+        let mut playout = Vec::new();
         if let rulesets::Status::Ongoing = status {
             self.simulation_count += 1;
             let (to_simulate, state) =
                 simulation::fetch_random_child::<RuleSet>(&self.tree, selected, &mut self.rng);
-            status = simulation::simulate::<RuleSet>(&self.ruleset, &state, &mut self.rng);
-            selected = to_simulate;
+            let (simulated_status, simulated_playout) = simulation::simulate::<RuleSet>(
+                &self.ruleset,
+                &state,
+                self.rollout_policy.as_mut(),
+                &mut self.rng,
+            )?;
+            status = simulated_status;
+            playout = simulated_playout;
+            if to_simulate != selected {
+                path.push(to_simulate);
+            }
+        }
+        backpropagation::backpropagate(&mut self.tree, &path, true, Some(status));
+        if let Some(update_amaf) = self.amaf_update {
+            update_amaf(&mut self.tree, &self.ruleset, &path, &playout, status);
         }
-        backpropagation::backpropagate(&mut self.tree, selected, true, Some(status));
+        Ok(())
     }
 
     pub fn play_scores(&self) -> Option<Vec<ai::PlyConsideration<RuleSet::Ply>>> {
@@ -82,7 +241,7 @@ where
                 return None;
             }
         };
-        Some(analysis::play_scores::<RuleSet>(&self.tree, parent))
+        Some(analysis::play_scores(&self.ruleset, &self.tree, parent))
     }
 
     pub fn walk_best(&mut self) {
@@ -107,18 +266,439 @@ where
     }
 }
 
+impl<RuleSet> ai::Agent<RuleSet> for MCTS<RuleSet>
+where
+    RuleSet: rulesets::HasStatesWithSymmetries + rulesets::Deterministic + rulesets::TurnByTurn,
+    RuleSet::Ply: Eq + Ord + hash::Hash,
+    RuleSet::State: Eq + Ord + hash::Hash,
+{
+    /// Plays a proven win if any, and otherwise the most visited ply not proven to lose.
+    fn play(&mut self, state: &RuleSet::State) -> Result<RuleSet::Ply, Box<dyn error::Error>> {
+        self.search(state)?;
+        let root = self.root.unwrap();
+        let player = self.tree.node_weight(root).unwrap().current_player;
+        let best = self
+            .tree
+            .edges(root)
+            .map(|edge| {
+                let node_weight = self.tree.node_weight(edge.target()).unwrap();
+                let rank = match node_weight.game_status().player_pov(player) {
+                    rulesets::PlayerStatus::Win => 2,
+                    rulesets::PlayerStatus::Loss => 0,
+                    _ => 1,
+                };
+                (edge.weight().ply, rank, node_weight.visits)
+            })
+            .max_by(|(_, rank_a, visits_a), (_, rank_b, visits_b)| {
+                rank_a
+                    .cmp(rank_b)
+                    .then(visits_a.partial_cmp(visits_b).unwrap())
+            });
+        match best {
+            Some((ply, _, _)) => Ok(ply),
+            None => Err("no ply to play on a finished game".into()),
+        }
+    }
+}
+
+impl<RuleSet> ai::Policy<RuleSet> for MCTS<RuleSet>
+where
+    RuleSet: rulesets::HasStatesWithSymmetries + rulesets::Deterministic + rulesets::TurnByTurn,
+    RuleSet::Ply: Eq + Ord + hash::Hash,
+    RuleSet::State: Eq + Ord + hash::Hash,
+{
+    /// Spreads the probabilities according to the visits of the plies, the value being the
+    /// expected outcome of the root for its current player, from -1 to 1.
+    fn predict(
+        &mut self,
+        state: &RuleSet::State,
+    ) -> Result<ai::Prediction<RuleSet>, Box<dyn error::Error>> {
+        self.search(state)?;
+        let root_weight = self.tree.node_weight(self.root.unwrap()).unwrap();
+        // The score of a node is the expected outcome of the player who moved into it
+        let value = 1.0 - 2.0 * root_weight.score();
+        let root_visits = self.root_visits();
+        let total_visits = root_visits.iter().map(|(_, visits)| visits).sum::<f32>();
+        Ok(ai::Prediction {
+            value,
+            probabilities: root_visits
+                .into_iter()
+                .map(|(ply, visits)| (ply, visits / total_visits))
+                .collect(),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::super::time_manager;
     use super::*;
+    use crate::interface::rulesets::Deterministic;
     use crate::interface::rulesets::RuleSetTrait;
     use crate::rulesets::connectn;
+    use std::time;
 
     #[test]
     fn test_simulate() {
         let ruleset = connectn::TicTacToe::new();
         let state = ruleset.initial_state();
         let mut algo = MCTS::new(ruleset);
+        algo.set_seed(0);
         algo.set_state(state);
-        algo.iterate();
+        algo.iterate().unwrap();
+    }
+
+    macro_rules! play_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<(), Box<dyn error::Error>> {
+                    let (p1_indices, p2_indices, current_player, expected_index) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let state = connectn::TicTacToeState::from_indices(&p1_indices, &p2_indices, current_player);
+                    let mut algo = MCTS::new(ruleset);
+                    algo.set_seed(0);
+                    let ply = ai::Agent::play(&mut algo, &state)?;
+                    assert_eq!(ply, connectn::Ply::new(expected_index));
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    play_tests! {
+        immediate_win: ([4, 1, 0], [5, 7, 8], 0, 2),
+        forced_block: ([4, 1], [0, 5], 1, 7),
+    }
+
+    macro_rules! tree_policy_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<(), Box<dyn error::Error>> {
+                    let ruleset = connectn::TicTacToe::new();
+                    let state = connectn::TicTacToeState::from_indices(&[4, 1], &[0, 5], 1);
+                    let mut algo = MCTS::new(ruleset);
+                    algo.set_seed(0);
+                    algo.set_tree_policy($value);
+                    let ply = ai::Agent::play(&mut algo, &state)?;
+                    assert_eq!(ply, connectn::Ply::new(7));
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    tree_policy_tests! {
+        ucb1_tuned: tree_policy::UCB1Tuned::new(),
+        ucbv: tree_policy::UCBV::default(),
+        progressive_bias: tree_policy::ProgressiveBias::<connectn::TicTacToe, _, _>::new(
+            tree_policy::UCB1::new(1.0),
+            |_: &connectn::TicTacToeState| 0.0,
+            1.0,
+        ),
+    }
+
+    macro_rules! rollout_policy_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<(), Box<dyn error::Error>> {
+                    let ruleset = connectn::TicTacToe::new();
+                    let state = connectn::TicTacToeState::from_indices(&[4, 1], &[0, 5], 1);
+                    let mut algo = MCTS::new(ruleset);
+                    algo.set_seed(0);
+                    algo.set_rollout_policy($value);
+                    let ply = ai::Agent::play(&mut algo, &state)?;
+                    assert_eq!(ply, connectn::Ply::new(7));
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    rollout_policy_tests! {
+        decisive: rollout_policy::Decisive::new(),
+        truncated: rollout_policy::Truncated::new(
+            rollout_policy::Uniform::new(),
+            |_: &connectn::TicTacToeState| 0.0,
+            2,
+        ),
+    }
+
+    #[test]
+    fn test_predict() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let state = connectn::TicTacToeState::from_indices(&[4, 1, 0], &[5, 7], 1);
+        let mut algo = MCTS::new(ruleset);
+        algo.set_seed(0);
+        algo.set_budget(budget::Budget::iterations(200));
+        let prediction = ai::Policy::predict(&mut algo, &state)?;
+        // Two winning threats cannot be both blocked
+        assert!(prediction.value < 0.0);
+        let total = prediction
+            .probabilities
+            .iter()
+            .map(|(_, probability)| probability)
+            .sum::<f32>();
+        assert!((total - 1.0).abs() < 1e-5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_symmetric_predictions() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let state = ruleset.initial_state();
+        let mut algo = MCTS::new(ruleset);
+        algo.set_seed(0);
+        algo.set_budget(budget::Budget::iterations(200));
+        let prediction = ai::Policy::predict(&mut algo, &state)?;
+        // Every legal ply is predicted, equivalent ones sharing the visits of the expanded one
+        let mut probabilities = prediction.probabilities;
+        probabilities.sort_by_key(|(ply, _)| *ply);
+        let plies = probabilities
+            .iter()
+            .map(|(ply, _)| *ply)
+            .collect::<Vec<_>>();
+        assert_eq!(plies, (0..9).map(connectn::Ply::new).collect::<Vec<_>>());
+        for corner in &[2, 6, 8] {
+            assert_eq!(probabilities[*corner].1, probabilities[0].1);
+        }
+        for edge in &[3, 5, 7] {
+            assert_eq!(probabilities[*edge].1, probabilities[1].1);
+        }
+        let total = probabilities
+            .iter()
+            .map(|(_, probability)| probability)
+            .sum::<f32>();
+        assert!((total - 1.0).abs() < 1e-5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_time_budget() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let state = ruleset.initial_state();
+        let mut algo = MCTS::new(ruleset);
+        algo.set_seed(0);
+        algo.set_budget(budget::Budget::time(time::Duration::from_millis(50)));
+        let start = time::Instant::now();
+        ai::Agent::play(&mut algo, &state)?;
+        assert!(start.elapsed() < time::Duration::from_millis(500));
+        assert!(algo.root_visits().iter().all(|(_, visits)| *visits > 0.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_finished_game() {
+        let ruleset = connectn::TicTacToe::new();
+        let state = connectn::TicTacToeState::from_indices(&[4, 1, 0, 2], &[5, 7, 8], 1);
+        let mut algo = MCTS::new(ruleset);
+        algo.set_seed(0);
+        assert!(ai::Agent::play(&mut algo, &state).is_err());
+        let prediction = ai::Policy::predict(&mut algo, &state).unwrap();
+        assert_eq!(prediction.value, -1.0);
+        assert!(prediction.probabilities.is_empty());
+    }
+
+    #[test]
+    fn test_tree_reuse() {
+        let ruleset = connectn::TicTacToe::new();
+        let state = ruleset.initial_state();
+        let mut algo = MCTS::new(ruleset.clone());
+        algo.set_seed(0);
+        algo.set_budget(budget::Budget::iterations(200));
+        algo.search(&state).unwrap();
+        let node_count = algo.tree.node_count();
+        // The center has no equivalent ply, its node visits being its own
+        let (ply, visits) = algo
+            .root_visits()
+            .into_iter()
+            .find(|(ply, _)| *ply == connectn::Ply::new(4))
+            .unwrap();
+        let next_state = ruleset.play(&state, &ply).unwrap();
+        algo.set_state(next_state.clone());
+        let root_weight = algo.tree.node_weight(algo.root.unwrap()).unwrap();
+        assert_eq!(root_weight.state, next_state);
+        assert_eq!(root_weight.visits, visits);
+        assert!(algo.tree.node_count() < node_count);
+        // Unrelated states start a new tree
+        let other_state = connectn::TicTacToeState::from_indices(&[4, 1], &[0, 5], 1);
+        algo.set_state(other_state);
+        assert_eq!(algo.tree.node_count(), 1);
+    }
+
+    #[test]
+    fn test_symmetric_tree_reuse() {
+        let ruleset = connectn::TicTacToe::new();
+        let state = ruleset.initial_state();
+        let mut algo = MCTS::new(ruleset.clone());
+        algo.set_seed(0);
+        algo.set_budget(budget::Budget::iterations(200));
+        algo.search(&state).unwrap();
+        // Only one corner is expanded, the others reusing its subtree
+        let corner_visits = algo
+            .root_visits()
+            .into_iter()
+            .filter(|(ply, _)| [0, 2, 6, 8].contains(&ply.index))
+            .map(|(_, visits)| visits)
+            .sum::<f32>();
+        for corner in &[0, 2, 6, 8] {
+            let mut algo = MCTS::new(ruleset.clone());
+            algo.set_seed(0);
+            algo.set_budget(budget::Budget::iterations(200));
+            algo.search(&state).unwrap();
+            let next_state = ruleset.play(&state, &connectn::Ply::new(*corner)).unwrap();
+            algo.set_state(next_state.clone());
+            let root_weight = algo.tree.node_weight(algo.root.unwrap()).unwrap();
+            assert_eq!(root_weight.state, next_state);
+            assert_eq!(root_weight.visits, corner_visits);
+            for edge in algo.tree.raw_edges() {
+                let source = &algo.tree.node_weight(edge.source()).unwrap().state;
+                let target = &algo.tree.node_weight(edge.target()).unwrap().state;
+                assert_eq!(ruleset.play(source, &edge.weight.ply).unwrap(), *target);
+            }
+        }
+    }
+
+    #[test]
+    fn test_amaf_statistics() {
+        let ruleset = connectn::TicTacToe::new();
+        let state = ruleset.initial_state();
+        let mut algo = MCTS::new(ruleset);
+        algo.set_seed(0);
+        algo.set_budget(budget::Budget::iterations(100));
+        algo.search(&state).unwrap();
+        let amaf_visits = |algo: &MCTS<connectn::TicTacToe>| {
+            algo.tree
+                .raw_edges()
+                .iter()
+                .map(|edge| edge.weight.amaf_visits)
+                .sum::<f32>()
+        };
+        // Statistics are only gathered for the policies reading them
+        assert_eq!(amaf_visits(&algo), 0.0);
+        algo.set_amaf_tree_policy(tree_policy::Rave::<tree_policy::UCB1>::default());
+        algo.search(&state).unwrap();
+        assert!(amaf_visits(&algo) > 0.0);
+    }
+
+    #[test]
+    fn test_rave_policy() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let state = connectn::TicTacToeState::from_indices(&[4, 1], &[0, 5], 1);
+        let mut algo = MCTS::new(ruleset);
+        algo.set_seed(0);
+        algo.set_amaf_tree_policy(tree_policy::Rave::<tree_policy::UCB1>::default());
+        let ply = ai::Agent::play(&mut algo, &state)?;
+        assert_eq!(ply, connectn::Ply::new(7));
+        Ok(())
+    }
+
+    #[test]
+    fn test_proven_loss() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        // Two winning threats cannot be both blocked
+        let state = connectn::TicTacToeState::from_indices(&[4, 1, 0], &[5, 7], 1);
+        let mut algo = MCTS::new(ruleset);
+        algo.set_seed(0);
+        algo.set_budget(budget::Budget::iterations(1000));
+        let prediction = ai::Policy::predict(&mut algo, &state)?;
+        assert_eq!(prediction.value, -1.0);
+        let root_weight = algo.tree.node_weight(algo.root.unwrap()).unwrap();
+        assert_eq!(
+            root_weight.game_status(),
+            rulesets::Status::Win { player: 0 }
+        );
+        // The search stops as soon as the root is proven
+        assert!(root_weight.visits < 1000.0);
+        let play_scores = algo.play_scores().unwrap();
+        assert!(play_scores
+            .iter()
+            .all(|consideration| consideration.score == 0.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_transpositions() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let state = connectn::TicTacToeState::from_indices(&[4, 1], &[0, 5], 1);
+        let mut algo = MCTS::new(ruleset);
+        algo.set_seed(0);
+        algo.set_transpositions(true);
+        let ply = ai::Agent::play(&mut algo, &state)?;
+        assert_eq!(ply, connectn::Ply::new(7));
+        // Plies played in another order lead to the same node
+        assert!(algo.tree.node_indices().any(|node| algo
+            .tree
+            .neighbors_directed(node, petgraph::Direction::Incoming)
+            .nth(1)
+            .is_some()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_transposition_visits() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let mut algo = MCTS::new(ruleset.clone());
+        algo.set_seed(0);
+        algo.set_transpositions(true);
+        algo.set_state(ruleset.initial_state());
+        for _ in 0..300 {
+            algo.iterate()?;
+        }
+        let visits = analysis::node_visits::<connectn::TicTacToe>(&algo.tree, algo.root.unwrap());
+        // Iterations are counted once by every node they go through, whatever its parents
+        assert_eq!(visits[0].0, 300.0);
+        for (node_visits, edge_visits) in &visits[1..] {
+            assert_eq!(node_visits, edge_visits);
+        }
+        assert!(algo.tree.node_indices().any(|node| algo
+            .tree
+            .neighbors_directed(node, petgraph::Direction::Incoming)
+            .nth(1)
+            .is_some()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_early_stop() {
+        let ruleset = connectn::TicTacToe::new();
+        let state = connectn::TicTacToeState::from_indices(&[4, 1, 0], &[5, 7, 8], 0);
+        let mut algo = MCTS::new(ruleset);
+        algo.set_seed(0);
+        algo.set_budget(budget::Budget {
+            early_stop: true,
+            ..budget::Budget::iterations(1000)
+        });
+        algo.search(&state).unwrap();
+        // The winning ply is found as soon as the root is expanded
+        let visits = algo.root_visits();
+        assert!(visits.iter().map(|(_, visits)| visits).sum::<f32>() < 10.0);
+    }
+
+    macro_rules! exhausted_budget_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<(), Box<dyn error::Error>> {
+                    let ruleset = connectn::TicTacToe::new();
+                    let state = ruleset.initial_state();
+                    let mut algo = MCTS::new(ruleset.clone());
+                    algo.set_seed(0);
+                    algo.set_budget($value);
+                    // The root is expanded even though the budget is spent from the start
+                    let ply = ai::Agent::play(&mut algo, &state)?;
+                    assert!(ruleset.play(&state, &ply).is_ok());
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    exhausted_budget_tests! {
+        node_budget: budget::Budget::nodes(1),
+        flagged_clock: time_manager::TimeManager::new()
+            .budget(time::Duration::from_millis(20), time::Duration::from_millis(0)),
     }
 }
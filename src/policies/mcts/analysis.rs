@@ -1,28 +1,106 @@
 use super::edges;
-use super::nodes;
 use crate::interface::ai;
 use crate::interface::rulesets;
 
 use petgraph::graph;
+use petgraph::visit::EdgeRef;
+use std::collections::HashMap;
+use std::hash;
+use std::iter;
 
-pub fn play_scores<RuleSet: rulesets::TurnByTurn>(
-    tree: &graph::Graph<nodes::Node<RuleSet::State>, edges::Edge<RuleSet::Ply>>,
+pub fn play_scores<RuleSet: rulesets::HasStatesWithSymmetries + rulesets::TurnByTurn>(
+    ruleset: &RuleSet,
+    tree: &edges::Tree<RuleSet>,
     parent: graph::NodeIndex<u32>,
 ) -> Vec<ai::PlyConsideration<RuleSet::Ply>> {
+    weighted_play_scores(ruleset, tree, parent)
+        .into_iter()
+        .map(|(consideration, _)| consideration)
+        .collect()
+}
+
+/// Returns the play scores of the children of the node along with their visits, which weigh
+/// them once merged with the scores of other trees.
+pub fn weighted_play_scores<RuleSet: rulesets::HasStatesWithSymmetries + rulesets::TurnByTurn>(
+    ruleset: &RuleSet,
+    tree: &edges::Tree<RuleSet>,
+    parent: graph::NodeIndex<u32>,
+) -> Vec<(ai::PlyConsideration<RuleSet::Ply>, f32)> {
     let mut scores = tree
         .neighbors(parent)
         .map(|node_index| {
             let node_weight = tree.node_weight(node_index).unwrap();
             let edge = tree.find_edge(parent, node_index).unwrap();
             let edge_weight = tree.edge_weight(edge).unwrap();
-            let follow_up = best_play::<RuleSet>(tree, node_index);
-            ai::PlyConsideration {
+            let follow_up = best_play(ruleset, tree, edge);
+            let consideration = ai::PlyConsideration {
                 ply: edge_weight.ply,
                 score: node_weight.score(),
                 win_rate: node_weight.win_rate(),
                 draw_rate: node_weight.draw_rate(),
                 follow_up,
-            }
+            };
+            (consideration, node_weight.visits)
+        })
+        .collect::<Vec<_>>();
+    scores.sort_by(|(consideration_a, _), (consideration_b, _)| {
+        consideration_a
+            .score
+            .partial_cmp(&consideration_b.score)
+            .unwrap()
+            .reverse()
+    });
+    scores
+}
+
+/// Statistics of a ply gathered over several trees
+struct MergedPly<Ply> {
+    consideration: ai::PlyConsideration<Ply>,
+    /// Sum of the weights the statistics were added with
+    weight: f32,
+    /// Visits of the child the follow-up comes from
+    visits: f32,
+}
+
+/// Merges the weighted play scores of several trees searched from the same state, weighting
+/// them by their visits, the follow-up coming from the tree visiting the ply the most.
+pub fn merge_play_scores<Ply: Copy + hash::Hash + Eq>(
+    trees: Vec<Vec<(ai::PlyConsideration<Ply>, f32)>>,
+) -> Vec<ai::PlyConsideration<Ply>> {
+    let mut merged: HashMap<Ply, MergedPly<Ply>> = HashMap::new();
+    for (consideration, visits) in trees.into_iter().flatten() {
+        // Unvisited children weigh as much as a single visit
+        let weight = visits.max(1.0);
+        let ply = consideration.ply;
+        let merged_ply = merged.entry(ply).or_insert_with(|| MergedPly {
+            consideration: ai::PlyConsideration {
+                ply,
+                score: 0.0,
+                win_rate: 0.0,
+                draw_rate: 0.0,
+                follow_up: Vec::new(),
+            },
+            weight: 0.0,
+            visits: -1.0,
+        });
+        merged_ply.weight += weight;
+        let merged_consideration = &mut merged_ply.consideration;
+        merged_consideration.score += weight * consideration.score;
+        merged_consideration.win_rate += weight * consideration.win_rate;
+        merged_consideration.draw_rate += weight * consideration.draw_rate;
+        if visits > merged_ply.visits {
+            merged_ply.visits = visits;
+            merged_consideration.follow_up = consideration.follow_up;
+        }
+    }
+    let mut scores = merged
+        .into_values()
+        .map(|merged_ply| {
+            let mut consideration = merged_ply.consideration;
+            consideration.score /= merged_ply.weight;
+            consideration.win_rate /= merged_ply.weight;
+            consideration.draw_rate /= merged_ply.weight;
+            consideration
         })
         .collect::<Vec<_>>();
     scores.sort_by(|consideration_a, consideration_b| {
@@ -35,25 +113,63 @@ pub fn play_scores<RuleSet: rulesets::TurnByTurn>(
     scores
 }
 
-fn best_play<RuleSet: rulesets::TurnByTurn>(
-    tree: &graph::Graph<nodes::Node<RuleSet::State>, edges::Edge<RuleSet::Ply>>,
-    mut current_node: graph::NodeIndex<u32>,
+/// Visits of nodes along with the visits of the edges leading to them
+pub type NodeVisits = Vec<(f32, f32)>;
+
+/// Returns the visits of the nodes of the graph, the root coming first, along with the visits of
+/// the edges leading to them, which add up to the visits of the node as every iteration through
+/// it goes through one of its edges.
+pub fn node_visits<RuleSet: rulesets::HasStatesWithSymmetries>(
+    tree: &edges::Tree<RuleSet>,
+    root: graph::NodeIndex<u32>,
+) -> NodeVisits {
+    let others = tree.node_indices().filter(|node| *node != root);
+    iter::once(root)
+        .chain(others)
+        .map(|node| {
+            let edge_visits = tree
+                .edges_directed(node, petgraph::Direction::Incoming)
+                .map(|edge| edge.weight().visits)
+                .sum();
+            (tree.node_weight(node).unwrap().visits, edge_visits)
+        })
+        .collect()
+}
+
+/// Returns the plies of the best children from the target of the edge down, in the frame of its
+/// source, as the plies below edges merged through transpositions are in the frame of their target.
+fn best_play<RuleSet: rulesets::HasStatesWithSymmetries + rulesets::TurnByTurn>(
+    ruleset: &RuleSet,
+    tree: &edges::Tree<RuleSet>,
+    edge: graph::EdgeIndex<u32>,
 ) -> Vec<RuleSet::Ply> {
     let mut result = Vec::new();
+    let (_, mut current_node) = tree.edge_endpoints(edge).unwrap();
+    let mut symmetries = tree
+        .edge_weight(edge)
+        .unwrap()
+        .symmetry
+        .iter()
+        .collect::<Vec<_>>();
     loop {
-        let neighbours = tree.neighbors(current_node).map(|node_index| {
-            let node_weight = tree.node_weight(node_index).unwrap();
-            let edge = tree.find_edge(current_node, node_index).unwrap();
-            let edge_weight = tree.edge_weight(edge).unwrap();
-            (node_weight.score(), node_index, edge_weight.ply)
+        let neighbours = tree.edges(current_node).map(|edge| {
+            let node_weight = tree.node_weight(edge.target()).unwrap();
+            (node_weight.score(), edge.target(), edge.weight())
         });
         let best_neighbour = {
             neighbours
                 .max_by(|(score_a, _, _), (score_b, _, _)| score_a.partial_cmp(score_b).unwrap())
         };
         current_node = match best_neighbour {
-            Some((_, node_index, ply)) => {
+            Some((_, node_index, edge_weight)) => {
+                let ply = symmetries
+                    .iter()
+                    .rev()
+                    .fold(edge_weight.ply, |ply, symmetry| {
+                        ruleset.swap_ply(&ply, symmetry)
+                    });
                 result.push(ply);
+                symmetries.extend(edge_weight.symmetry.iter());
                 node_index
             }
             None => break,
@@ -61,3 +177,50 @@ fn best_play<RuleSet: rulesets::TurnByTurn>(
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rulesets::connectn;
+
+    fn consideration(
+        index: u8,
+        score: f32,
+        follow_up: &[u8],
+    ) -> ai::PlyConsideration<connectn::TicTacToePly> {
+        ai::PlyConsideration {
+            ply: connectn::Ply::new(index),
+            score,
+            win_rate: score,
+            draw_rate: 0.0,
+            follow_up: follow_up
+                .iter()
+                .map(|index| connectn::Ply::new(*index))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_merge_play_scores() {
+        let trees = vec![
+            vec![
+                (consideration(0, 1.0, &[1]), 30.0),
+                (consideration(4, 0.0, &[]), 10.0),
+            ],
+            vec![
+                (consideration(4, 1.0, &[8]), 30.0),
+                (consideration(0, 0.0, &[2]), 10.0),
+            ],
+            vec![(consideration(4, 0.5, &[]), 0.0)],
+        ];
+        let merged = merge_play_scores(trees);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].ply, connectn::Ply::new(0));
+        assert!((merged[0].score - 0.75).abs() < 1e-6);
+        assert_eq!(merged[0].follow_up, vec![connectn::Ply::new(1)]);
+        // Unvisited children weigh as much as a single visit
+        assert_eq!(merged[1].ply, connectn::Ply::new(4));
+        assert!((merged[1].score - 30.5 / 41.0).abs() < 1e-6);
+        assert_eq!(merged[1].follow_up, vec![connectn::Ply::new(8)]);
+    }
+}
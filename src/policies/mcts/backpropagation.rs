@@ -1,20 +1,55 @@
+use super::edges;
 use super::nodes;
+use super::solver;
 use crate::interface::rulesets;
 use petgraph::graph;
+use std::fmt;
 
-pub fn backpropagate<State: rulesets::StateTrait, Edge>(
-    tree: &mut graph::Graph<nodes::Node<State>, Edge>,
-    node: graph::NodeIndex<u32>,
+/// Backpropagates the outcome of an iteration along the path it took from the root, each node
+/// and edge of the path being updated once, so that transpositions giving nodes several parents
+/// do not count the outcome several times.
+pub fn backpropagate<State: rulesets::StateTrait, Ply: fmt::Debug, Symmetry>(
+    tree: &mut graph::Graph<nodes::Node<State>, edges::Edge<Ply, Symmetry>>,
+    path: &[graph::NodeIndex<u32>],
     update_visits: bool,
     status: Option<rulesets::Status>,
 ) {
-    let mut neighbours = tree
-        .neighbors_directed(node, petgraph::Direction::Incoming)
-        .detach();
-    while let Some((_, parent)) = neighbours.next(tree) {
-        backpropagate(tree, parent, update_visits, status);
+    // Parents are proven after the node, as their proof depends on its outcome
+    for (depth, node) in path.iter().enumerate().rev() {
+        solver::update_proof(tree, *node);
+        update_tallies(tree, *node, update_visits, status);
+        if update_visits && depth > 0 {
+            let edge = tree.find_edge(path[depth - 1], *node).unwrap();
+            tree.edge_weight_mut(edge).unwrap().visits += 1.0;
+        }
+    }
+}
+
+/// Backpropagates the value of a state given by an evaluator, for the given player, along the
+/// path leading to it, the visits being already counted.
+pub fn backpropagate_value<State: rulesets::StateTrait, Edge>(
+    tree: &mut graph::Graph<nodes::Node<State>, Edge>,
+    path: &[graph::NodeIndex<u32>],
+    player: rulesets::Player,
+    value: f32,
+) {
+    for node in path.iter().rev() {
+        solver::update_proof(tree, *node);
+        tree.node_weight_mut(*node)
+            .unwrap()
+            .backpropagate_value(player, value);
+    }
+}
+
+/// Adds iterations in progress to the nodes of the path, or removes them when negative.
+pub fn add_pending<State: rulesets::StateTrait, Edge>(
+    tree: &mut graph::Graph<nodes::Node<State>, Edge>,
+    path: &[graph::NodeIndex<u32>],
+    pending: f32,
+) {
+    for node in path {
+        tree.node_weight_mut(*node).unwrap().pending += pending;
     }
-    update_tallies(tree, node, update_visits, status);
 }
 
 pub fn update_tallies<State: rulesets::StateTrait, Edge>(
@@ -31,3 +66,62 @@ pub fn update_tallies<State: rulesets::StateTrait, Edge>(
         weight.backpropagate(status);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rulesets::connectn;
+
+    type TicTacToeGraph = graph::Graph<
+        nodes::Node<connectn::TicTacToeState>,
+        edges::Edge<connectn::TicTacToePly, connectn::Symmetry>,
+    >;
+
+    fn add_node(
+        tree: &mut TicTacToeGraph,
+        p1_indices: &[usize],
+        p2_indices: &[usize],
+    ) -> graph::NodeIndex<u32> {
+        let current_player = ((p1_indices.len() + p2_indices.len()) % 2) as u8;
+        let state = connectn::TicTacToeState::from_indices(p1_indices, p2_indices, current_player);
+        tree.add_node(nodes::Node::new(
+            state,
+            rulesets::Status::Ongoing,
+            current_player,
+        ))
+    }
+
+    #[test]
+    fn test_transposition() {
+        let mut tree = TicTacToeGraph::new();
+        let root = add_node(&mut tree, &[], &[]);
+        let first = add_node(&mut tree, &[0], &[4]);
+        let second = add_node(&mut tree, &[2], &[4]);
+        let transposition = add_node(&mut tree, &[0, 2], &[4]);
+        tree.add_edge(root, first, edges::Edge::new(connectn::Ply::new(0)));
+        tree.add_edge(root, second, edges::Edge::new(connectn::Ply::new(2)));
+        tree.add_edge(
+            first,
+            transposition,
+            edges::Edge::new(connectn::Ply::new(2)),
+        );
+        tree.add_edge(
+            second,
+            transposition,
+            edges::Edge::new(connectn::Ply::new(0)),
+        );
+        let status = rulesets::Status::Win { player: 0 };
+        backpropagate(&mut tree, &[root, first, transposition], true, Some(status));
+        let visits = |node| tree.node_weight(node).unwrap().visits;
+        assert_eq!(visits(root), 1.0);
+        assert_eq!(visits(first), 1.0);
+        assert_eq!(visits(second), 0.0);
+        assert_eq!(visits(transposition), 1.0);
+        let edge_visits = |parent, child| {
+            let edge = tree.find_edge(parent, child).unwrap();
+            tree.edge_weight(edge).unwrap().visits
+        };
+        assert_eq!(edge_visits(first, transposition), 1.0);
+        assert_eq!(edge_visits(second, transposition), 0.0);
+    }
+}
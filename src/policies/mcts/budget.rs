@@ -0,0 +1,163 @@
+use std::time;
+
+/// Default number of iterations run for each decision
+const DEFAULT_ITERATIONS: usize = 1000;
+
+/// Amount of search spent on each decision, the search stopping as soon as any of the limits is
+/// reached.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Budget {
+    pub iterations: Option<usize>,
+    pub time: Option<time::Duration>,
+    /// Maximum number of nodes in the tree
+    pub nodes: Option<usize>,
+    /// Whether to stop once the decision cannot change anymore, see `stopping::should_stop`
+    pub early_stop: bool,
+}
+
+impl Budget {
+    pub fn iterations(iterations: usize) -> Budget {
+        Budget {
+            iterations: Some(iterations),
+            time: None,
+            nodes: None,
+            early_stop: false,
+        }
+    }
+
+    pub fn time(time: time::Duration) -> Budget {
+        Budget {
+            time: Some(time),
+            iterations: None,
+            nodes: None,
+            early_stop: false,
+        }
+    }
+
+    pub fn nodes(nodes: usize) -> Budget {
+        Budget {
+            nodes: Some(nodes),
+            iterations: None,
+            time: None,
+            early_stop: false,
+        }
+    }
+
+    /// Returns the budget of each of the given number of searches running at once, which share
+    /// the iterations and the nodes, each one running for the whole time.
+    pub fn share(&self, searches: usize) -> Budget {
+        Budget {
+            iterations: self
+                .iterations
+                .map(|iterations| iterations.div_ceil(searches)),
+            nodes: self.nodes.map(|nodes| nodes.div_ceil(searches)),
+            ..*self
+        }
+    }
+}
+
+impl Default for Budget {
+    fn default() -> Budget {
+        Budget::iterations(DEFAULT_ITERATIONS)
+    }
+}
+
+/// Spending of a budget during a search.
+pub struct Tracker {
+    budget: Budget,
+    start: time::Instant,
+    iterations: usize,
+}
+
+impl Tracker {
+    pub fn new(budget: Budget) -> Tracker {
+        Tracker {
+            budget,
+            start: time::Instant::now(),
+            iterations: 0,
+        }
+    }
+
+    pub fn early_stop(&self) -> bool {
+        self.budget.early_stop
+    }
+
+    pub fn add_iterations(&mut self, iterations: usize) {
+        self.iterations += iterations;
+    }
+
+    pub fn is_exhausted(&self, node_count: usize) -> bool {
+        matches!(self.budget.iterations, Some(iterations) if self.iterations >= iterations)
+            || matches!(self.budget.time, Some(time) if self.start.elapsed() >= time)
+            || matches!(self.budget.nodes, Some(nodes) if node_count >= nodes)
+    }
+
+    /// Returns the number of iterations left, those of a time limit being estimated from the
+    /// pace of the search so far, or `None` when iterations and time are not limited.
+    pub fn remaining_iterations(&self) -> Option<usize> {
+        let by_count = self
+            .budget
+            .iterations
+            .map(|iterations| iterations.saturating_sub(self.iterations));
+        let by_time = self.budget.time.map(|time| {
+            let elapsed = self.start.elapsed();
+            if self.iterations == 0 || elapsed.as_secs_f64() == 0.0 {
+                return usize::MAX;
+            }
+            let remaining = time.checked_sub(elapsed).unwrap_or_default();
+            (remaining.as_secs_f64() * self.iterations as f64 / elapsed.as_secs_f64()) as usize
+        });
+        match (by_count, by_time) {
+            (Some(by_count), Some(by_time)) => Some(by_count.min(by_time)),
+            (by_count, by_time) => by_count.or(by_time),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_iterations() {
+        let mut tracker = Tracker::new(Budget::iterations(10));
+        tracker.add_iterations(4);
+        assert!(!tracker.is_exhausted(1000));
+        assert_eq!(tracker.remaining_iterations(), Some(6));
+        tracker.add_iterations(6);
+        assert!(tracker.is_exhausted(1000));
+        assert_eq!(tracker.remaining_iterations(), Some(0));
+    }
+
+    #[test]
+    fn test_nodes() {
+        let tracker = Tracker::new(Budget::nodes(100));
+        assert!(!tracker.is_exhausted(99));
+        assert!(tracker.is_exhausted(100));
+        assert_eq!(tracker.remaining_iterations(), None);
+    }
+
+    #[test]
+    fn test_share() {
+        let budget = Budget {
+            nodes: Some(100),
+            time: Some(time::Duration::from_millis(20)),
+            ..Budget::iterations(10)
+        };
+        let shared = budget.share(3);
+        assert_eq!(shared.iterations, Some(4));
+        assert_eq!(shared.nodes, Some(34));
+        assert_eq!(shared.time, budget.time);
+    }
+
+    #[test]
+    fn test_time() {
+        let mut tracker = Tracker::new(Budget::time(time::Duration::from_millis(20)));
+        assert!(!tracker.is_exhausted(0));
+        tracker.add_iterations(5);
+        thread::sleep(time::Duration::from_millis(25));
+        assert!(tracker.is_exhausted(0));
+        assert_eq!(tracker.remaining_iterations(), Some(0));
+    }
+}
@@ -1,12 +1,95 @@
+use super::nodes;
+use crate::interface::rulesets;
+use petgraph::graph;
 use std::fmt;
 
+/// Graph searched for a ruleset
+pub type Tree<RuleSet> = graph::Graph<
+    nodes::Node<<RuleSet as rulesets::RuleSetTrait>::State>,
+    Edge<
+        <RuleSet as rulesets::RuleSetTrait>::Ply,
+        <RuleSet as rulesets::HasStatesWithSymmetries>::Symmetry,
+    >,
+>;
+
 #[derive(Debug)]
-pub struct Edge<Ply: fmt::Debug> {
+pub struct Edge<Ply: fmt::Debug, Symmetry> {
     pub ply: Ply,
+    /// Number of iterations that went through the edge, which is less than the visits of its
+    /// target once transpositions give it several parents
+    pub visits: f32,
+    /// Probability of the ply given by a policy, when the source was evaluated by one
+    pub prior: Option<f32>,
+    /// Symmetry mapping the state of the target onto the state the ply leads to, when
+    /// transpositions merged the latter into a symmetric node, so that plies from the target map
+    /// back to the frame of the source
+    pub symmetry: Option<Symmetry>,
+    /// Number of simulations through the source in which the player moving along the edge later
+    /// played its ply, all moves being counted as the first one
+    pub amaf_visits: f32,
+    /// Mean outcome of those simulations for the player moving along the edge
+    pub amaf_score: f32,
+}
+
+impl<Ply: fmt::Debug, Symmetry> Edge<Ply, Symmetry> {
+    pub fn new(ply: Ply) -> Edge<Ply, Symmetry> {
+        Edge {
+            ply,
+            visits: 0.0,
+            prior: None,
+            symmetry: None,
+            amaf_visits: 0.0,
+            amaf_score: 0.0,
+        }
+    }
+
+    /// Counts the outcome of a simulation in which the player moving along the edge later played
+    /// its ply.
+    pub fn add_amaf_outcome(&mut self, status: rulesets::Status, player: rulesets::Player) {
+        let outcome = match status.player_pov(player) {
+            rulesets::PlayerStatus::Win => 1.0,
+            rulesets::PlayerStatus::Draw => 0.5,
+            rulesets::PlayerStatus::Loss => 0.0,
+            rulesets::PlayerStatus::Ongoing => unreachable!(),
+        };
+        self.amaf_visits += 1.0;
+        self.amaf_score += (outcome - self.amaf_score) / self.amaf_visits;
+    }
+}
+
+/// Edges which may count the iterations that went through them, hold the prior of their ply and
+/// gather its all-moves-as-first statistics.
+pub trait EdgeStatistics {
+    fn visits(&self) -> Option<f32>;
+    fn prior(&self) -> Option<f32>;
+    /// Number of all-moves-as-first simulations along with their mean outcome
+    fn amaf(&self) -> Option<(f32, f32)>;
 }
 
-impl<Ply: fmt::Debug> Edge<Ply> {
-    pub fn new(ply: Ply) -> Edge<Ply> {
-        Edge { ply }
+impl<Ply: fmt::Debug, Symmetry> EdgeStatistics for Edge<Ply, Symmetry> {
+    fn visits(&self) -> Option<f32> {
+        Some(self.visits)
+    }
+
+    fn prior(&self) -> Option<f32> {
+        self.prior
+    }
+
+    fn amaf(&self) -> Option<(f32, f32)> {
+        Some((self.amaf_visits, self.amaf_score))
+    }
+}
+
+impl EdgeStatistics for () {
+    fn visits(&self) -> Option<f32> {
+        None
+    }
+
+    fn prior(&self) -> Option<f32> {
+        None
+    }
+
+    fn amaf(&self) -> Option<(f32, f32)> {
+        None
     }
 }
@@ -5,6 +5,7 @@ use std::hash;
 
 use super::super::edges;
 use super::super::nodes;
+use super::super::transpositions;
 
 use petgraph::graph;
 
@@ -16,13 +17,14 @@ pub enum ExpansionStatus<State> {
 }
 
 pub fn expand<RuleSet>(
-    tree: &mut graph::Graph<nodes::Node<RuleSet::State>, edges::Edge<RuleSet::Ply>>,
+    tree: &mut edges::Tree<RuleSet>,
     ruleset: &RuleSet,
     node: graph::NodeIndex<u32>,
+    mut transpositions: Option<&mut transpositions::Transpositions<RuleSet>>,
 ) -> (rulesets::Status, bool)
 where
     RuleSet: rulesets::HasStatesWithSymmetries + rulesets::Deterministic + rulesets::TurnByTurn,
-    RuleSet::State: Eq,
+    RuleSet::State: Eq + Ord + hash::Hash,
     RuleSet::Ply: Eq + Ord + hash::Hash,
 {
     let state = match ponder_expansion::<RuleSet>(tree, node, true) {
@@ -34,13 +36,19 @@ where
     let mut iterator = iterator::Expander::new(ruleset, &state);
 
     while let Some(successor) = iterator.iterate() {
-        save_expansion(tree, node, successor);
+        save_successor(
+            tree,
+            ruleset,
+            transpositions.as_deref_mut(),
+            node,
+            successor,
+        );
     }
     (rulesets::Status::Ongoing, true)
 }
 
-pub fn ponder_expansion<RuleSet: rulesets::TurnByTurn>(
-    tree: &mut graph::Graph<nodes::Node<RuleSet::State>, edges::Edge<RuleSet::Ply>>,
+pub fn ponder_expansion<RuleSet: rulesets::HasStatesWithSymmetries + rulesets::TurnByTurn>(
+    tree: &mut edges::Tree<RuleSet>,
     node_index: graph::NodeIndex<u32>,
     check_for_visits: bool,
 ) -> ExpansionStatus<RuleSet::State> {
@@ -60,15 +68,35 @@ pub fn ponder_expansion<RuleSet: rulesets::TurnByTurn>(
     ExpansionStatus::RequiresExpansion(weight.state.clone())
 }
 
-pub fn save_expansion<RuleSet: rulesets::TurnByTurn>(
-    tree: &mut graph::Graph<nodes::Node<RuleSet::State>, edges::Edge<RuleSet::Ply>>,
+pub fn save_expansion<RuleSet: rulesets::HasStatesWithSymmetries + rulesets::TurnByTurn>(
+    tree: &mut edges::Tree<RuleSet>,
     node_index: graph::NodeIndex<u32>,
     successor: items::Play<RuleSet>,
-) {
+) -> graph::NodeIndex<u32> {
     let mut parent_weight = tree.node_weight_mut(node_index).unwrap();
     parent_weight.expanding = false;
     let node_weight = nodes::Node::new(successor.state, successor.status, successor.current_player);
     let child_index = tree.add_node(node_weight);
     let edge_weight = edges::Edge::new(successor.ply);
     tree.add_edge(node_index, child_index, edge_weight);
+    child_index
+}
+
+/// Saves a successor of the node, merging it with the node of the same or of a symmetric state
+/// when transpositions are tracked.
+pub fn save_successor<RuleSet>(
+    tree: &mut edges::Tree<RuleSet>,
+    ruleset: &RuleSet,
+    transpositions: Option<&mut transpositions::Transpositions<RuleSet>>,
+    node_index: graph::NodeIndex<u32>,
+    successor: items::Play<RuleSet>,
+) -> graph::NodeIndex<u32>
+where
+    RuleSet: rulesets::HasStatesWithSymmetries + rulesets::TurnByTurn,
+    RuleSet::State: Eq + Ord + hash::Hash,
+{
+    match transpositions {
+        Some(transpositions) => transpositions.save_expansion(ruleset, tree, node_index, successor),
+        None => save_expansion(tree, node_index, successor),
+    }
 }
@@ -9,8 +9,10 @@ mod worker;
 pub use algo::expand;
 pub use algo::ponder_expansion;
 pub use algo::save_expansion;
+pub use algo::save_successor;
 pub use algo::ExpansionStatus;
 pub use items::Play;
+pub use iterator::Expander;
 pub use pool::Pool;
 pub use requests::Request;
 pub use responses::Response;
@@ -0,0 +1,34 @@
+//! Evaluators of the leaves reached by the selection, replacing the simulations
+//!
+//! # References
+//!
+//! * [Mastering the game of Go without human knowledge](https://www.nature.com/articles/nature24270)
+
+mod policy;
+mod qvalue;
+
+pub use policy::PolicyEvaluator;
+pub use qvalue::QValueEvaluator;
+
+use crate::interface::rulesets;
+use std::error;
+use std::sync;
+
+/// Evaluator kept across the starts of the orchestrator, each one handing it to a new worker
+pub type Shared<RuleSet> = sync::Arc<sync::Mutex<dyn LeafEvaluator<RuleSet>>>;
+
+/// Evaluation of an ongoing state
+pub struct Evaluation<RuleSet: rulesets::RuleSetTrait> {
+    /// Expected outcome for the current player of the state, within [-1, 1]
+    pub value: f32,
+    /// Probabilities of playing each ply, when the evaluator predicts them
+    pub priors: Option<Vec<(RuleSet::Ply, f32)>>,
+}
+
+/// Evaluates leaves by batches, which networks can do in a single run.
+pub trait LeafEvaluator<RuleSet: rulesets::RuleSetTrait>: Send {
+    fn evaluate_batch(
+        &mut self,
+        states: &[RuleSet::State],
+    ) -> Result<Vec<Evaluation<RuleSet>>, Box<dyn error::Error>>;
+}
@@ -0,0 +1,85 @@
+use super::Evaluation;
+use super::LeafEvaluator;
+use crate::interface::ai;
+use crate::interface::rulesets;
+use std::error;
+use std::marker;
+
+/// Evaluates leaves with the value and the probabilities predicted by a policy, such as a
+/// neural network.
+pub struct PolicyEvaluator<RuleSet, Policy>
+where
+    RuleSet: rulesets::RuleSetTrait,
+    Policy: ai::Policy<RuleSet>,
+{
+    policy: Policy,
+    ruleset: marker::PhantomData<fn() -> RuleSet>,
+}
+
+impl<RuleSet, Policy> PolicyEvaluator<RuleSet, Policy>
+where
+    RuleSet: rulesets::RuleSetTrait,
+    Policy: ai::Policy<RuleSet>,
+{
+    pub fn new(policy: Policy) -> PolicyEvaluator<RuleSet, Policy> {
+        PolicyEvaluator {
+            policy,
+            ruleset: marker::PhantomData,
+        }
+    }
+}
+
+impl<RuleSet, Policy> LeafEvaluator<RuleSet> for PolicyEvaluator<RuleSet, Policy>
+where
+    RuleSet: rulesets::RuleSetTrait,
+    Policy: ai::Policy<RuleSet> + Send,
+{
+    fn evaluate_batch(
+        &mut self,
+        states: &[RuleSet::State],
+    ) -> Result<Vec<Evaluation<RuleSet>>, Box<dyn error::Error>> {
+        let predictions = self.policy.predict_batch(states)?;
+        Ok(predictions
+            .into_iter()
+            .map(|prediction| Evaluation {
+                value: prediction.value,
+                priors: Some(prediction.probabilities),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::rulesets::connectn;
+
+    struct Corner;
+
+    impl ai::Policy<connectn::TicTacToe> for Corner {
+        fn predict(
+            &mut self,
+            _state: &connectn::TicTacToeState,
+        ) -> Result<ai::Prediction<connectn::TicTacToe>, Box<dyn error::Error>> {
+            Ok(ai::Prediction {
+                value: 0.5,
+                probabilities: vec![(connectn::Ply::new(0), 1.0)],
+            })
+        }
+    }
+
+    #[test]
+    fn test_evaluate_batch() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let states = vec![ruleset.initial_state(); 3];
+        let mut evaluator = PolicyEvaluator::new(Corner);
+        let evaluations = evaluator.evaluate_batch(&states)?;
+        assert_eq!(evaluations.len(), 3);
+        for evaluation in evaluations {
+            assert_eq!(evaluation.value, 0.5);
+            assert_eq!(evaluation.priors, Some(vec![(connectn::Ply::new(0), 1.0)]));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use super::Evaluation;
+use super::LeafEvaluator;
+use crate::interface::ai;
+use crate::interface::rulesets;
+use std::error;
+use std::marker;
+
+/// Evaluates leaves with the value given by a q-value, the children being left without priors.
+pub struct QValueEvaluator<RuleSet, QValue>
+where
+    RuleSet: rulesets::RuleSetTrait,
+    QValue: ai::QValue<RuleSet>,
+{
+    qvalue: QValue,
+    ruleset: marker::PhantomData<fn() -> RuleSet>,
+}
+
+impl<RuleSet, QValue> QValueEvaluator<RuleSet, QValue>
+where
+    RuleSet: rulesets::RuleSetTrait,
+    QValue: ai::QValue<RuleSet>,
+{
+    pub fn new(qvalue: QValue) -> QValueEvaluator<RuleSet, QValue> {
+        QValueEvaluator {
+            qvalue,
+            ruleset: marker::PhantomData,
+        }
+    }
+}
+
+impl<RuleSet, QValue> LeafEvaluator<RuleSet> for QValueEvaluator<RuleSet, QValue>
+where
+    RuleSet: rulesets::RuleSetTrait,
+    QValue: ai::QValue<RuleSet> + Send,
+{
+    fn evaluate_batch(
+        &mut self,
+        states: &[RuleSet::State],
+    ) -> Result<Vec<Evaluation<RuleSet>>, Box<dyn error::Error>> {
+        let values = self.qvalue.evaluate_batch(states)?;
+        Ok(values
+            .into_iter()
+            .map(|value| Evaluation {
+                value,
+                priors: None,
+            })
+            .collect())
+    }
+}
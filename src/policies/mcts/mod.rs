@@ -7,21 +7,37 @@
 //! * [P-UCT algorithm](https://openreview.net/attachment?id=BJlQtJSKDB&name=original_pdf)
 //! * [Structured parallel MCTS](https://arxiv.org/pdf/1704.00325.pdf)
 //! * [Parallelization with a MPPA architecture](https://hal.archives-ouvertes.fr/hal-02183609/document)
+//! * [Watch the unobserved: a simple approach to parallelizing Monte Carlo tree search](https://arxiv.org/pdf/1810.11755.pdf)
 //! * [Parallel Monte-Carlo tree search](http://citeseerx.ist.psu.edu/viewdoc/download?doi=10.1.1.159.4373&rep=rep1&type=pdf)
 //!
 //! ## Variants
 //!
 //! * [Asymmetric move selection strategies](https://arxiv.org/pdf/1605.02321.pdf)
+//! * [Monte-Carlo tree search solver](https://dke.maastrichtuniversity.nl/m.winands/documents/uctloa.pdf)
+//! * [Transpositions and move groups in Monte Carlo tree search](https://ieeexplore.ieee.org/document/5035667)
 
 mod algo;
 mod analysis;
 mod backpropagation;
+mod budget;
 mod edges;
 mod expansion;
+pub mod leaf_evaluator;
 mod nodes;
 pub mod puct;
+mod rave;
+mod reuse;
+pub mod rollout_policy;
 mod selection;
 mod simulation;
-mod uct_value;
+mod solver;
+mod stopping;
+mod time_manager;
+mod transpositions;
+pub mod tree_policy;
+mod virtual_loss;
 
 pub use algo::MCTS;
+pub use budget::Budget;
+pub use time_manager::TimeManager;
+pub use virtual_loss::VirtualLoss;
@@ -3,8 +3,17 @@ use crate::interface::rulesets::StateTrait;
 
 #[derive(Debug)]
 pub enum Status {
-    Terminal { status: rulesets::Status },
-    Ongoing { score: f32, draw_rate: f32 },
+    Terminal {
+        status: rulesets::Status,
+    },
+    /// Outcome of an ongoing game under perfect play, known from the outcomes of the children
+    Proven {
+        status: rulesets::Status,
+    },
+    Ongoing {
+        score: f32,
+        draw_rate: f32,
+    },
 }
 
 #[derive(Debug)]
@@ -14,6 +23,8 @@ pub struct Node<State: StateTrait> {
     pub visits: f32,
     pub expanding: bool,
     pub current_player: rulesets::Player,
+    /// Number of iterations through the node whose outcome is still awaited
+    pub pending: f32,
 }
 
 impl<State: rulesets::StateTrait> Node<State> {
@@ -36,23 +47,36 @@ impl<State: rulesets::StateTrait> Node<State> {
             visits: 0.0,
             expanding: false,
             current_player,
+            pending: 0.0,
         }
     }
 
     pub fn is_visited(&self) -> bool {
         match self.status {
-            Status::Terminal { .. } => true,
+            Status::Terminal { .. } | Status::Proven { .. } => true,
             _ => self.visits > 0.0,
         }
     }
 
+    /// Returns the outcome of the game from the node, when it is finished or proven.
     pub fn game_status(&self) -> rulesets::Status {
         match self.status {
-            Status::Terminal { status } => status,
+            Status::Terminal { status } | Status::Proven { status } => status,
             _ => rulesets::Status::Ongoing,
         }
     }
 
+    /// Tells whether the outcome of the node is known to be a loss for the player who moved into
+    /// it.
+    pub fn proven_loss(&self) -> bool {
+        match self.status {
+            Status::Terminal { status } | Status::Proven { status } => {
+                status.player_pov(self.current_player) == rulesets::PlayerStatus::Win
+            }
+            Status::Ongoing { .. } => false,
+        }
+    }
+
     pub fn add_visit(&mut self) {
         self.visits += 1.0;
         if let Status::Ongoing {
@@ -83,6 +107,14 @@ impl<State: rulesets::StateTrait> Node<State> {
             visits: visits as f32,
             expanding: false,
             current_player,
+            pending: 0.0,
+        }
+    }
+
+    pub fn prove(&mut self, status: rulesets::Status) {
+        debug_assert!(status != rulesets::Status::Ongoing);
+        if let Status::Ongoing { .. } = self.status {
+            self.status = Status::Proven { status };
         }
     }
 
@@ -103,38 +135,73 @@ impl<State: rulesets::StateTrait> Node<State> {
                 }
                 Status::Ongoing { score, draw_rate }
             }
-            Status::Terminal { .. } => return,
+            Status::Terminal { .. } | Status::Proven { .. } => return,
         };
     }
 
+    /// Updates the score from the value of a state given by an evaluator, from the point of view
+    /// of the given player, within [-1, 1].
+    pub fn backpropagate_value(&mut self, player: rulesets::Player, value: f32) {
+        if let Status::Ongoing {
+            mut score,
+            draw_rate,
+        } = self.status
+        {
+            let current_player_value = if player == self.current_player {
+                value
+            } else {
+                -value
+            };
+            score += (1.0 - current_player_value) / 2.0 / self.visits;
+            self.status = Status::Ongoing { score, draw_rate };
+        }
+    }
+
     pub fn score(&self) -> f32 {
         match &self.status {
-            Status::Terminal { status } => match status.player_pov(self.current_player) {
-                rulesets::PlayerStatus::Win => 0.0,
-                rulesets::PlayerStatus::Draw => 0.5,
-                rulesets::PlayerStatus::Loss => 1.0,
-                rulesets::PlayerStatus::Ongoing => unreachable!(),
-            },
+            Status::Terminal { status } | Status::Proven { status } => {
+                match status.player_pov(self.current_player) {
+                    rulesets::PlayerStatus::Win => 0.0,
+                    rulesets::PlayerStatus::Draw => 0.5,
+                    rulesets::PlayerStatus::Loss => 1.0,
+                    rulesets::PlayerStatus::Ongoing => unreachable!(),
+                }
+            }
             Status::Ongoing { score, .. } => *score,
         }
     }
 
+    /// Variance of the outcomes, each outcome being 1 for a win of the player who moved into the
+    /// node, 0.5 for a draw and 0 for a loss.
+    pub fn variance(&self) -> f32 {
+        match &self.status {
+            Status::Terminal { .. } | Status::Proven { .. } => 0.0,
+            Status::Ongoing { score, draw_rate } => {
+                (score - 0.25 * draw_rate - score * score).max(0.0)
+            }
+        }
+    }
+
     pub fn win_rate(&self) -> f32 {
         match &self.status {
-            Status::Terminal { status } => match status.player_pov(self.current_player) {
-                rulesets::PlayerStatus::Loss => 1.0,
-                _ => 0.0,
-            },
+            Status::Terminal { status } | Status::Proven { status } => {
+                match status.player_pov(self.current_player) {
+                    rulesets::PlayerStatus::Loss => 1.0,
+                    _ => 0.0,
+                }
+            }
             Status::Ongoing { score, draw_rate } => score - 0.5 * draw_rate,
         }
     }
 
     pub fn draw_rate(&self) -> f32 {
         match &self.status {
-            Status::Terminal { status } => match status.player_pov(self.current_player) {
-                rulesets::PlayerStatus::Draw => 1.0,
-                _ => 0.0,
-            },
+            Status::Terminal { status } | Status::Proven { status } => {
+                match status.player_pov(self.current_player) {
+                    rulesets::PlayerStatus::Draw => 1.0,
+                    _ => 0.0,
+                }
+            }
             Status::Ongoing { draw_rate, .. } => *draw_rate,
         }
     }
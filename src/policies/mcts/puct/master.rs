@@ -1,10 +1,18 @@
 use super::super::analysis;
 use super::super::backpropagation;
+use super::super::budget;
 use super::super::edges;
 use super::super::expansion;
 use super::super::nodes;
+use super::super::rave;
+use super::super::reuse;
 use super::super::selection;
 use super::super::simulation;
+use super::super::stopping;
+use super::super::transpositions;
+use super::super::tree_policy;
+use super::super::virtual_loss;
+use super::parallelism;
 use super::requests;
 use super::responses;
 use crate::interface::ai;
@@ -12,7 +20,13 @@ use crate::interface::rulesets;
 use crossbeam::channel;
 use petgraph::graph;
 use rand::rngs;
+use rand::SeedableRng;
+use std::collections::HashMap;
 use std::error;
+use std::hash;
+
+/// Number of iterations run concurrently between two checks of the stopping rules
+const SEARCH_CHUNK: usize = 256;
 
 #[derive(Debug)]
 enum SelectionResult {
@@ -28,19 +42,34 @@ where
         + rulesets::Deterministic
         + rulesets::TurnByTurn
         + 'static,
+    RuleSet::Ply: Eq + Ord + hash::Hash,
+    RuleSet::State: Eq + Ord + hash::Hash,
 {
-    tree: graph::Graph<nodes::Node<RuleSet::State>, edges::Edge<RuleSet::Ply>>,
+    tree: edges::Tree<RuleSet>,
     root: Option<graph::NodeIndex<u32>>,
     ruleset: RuleSet,
+    tree_policy: Box<dyn tree_policy::TreePolicy<RuleSet::State>>,
+    amaf_update: Option<rave::AmafUpdate<RuleSet>>,
+    /// Whether the simulation workers evaluate and expand the leaves instead of simulating them
+    evaluates_leaves: bool,
+    virtual_loss: virtual_loss::VirtualLoss,
+    /// Parallelism of the tree, root parallelism giving each tree its own master
+    parallelism: parallelism::Parallelism,
+    merges_transpositions: bool,
+    transpositions: Option<transpositions::Transpositions<RuleSet>>,
+    /// Paths from their root to the nodes being expanded
+    expansion_paths: HashMap<graph::NodeIndex<u32>, Vec<graph::NodeIndex<u32>>>,
+    /// Paths from their root to the nodes being simulated, once per simulation in progress
+    simulation_paths: HashMap<graph::NodeIndex<u32>, Vec<Vec<graph::NodeIndex<u32>>>>,
 
-    rng: rngs::ThreadRng,
+    rng: rngs::StdRng,
 
     master_request_receiver: channel::Receiver<requests::Request<RuleSet>>,
     master_response_sender: channel::Sender<responses::Response<RuleSet>>,
     expansion_request_sender: channel::Sender<expansion::Request<RuleSet>>,
     expansion_response_receiver: channel::Receiver<expansion::Response<RuleSet>>,
     simulation_request_sender: channel::Sender<simulation::Request<RuleSet>>,
-    simulation_response_receiver: channel::Receiver<simulation::Response>,
+    simulation_response_receiver: channel::Receiver<simulation::Response<RuleSet>>,
 }
 
 impl<RuleSet> Master<RuleSet>
@@ -49,6 +78,8 @@ where
         + rulesets::Deterministic
         + rulesets::TurnByTurn
         + 'static,
+    RuleSet::Ply: Eq + Ord + hash::Hash,
+    RuleSet::State: Eq + Ord + hash::Hash,
 {
     pub fn new(
         ruleset: RuleSet,
@@ -57,13 +88,22 @@ where
         expansion_request_sender: channel::Sender<expansion::Request<RuleSet>>,
         expansion_response_receiver: channel::Receiver<expansion::Response<RuleSet>>,
         simulation_request_sender: channel::Sender<simulation::Request<RuleSet>>,
-        simulation_response_receiver: channel::Receiver<simulation::Response>,
+        simulation_response_receiver: channel::Receiver<simulation::Response<RuleSet>>,
     ) -> Master<RuleSet> {
         Master {
             tree: graph::Graph::new(),
             root: None,
             ruleset,
-            rng: rand::thread_rng(),
+            tree_policy: Box::new(tree_policy::UCB1::default()),
+            amaf_update: None,
+            evaluates_leaves: false,
+            virtual_loss: virtual_loss::VirtualLoss::default(),
+            parallelism: parallelism::Parallelism::default(),
+            merges_transpositions: false,
+            transpositions: None,
+            expansion_paths: HashMap::new(),
+            simulation_paths: HashMap::new(),
+            rng: rngs::StdRng::from_entropy(),
             master_request_receiver,
             master_response_sender,
             expansion_request_sender,
@@ -73,7 +113,33 @@ where
         }
     }
 
+    /// Sets whether the simulation workers evaluate and expand the leaves instead of simulating
+    /// them.
+    pub fn set_evaluates_leaves(&mut self, evaluates_leaves: bool) {
+        self.evaluates_leaves = evaluates_leaves;
+    }
+
+    /// Sets the state to search from, reusing the subtree of the current root when the state
+    /// follows it by a ply of each player at most.
     fn set_state(&mut self, state: RuleSet::State) {
+        self.reset_root(state);
+        self.reset_transpositions();
+    }
+
+    fn reset_root(&mut self, state: RuleSet::State) {
+        if let Some(root) = self.root {
+            self.root = reuse::advance_root(
+                &mut self.tree,
+                &self.ruleset,
+                root,
+                &state,
+                reuse::MAX_ADVANCE_DEPTH,
+            );
+            if self.root.is_some() {
+                return;
+            }
+            self.tree.clear();
+        }
         let status = self.ruleset.status(&state);
         let current_player = self.ruleset.current_player(&state);
         let index = self
@@ -82,39 +148,87 @@ where
         self.root = Some(index);
     }
 
-    fn iterate_concurrent(
+    /// Indexes the nodes of the tree by state when transpositions are merged.
+    fn reset_transpositions(&mut self) {
+        self.transpositions = if self.merges_transpositions {
+            let mut transpositions = transpositions::Transpositions::new();
+            transpositions.rebuild(&self.ruleset, &self.tree);
+            Some(transpositions)
+        } else {
+            None
+        };
+    }
+
+    /// Runs iterations, the way they are spread over the workers depending on the parallelism.
+    fn iterate(
         &mut self,
         iteration_count: usize,
         expansion_workers: usize,
         simulation_workers: usize,
     ) -> Result<(), Box<dyn error::Error>> {
+        if self.parallelism != parallelism::Parallelism::Leaf {
+            self.iterate_concurrent(iteration_count, None, expansion_workers, simulation_workers)?;
+            return Ok(());
+        }
+        for _ in 0..iteration_count {
+            self.iterate_sequential(simulation_workers.max(1))?;
+        }
+        Ok(())
+    }
+
+    /// Runs iterations over the workers and returns how many were started, stopping early once the
+    /// budget of the tracker is spent, though never before the first iteration.
+    fn iterate_concurrent(
+        &mut self,
+        iteration_count: usize,
+        tracker: Option<&budget::Tracker>,
+        expansion_workers: usize,
+        simulation_workers: usize,
+    ) -> Result<usize, Box<dyn error::Error>> {
         let node = match self.root {
             Some(node) => node,
             None => {
-                return Ok(());
+                return Ok(0);
             }
         };
         let expansion_threshold = expansion_workers as isize;
         let simulation_threshold = simulation_workers as isize;
         let mut expansion_jobs: isize = 0;
         let mut simulation_jobs: isize = 0;
-        for _ in 0..iteration_count {
+        // Selections reaching a node being expanded wait for it without counting as iterations
+        let mut started = 0;
+        while started < iteration_count {
+            let exhausted = matches!(
+                tracker,
+                Some(tracker) if tracker.is_exhausted(self.tree.node_count())
+            );
+            if started > 0 && exhausted {
+                break;
+            }
             if simulation_jobs < simulation_threshold * 10 {
                 let mut waiting_for_expansion = false;
-                let result = self.make_selection(node)?;
+                let result = self.make_selection(node, 1)?;
                 match result {
                     SelectionResult::Expansion => expansion_jobs += 1,
                     SelectionResult::Simulation => simulation_jobs += 1,
                     SelectionResult::Nothing => (),
                     SelectionResult::PendingExpansion => waiting_for_expansion = true,
                 }
-                if !waiting_for_expansion && expansion_jobs < expansion_threshold {
+                if !waiting_for_expansion {
+                    started += 1;
+                }
+                // Evaluations are gathered into batches, unlike simulations which run one by one
+                let gathering_evaluations =
+                    self.evaluates_leaves && simulation_jobs < simulation_threshold;
+                if !waiting_for_expansion
+                    && (expansion_jobs < expansion_threshold || gathering_evaluations)
+                {
                     continue;
                 }
             }
 
             if expansion_jobs > 0 {
-                let jobs = self.wait_for_expansion()?;
+                let jobs = self.wait_for_expansion(1)?;
                 expansion_jobs -= jobs;
                 simulation_jobs += jobs;
                 if simulation_jobs < simulation_threshold {
@@ -130,21 +244,33 @@ where
             expansion_jobs, simulation_jobs
         );
         while expansion_jobs > 0 {
-            let jobs = self.wait_for_expansion()?;
+            let jobs = self.wait_for_expansion(1)?;
             expansion_jobs -= jobs;
             simulation_jobs += jobs;
         }
         while simulation_jobs > 0 {
             simulation_jobs -= self.wait_for_simulation()?;
         }
-        Ok(())
+        Ok(started)
     }
 
+    /// Selects a leaf from the node and sends it to the workers, to be simulated the given number
+    /// of times at once.
     fn make_selection(
         &mut self,
         node: graph::NodeIndex<u32>,
+        simulations: usize,
     ) -> Result<SelectionResult, Box<dyn error::Error>> {
-        let selected = selection::select(&self.tree, node);
+        let mut path = selection::select(
+            &self.tree,
+            node,
+            self.tree_policy.as_ref(),
+            self.virtual_loss,
+        );
+        if self.evaluates_leaves {
+            return self.request_evaluation(path);
+        }
+        let selected = *path.last().unwrap();
         match expansion::ponder_expansion::<RuleSet>(&mut self.tree, selected, true) {
             expansion::ExpansionStatus::RequiresExpansion(state) => {
                 let request = expansion::Request::ExpansionRequest {
@@ -152,56 +278,121 @@ where
                     state,
                 };
                 self.expansion_request_sender.send(request)?;
-                backpropagation::backpropagate(&mut self.tree, selected, true, None);
+                backpropagation::add_pending(&mut self.tree, &path, simulations as f32);
+                self.expansion_paths.insert(selected, path);
                 Ok(SelectionResult::Expansion)
             }
             expansion::ExpansionStatus::NotVisited => {
                 let (to_simulate, state) =
                     simulation::fetch_random_child::<RuleSet>(&self.tree, selected, &mut self.rng);
+                if to_simulate != selected {
+                    path.push(to_simulate);
+                }
+                self.request_simulations(path, state, simulations)?;
+                Ok(SelectionResult::Simulation)
+            }
+            expansion::ExpansionStatus::Terminal(status) => {
+                backpropagation::backpropagate(&mut self.tree, &path, true, Some(status));
+                self.update_amaf(&path, &Vec::new(), status);
+                Ok(SelectionResult::Nothing)
+            }
+            expansion::ExpansionStatus::PendingExpansion => Ok(SelectionResult::PendingExpansion),
+        }
+    }
+
+    /// Sends the selected leaf to be evaluated and expanded, its node being marked as expanding
+    /// until the response comes back.
+    fn request_evaluation(
+        &mut self,
+        path: Vec<graph::NodeIndex<u32>>,
+    ) -> Result<SelectionResult, Box<dyn error::Error>> {
+        let selected = *path.last().unwrap();
+        // Nodes left with children only by unselectable ones have nothing to evaluate
+        if self.tree.neighbors(selected).next().is_some() {
+            return Ok(SelectionResult::Nothing);
+        }
+        match expansion::ponder_expansion::<RuleSet>(&mut self.tree, selected, false) {
+            expansion::ExpansionStatus::RequiresExpansion(state) => {
                 let request = simulation::Request::SimulationRequest {
-                    node_index: to_simulate,
+                    node_index: selected,
                     state,
                 };
                 self.simulation_request_sender.send(request)?;
-                backpropagation::backpropagate(&mut self.tree, selected, true, None);
+                backpropagation::add_pending(&mut self.tree, &path, 1.0);
+                self.simulation_paths
+                    .entry(selected)
+                    .or_default()
+                    .push(path);
                 Ok(SelectionResult::Simulation)
             }
             expansion::ExpansionStatus::Terminal(status) => {
-                backpropagation::backpropagate(&mut self.tree, selected, true, Some(status));
+                backpropagation::backpropagate(&mut self.tree, &path, true, Some(status));
+                self.update_amaf(&path, &Vec::new(), status);
                 Ok(SelectionResult::Nothing)
             }
             expansion::ExpansionStatus::PendingExpansion => Ok(SelectionResult::PendingExpansion),
+            expansion::ExpansionStatus::NotVisited => unreachable!(),
+        }
+    }
+
+    /// Sends the last node of the path to be simulated the given number of times, the nodes of
+    /// the path counting the simulations in progress.
+    fn request_simulations(
+        &mut self,
+        path: Vec<graph::NodeIndex<u32>>,
+        state: RuleSet::State,
+        simulations: usize,
+    ) -> Result<(), Box<dyn error::Error>> {
+        backpropagation::add_pending(&mut self.tree, &path, simulations as f32);
+        let node_index = *path.last().unwrap();
+        let paths = self.simulation_paths.entry(node_index).or_default();
+        paths.extend((0..simulations).map(|_| path.clone()));
+        for _ in 0..simulations {
+            let request = simulation::Request::SimulationRequest {
+                node_index,
+                state: state.clone(),
+            };
+            self.simulation_request_sender.send(request)?;
         }
+        Ok(())
     }
 
     fn handle_expansion(
         &mut self,
         node_index: graph::NodeIndex<u32>,
         successors: Vec<expansion::Play<RuleSet>>,
+        simulations: usize,
     ) -> Result<(), Box<dyn error::Error>> {
         for successor in successors {
-            expansion::save_expansion(&mut self.tree, node_index, successor);
+            expansion::save_successor(
+                &mut self.tree,
+                &self.ruleset,
+                self.transpositions.as_mut(),
+                node_index,
+                successor,
+            );
         }
+        let mut path = self.expansion_paths.remove(&node_index).unwrap();
         let (to_simulate, state) =
             simulation::fetch_random_child::<RuleSet>(&self.tree, node_index, &mut self.rng);
-        let request = simulation::Request::SimulationRequest {
-            node_index: to_simulate,
-            state,
-        };
-        self.simulation_request_sender.send(request)?;
-        backpropagation::update_tallies(&mut self.tree, to_simulate, true, None);
+        // The path already counts the iterations in progress since its selection
+        backpropagation::add_pending(&mut self.tree, &path, -(simulations as f32));
+        if to_simulate != node_index {
+            path.push(to_simulate);
+        }
+        self.request_simulations(path, state, simulations)?;
         Ok(())
     }
 
-    fn wait_for_expansion(&mut self) -> Result<isize, Box<dyn error::Error>> {
+    fn wait_for_expansion(&mut self, simulations: usize) -> Result<isize, Box<dyn error::Error>> {
         let response = self.expansion_response_receiver.recv()?;
-        self.handle_expansion(response.node_index, response.successors)?;
+        self.handle_expansion(response.node_index, response.successors, simulations)?;
         let mut handled = 1;
         loop {
             match self.expansion_response_receiver.try_recv() {
                 Ok(response) => {
                     handled += 1;
-                    self.handle_expansion(response.node_index, response.successors)?;
+                    self.handle_expansion(response.node_index, response.successors, simulations)?;
                 }
                 Err(channel::TryRecvError::Empty) => break,
                 Err(error) => return Err(Box::new(error)),
@@ -212,23 +403,13 @@ where
 
     fn wait_for_simulation(&mut self) -> Result<isize, Box<dyn error::Error>> {
         let response = self.simulation_response_receiver.recv()?;
-        backpropagation::backpropagate(
-            &mut self.tree,
-            response.node_index,
-            false,
-            Some(response.status),
-        );
+        self.handle_simulation(response);
         let mut handled = 1;
         loop {
             match self.simulation_response_receiver.try_recv() {
                 Ok(response) => {
                     handled += 1;
-                    backpropagation::backpropagate(
-                        &mut self.tree,
-                        response.node_index,
-                        false,
-                        Some(response.status),
-                    );
+                    self.handle_simulation(response);
                 }
                 Err(channel::TryRecvError::Empty) => break,
                 Err(error) => return Err(Box::new(error)),
@@ -237,33 +418,148 @@ where
         Ok(handled)
     }
 
-    fn iterate_sequential(&mut self) -> Result<(), Box<dyn error::Error>> {
+    fn handle_simulation(&mut self, response: simulation::Response<RuleSet>) {
+        let path = self
+            .simulation_paths
+            .get_mut(&response.node_index)
+            .unwrap()
+            .pop()
+            .unwrap();
+        backpropagation::add_pending(&mut self.tree, &path, -1.0);
+        if self.evaluates_leaves {
+            self.tree
+                .node_weight_mut(response.node_index)
+                .unwrap()
+                .expanding = false;
+        }
+        match response.evaluation {
+            Some(evaluation) => {
+                for (successor, prior) in evaluation.successors {
+                    let child_index = expansion::save_successor(
+                        &mut self.tree,
+                        &self.ruleset,
+                        self.transpositions.as_mut(),
+                        response.node_index,
+                        successor,
+                    );
+                    // Children merged through transpositions have one prior per parent
+                    let edge_index = self
+                        .tree
+                        .find_edge(response.node_index, child_index)
+                        .unwrap();
+                    self.tree.edge_weight_mut(edge_index).unwrap().prior = prior;
+                }
+                backpropagation::backpropagate(&mut self.tree, &path, true, None);
+                backpropagation::backpropagate_value(
+                    &mut self.tree,
+                    &path,
+                    evaluation.player,
+                    evaluation.value,
+                );
+            }
+            None => {
+                backpropagation::backpropagate(&mut self.tree, &path, true, Some(response.status));
+                self.update_amaf(&path, &response.playout, response.status);
+            }
+        }
+    }
+
+    fn update_amaf(
+        &mut self,
+        path: &[graph::NodeIndex<u32>],
+        playout: &simulation::Playout<RuleSet>,
+        status: rulesets::Status,
+    ) {
+        if let Some(update_amaf) = self.amaf_update {
+            update_amaf(&mut self.tree, &self.ruleset, path, playout, status);
+        }
+    }
+
+    /// Runs an iteration and waits for its outcome, the selected leaf being simulated the given
+    /// number of times at once.
+    fn iterate_sequential(&mut self, simulations: usize) -> Result<(), Box<dyn error::Error>> {
         let node = match self.root {
             Some(node) => node,
             None => {
                 return Ok(());
             }
         };
-        match self.make_selection(node)? {
+        // Evaluating a leaf several times would give the same result
+        let simulations = if self.evaluates_leaves {
+            1
+        } else {
+            simulations
+        };
+        match self.make_selection(node, simulations)? {
             SelectionResult::Expansion => {
-                self.wait_for_expansion()?;
+                self.wait_for_expansion(simulations)?;
             }
             SelectionResult::Simulation => (),
             SelectionResult::Nothing => return Ok(()),
             SelectionResult::PendingExpansion => unreachable!(),
         }
-        self.wait_for_simulation()?;
+        let mut remaining = simulations as isize;
+        while remaining > 0 {
+            remaining -= self.wait_for_simulation()?;
+        }
+        Ok(())
+    }
+
+    fn search(
+        &mut self,
+        budget: budget::Budget,
+        expansion_workers: usize,
+        simulation_workers: usize,
+    ) -> Result<(), Box<dyn error::Error>> {
+        let root = match self.root {
+            Some(root) => root,
+            None => return Ok(()),
+        };
+        if self.tree.node_weight(root).unwrap().game_status() != rulesets::Status::Ongoing {
+            return Ok(());
+        }
+        // Leaf parallelism waits for the simulations of each iteration anyway
+        let leaf_parallelism = self.parallelism == parallelism::Parallelism::Leaf;
+        let sequential = expansion_workers == 0 && simulation_workers == 0 || leaf_parallelism;
+        let simulations = if leaf_parallelism {
+            simulation_workers.max(1)
+        } else {
+            1
+        };
+        let mut tracker = budget::Tracker::new(budget);
+        while !stopping::should_stop(&self.tree, root, &tracker) {
+            if sequential {
+                self.iterate_sequential(simulations)?;
+                tracker.add_iterations(1);
+                continue;
+            }
+            let count = match tracker.remaining_iterations() {
+                Some(remaining) => remaining.clamp(1, SEARCH_CHUNK),
+                None => SEARCH_CHUNK,
+            };
+            let started = self.iterate_concurrent(
+                count,
+                Some(&tracker),
+                expansion_workers,
+                simulation_workers,
+            )?;
+            tracker.add_iterations(started);
+        }
         Ok(())
     }
 
-    fn play_scores(&self) -> Option<Vec<ai::PlyConsideration<RuleSet::Ply>>> {
+    fn play_scores(&self) -> Option<Vec<(ai::PlyConsideration<RuleSet::Ply>, f32)>> {
         let parent = match self.root {
             Some(node) => node,
             None => {
                 return None;
             }
         };
-        Some(analysis::play_scores::<RuleSet>(&self.tree, parent))
+        Some(analysis::weighted_play_scores(
+            &self.ruleset,
+            &self.tree,
+            parent,
+        ))
     }
 
     pub fn run(&mut self) -> Result<(), Box<dyn error::Error>> {
@@ -272,9 +568,26 @@ where
                 requests::Request::SetState(state) => {
                     self.set_state(state);
                 }
+                requests::Request::SetTreePolicy {
+                    policy,
+                    amaf_update,
+                } => {
+                    self.tree_policy = policy;
+                    self.amaf_update = amaf_update;
+                }
+                requests::Request::SetVirtualLoss(virtual_loss) => {
+                    self.virtual_loss = virtual_loss;
+                }
+                requests::Request::SetParallelism(parallelism) => {
+                    self.parallelism = parallelism;
+                }
+                requests::Request::SetTranspositions(enabled) => {
+                    self.merges_transpositions = enabled;
+                    self.reset_transpositions();
+                }
                 requests::Request::IterateSequentially { count } => {
                     for _ in 0..count {
-                        self.iterate_sequential()?;
+                        self.iterate_sequential(1)?;
                     }
                 }
                 requests::Request::IterateParallel {
@@ -282,13 +595,26 @@ where
                     expansions_to_do,
                     simulations_to_do,
                 } => {
-                    self.iterate_concurrent(count, expansions_to_do, simulations_to_do)?;
+                    self.iterate(count, expansions_to_do, simulations_to_do)?;
+                }
+                requests::Request::Search {
+                    budget,
+                    expansions_to_do,
+                    simulations_to_do,
+                } => {
+                    self.search(budget, expansions_to_do, simulations_to_do)?;
                 }
                 requests::Request::ListConsiderations => {
                     let result = self.play_scores().unwrap();
-                    let response = responses::Response {
-                        considerations: result,
+                    let response = responses::Response::Considerations(result);
+                    self.master_response_sender.send(response)?;
+                }
+                requests::Request::ListVisits => {
+                    let result = match self.root {
+                        Some(root) => analysis::node_visits::<RuleSet>(&self.tree, root),
+                        None => Vec::new(),
                     };
+                    let response = responses::Response::Visits(result);
                     self.master_response_sender.send(response)?;
                 }
                 requests::Request::Stop => break,
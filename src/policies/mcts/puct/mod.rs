@@ -1,6 +1,8 @@
 mod master;
 mod orchestrator;
+mod parallelism;
 mod requests;
 mod responses;
 
 pub use orchestrator::Orchestrator;
+pub use parallelism::Parallelism;
@@ -1,6 +1,14 @@
+use super::super::analysis;
+use super::super::budget;
 use super::super::expansion;
+use super::super::leaf_evaluator;
+use super::super::rave;
+use super::super::rollout_policy;
 use super::super::simulation;
+use super::super::tree_policy;
+use super::super::virtual_loss;
 use super::master;
+use super::parallelism;
 use super::requests;
 use super::responses;
 use crate::interface::ai;
@@ -8,28 +16,83 @@ use crate::interface::rulesets;
 use crossbeam::channel;
 use std::error;
 use std::hash;
-use std::mem;
+use std::sync;
 use std::thread;
 
-pub struct Orchestrator<RuleSet>
+/// Creates the rollout policy of each simulation worker
+type RolloutPolicyFactory<RuleSet> =
+    Box<dyn Fn() -> Box<dyn rollout_policy::RolloutPolicy<RuleSet>> + Send>;
+
+/// Creates the tree policy of each master
+type TreePolicyFactory<State> = Box<dyn Fn() -> Box<dyn tree_policy::TreePolicy<State>> + Send>;
+
+/// Master searching a tree, along with the workers expanding and simulating its leaves
+struct TreeSearch<RuleSet>
 where
     RuleSet: rulesets::HasStatesWithSymmetries
         + rulesets::Deterministic
         + rulesets::TurnByTurn
         + 'static,
     RuleSet::Ply: Eq + Ord + hash::Hash,
-    RuleSet::State: Eq,
+    RuleSet::State: Eq + Ord + hash::Hash,
 {
-    ruleset: RuleSet,
-    master_handle: Option<thread::JoinHandle<()>>,
+    master_handle: thread::JoinHandle<()>,
     master_request_sender: channel::Sender<requests::Request<RuleSet>>,
-    master_request_receiver: channel::Receiver<requests::Request<RuleSet>>,
-    master_response_sender: channel::Sender<responses::Response<RuleSet>>,
     master_response_receiver: channel::Receiver<responses::Response<RuleSet>>,
     expansion_pool: expansion::Pool<RuleSet>,
     simulation_pool: simulation::Pool<RuleSet>,
 }
 
+impl<RuleSet> TreeSearch<RuleSet>
+where
+    RuleSet: rulesets::HasStatesWithSymmetries
+        + rulesets::Deterministic
+        + rulesets::TurnByTurn
+        + 'static,
+    RuleSet::Ply: Eq + Ord + hash::Hash,
+    RuleSet::State: Eq + Ord + hash::Hash,
+{
+    /// Stops the master and its workers, and returns the number of expansions and simulations
+    /// they did.
+    fn stop(mut self) -> Result<(usize, usize), Box<dyn error::Error>> {
+        self.master_request_sender.send(requests::Request::Stop)?;
+        self.master_handle.join().unwrap();
+        let simulation_count = self.simulation_pool.stop()?;
+        let expansion_count = self.expansion_pool.stop()?;
+        Ok((expansion_count, simulation_count))
+    }
+}
+
+/// Runs the searches of the MCTS in their own threads.
+///
+/// Each tree is searched by its own master and workers, root parallelism running several of
+/// them at once, whose play scores are merged when listed. The settings are kept across starts,
+/// and given to the masters as they start.
+pub struct Orchestrator<RuleSet>
+where
+    RuleSet: rulesets::HasStatesWithSymmetries
+        + rulesets::Deterministic
+        + rulesets::TurnByTurn
+        + 'static,
+    RuleSet::Ply: Eq + Ord + hash::Hash,
+    RuleSet::State: Eq + Ord + hash::Hash,
+{
+    ruleset: RuleSet,
+    searches: Vec<TreeSearch<RuleSet>>,
+    expansion_workers: usize,
+    simulation_workers: usize,
+    /// Expansions and simulations of the searches stopped since the start
+    operation_counts: (usize, usize),
+    rollout_policy: RolloutPolicyFactory<RuleSet>,
+    leaf_evaluator: Option<(leaf_evaluator::Shared<RuleSet>, usize)>,
+    tree_policy: TreePolicyFactory<RuleSet::State>,
+    amaf_update: Option<rave::AmafUpdate<RuleSet>>,
+    virtual_loss: virtual_loss::VirtualLoss,
+    parallelism: parallelism::Parallelism,
+    transpositions: bool,
+    state: Option<RuleSet::State>,
+}
+
 impl<RuleSet> Orchestrator<RuleSet>
 where
     RuleSet: rulesets::HasStatesWithSymmetries
@@ -37,35 +100,131 @@ where
         + rulesets::TurnByTurn
         + 'static,
     RuleSet::Ply: Eq + Ord + hash::Hash,
-    RuleSet::State: Eq,
+    RuleSet::State: Eq + Ord + hash::Hash,
 {
     pub fn new(ruleset: RuleSet) -> Orchestrator<RuleSet> {
-        let expansion_pool = expansion::Pool::new();
-        let simulation_pool = simulation::Pool::new();
-        let (master_request_sender, master_request_receiver) = channel::unbounded();
-        let (master_response_sender, master_response_receiver) = channel::unbounded();
         Orchestrator {
             ruleset,
-            master_request_receiver,
-            master_request_sender,
-            master_response_receiver,
-            master_response_sender,
-            expansion_pool,
-            simulation_pool,
-            master_handle: None,
+            searches: Vec::new(),
+            expansion_workers: 0,
+            simulation_workers: 0,
+            operation_counts: (0, 0),
+            rollout_policy: Box::new(|| Box::new(rollout_policy::Uniform::new())),
+            leaf_evaluator: None,
+            tree_policy: Box::new(|| Box::new(tree_policy::UCB1::default())),
+            amaf_update: None,
+            virtual_loss: virtual_loss::VirtualLoss::default(),
+            parallelism: parallelism::Parallelism::default(),
+            transpositions: false,
+            state: None,
         }
     }
 
-    pub fn set_state(&self, state: RuleSet::State) -> Result<(), Box<dyn error::Error>> {
-        let request = requests::Request::SetState(state);
-        self.master_request_sender.send(request)?;
-        Ok(())
+    /// Sets how to create the rollout policy of each simulation worker spawned afterwards,
+    /// uniformly random plies being played by default.
+    pub fn set_rollout_policy<Factory, Policy>(&mut self, factory: Factory)
+    where
+        Factory: Fn() -> Policy + Send + 'static,
+        Policy: rollout_policy::RolloutPolicy<RuleSet> + 'static,
+    {
+        self.rollout_policy = Box::new(move || Box::new(factory()));
+    }
+
+    /// Evaluates the leaves with the evaluator from the next start on, instead of simulating
+    /// them, the requests being evaluated by batches of at most the given size.
+    ///
+    /// A single worker of each tree runs the evaluator, the number of simulations to do setting
+    /// how many leaves are pending at once.
+    pub fn set_leaf_evaluator<Evaluator>(&mut self, evaluator: Evaluator, batch_size: usize)
+    where
+        Evaluator: leaf_evaluator::LeafEvaluator<RuleSet> + 'static,
+    {
+        self.leaf_evaluator = Some((sync::Arc::new(sync::Mutex::new(evaluator)), batch_size));
+    }
+
+    pub fn set_state(&mut self, state: RuleSet::State) -> Result<(), Box<dyn error::Error>> {
+        self.state = Some(state.clone());
+        self.send(|| requests::Request::SetState(state.clone()))
+    }
+
+    /// Sets how to create the policy selecting the children to descend into, UCB1 by default.
+    pub fn set_tree_policy<Factory, Policy>(
+        &mut self,
+        factory: Factory,
+    ) -> Result<(), Box<dyn error::Error>>
+    where
+        Factory: Fn() -> Policy + Send + 'static,
+        Policy: tree_policy::TreePolicy<RuleSet::State> + 'static,
+    {
+        self.tree_policy = Box::new(move || Box::new(factory()));
+        self.amaf_update = None;
+        self.send(|| self.tree_policy_request())
+    }
+
+    /// Sets how to create a policy selecting the children to descend into from their
+    /// all-moves-as-first statistics, such as `Rave`, which the masters then gather.
+    pub fn set_amaf_tree_policy<Factory, Policy>(
+        &mut self,
+        factory: Factory,
+    ) -> Result<(), Box<dyn error::Error>>
+    where
+        RuleSet: rulesets::EncodableState,
+        Factory: Fn() -> Policy + Send + 'static,
+        Policy: tree_policy::TreePolicy<RuleSet::State> + 'static,
+    {
+        self.tree_policy = Box::new(move || Box::new(factory()));
+        self.amaf_update = Some(rave::update_amaf::<RuleSet>);
+        self.send(|| self.tree_policy_request())
+    }
+
+    /// Sets how the statistics of the nodes are adjusted for the iterations in progress through
+    /// them, a constant loss of one visit by default.
+    pub fn set_virtual_loss(
+        &mut self,
+        virtual_loss: virtual_loss::VirtualLoss,
+    ) -> Result<(), Box<dyn error::Error>> {
+        self.virtual_loss = virtual_loss;
+        self.send(|| requests::Request::SetVirtualLoss(virtual_loss))
+    }
+
+    /// Sets how the iterations are spread over the workers, tree parallelism by default.
+    ///
+    /// With leaf parallelism, the number of simulations to do sets how many times each leaf is
+    /// simulated. With root parallelism, the workers, the iterations and the nodes are shared
+    /// by the trees, each one being searched with tree parallelism. Changing the number of trees
+    /// once started stops or starts the searches of the trees in excess or missing, the new ones
+    /// searching the current state from scratch.
+    pub fn set_parallelism(
+        &mut self,
+        parallelism: parallelism::Parallelism,
+    ) -> Result<(), Box<dyn error::Error>> {
+        self.parallelism = parallelism;
+        if !self.searches.is_empty() {
+            let trees = parallelism.trees();
+            while self.searches.len() > trees {
+                let (expansion_count, simulation_count) = self.searches.pop().unwrap().stop()?;
+                self.operation_counts.0 += expansion_count;
+                self.operation_counts.1 += simulation_count;
+            }
+            while self.searches.len() < trees {
+                let search = self.spawn_search()?;
+                self.searches.push(search);
+            }
+        }
+        let tree_parallelism = self.tree_parallelism();
+        self.send(|| requests::Request::SetParallelism(tree_parallelism))
+    }
+
+    /// Sets whether plies reaching a state already in the tree lead to its node, merging
+    /// transpositions so that their statistics are shared, which is disabled by default.
+    pub fn set_transpositions(&mut self, enabled: bool) -> Result<(), Box<dyn error::Error>> {
+        self.transpositions = enabled;
+        self.send(|| requests::Request::SetTranspositions(enabled))
     }
 
     pub fn iterate_sequentially(&self, count: usize) -> Result<(), Box<dyn error::Error>> {
-        let request = requests::Request::IterateSequentially { count };
-        self.master_request_sender.send(request)?;
-        Ok(())
+        let count = self.share(count);
+        self.send(|| requests::Request::IterateSequentially { count })
     }
 
     pub fn iterate_parallel(
@@ -74,80 +233,447 @@ where
         expansions_to_do: usize,
         simulations_to_do: usize,
     ) -> Result<(), Box<dyn error::Error>> {
-        let request = requests::Request::IterateParallel {
+        let count = self.share(count);
+        let expansions_to_do = self.share(expansions_to_do);
+        let simulations_to_do = self.share(simulations_to_do);
+        self.send(|| requests::Request::IterateParallel {
             count,
             expansions_to_do,
             simulations_to_do,
-        };
-        self.master_request_sender.send(request)?;
-        Ok(())
+        })
     }
 
+    /// Searches until the budget is spent or, with early stopping, until the decision is settled,
+    /// running sequentially when no expansion nor simulation is to be done concurrently.
+    pub fn search(
+        &self,
+        budget: budget::Budget,
+        expansions_to_do: usize,
+        simulations_to_do: usize,
+    ) -> Result<(), Box<dyn error::Error>> {
+        let budget = budget.share(self.parallelism.trees());
+        let expansions_to_do = self.share(expansions_to_do);
+        let simulations_to_do = self.share(simulations_to_do);
+        self.send(|| requests::Request::Search {
+            budget,
+            expansions_to_do,
+            simulations_to_do,
+        })
+    }
+
+    /// Returns the scores of the plies of the root, merged over the trees with root
+    /// parallelism, or `None` when not started.
     pub fn ply_considerations(
         &self,
     ) -> Result<Option<Vec<ai::PlyConsideration<RuleSet::Ply>>>, Box<dyn error::Error>> {
-        let request = requests::Request::ListConsiderations;
-        self.master_request_sender.send(request)?;
-        let response = self.master_response_receiver.recv()?;
-        Ok(Some(response.considerations))
+        if self.searches.is_empty() {
+            return Ok(None);
+        }
+        self.send(|| requests::Request::ListConsiderations)?;
+        let mut trees = Vec::new();
+        for search in &self.searches {
+            match search.master_response_receiver.recv()? {
+                responses::Response::Considerations(considerations) => trees.push(considerations),
+                _ => return Err("unexpected response from the master".into()),
+            }
+        }
+        let considerations = if trees.len() == 1 {
+            trees
+                .pop()
+                .unwrap()
+                .into_iter()
+                .map(|(consideration, _)| consideration)
+                .collect()
+        } else {
+            analysis::merge_play_scores(trees)
+        };
+        Ok(Some(considerations))
     }
 
+    /// Returns the visits of the nodes of each tree along with the visits of the edges leading to
+    /// them, the root coming first, no tree being listed when not started.
+    pub fn node_visits(&self) -> Result<Vec<analysis::NodeVisits>, Box<dyn error::Error>> {
+        self.send(|| requests::Request::ListVisits)?;
+        let mut trees = Vec::new();
+        for search in &self.searches {
+            match search.master_response_receiver.recv()? {
+                responses::Response::Visits(visits) => trees.push(visits),
+                _ => return Err("unexpected response from the master".into()),
+            }
+        }
+        Ok(trees)
+    }
+
+    /// Starts a master with its workers for each tree, the workers being shared by the trees
+    /// with root parallelism.
     pub fn start(
         &mut self,
         expansion_workers: usize,
         simulation_workers: usize,
     ) -> Result<(), Box<dyn error::Error>> {
-        for _ in 0..expansion_workers {
-            self.expansion_pool.spawn(self.ruleset.clone())?;
+        self.expansion_workers = expansion_workers;
+        self.simulation_workers = simulation_workers;
+        for _ in 0..self.parallelism.trees() {
+            let search = self.spawn_search()?;
+            self.searches.push(search);
         }
-        for _ in 0..simulation_workers {
-            self.simulation_pool.spawn(self.ruleset.clone())?;
-        }
-        self.spawn_master()?;
         Ok(())
     }
 
     pub fn stop(&mut self) -> Result<(usize, usize), Box<dyn error::Error>> {
-        self.stop_master()?;
-        let simulation_count = self.simulation_pool.stop()?;
-        let expansion_count = self.expansion_pool.stop()?;
+        let (mut expansion_count, mut simulation_count) = self.operation_counts;
+        self.operation_counts = (0, 0);
+        while let Some(search) = self.searches.pop() {
+            let (expansions, simulations) = search.stop()?;
+            expansion_count += expansions;
+            simulation_count += simulations;
+        }
         Ok((expansion_count, simulation_count))
     }
 
-    fn stop_master(&mut self) -> Result<(), Box<dyn error::Error>> {
-        let request = requests::Request::Stop;
-        self.master_request_sender.send(request)?;
-        let master_handle = mem::replace(&mut self.master_handle, None);
-        if let Some(handle) = master_handle {
-            handle.join().unwrap();
+    /// Sends a request to the master of each tree.
+    fn send<Request>(&self, request: Request) -> Result<(), Box<dyn error::Error>>
+    where
+        Request: Fn() -> requests::Request<RuleSet>,
+    {
+        for search in &self.searches {
+            search.master_request_sender.send(request())?;
         }
         Ok(())
     }
 
-    fn spawn_master(&mut self) -> Result<(), Box<dyn error::Error>> {
-        let worker_name = "mcts-master";
+    /// Returns the share of each tree of the given amount of work.
+    fn share(&self, amount: usize) -> usize {
+        amount.div_ceil(self.parallelism.trees())
+    }
+
+    /// Returns how each master spreads its iterations, trees being searched with tree
+    /// parallelism under root parallelism.
+    fn tree_parallelism(&self) -> parallelism::Parallelism {
+        match self.parallelism {
+            parallelism::Parallelism::Root { .. } => parallelism::Parallelism::Tree,
+            parallelism => parallelism,
+        }
+    }
+
+    fn tree_policy_request(&self) -> requests::Request<RuleSet> {
+        requests::Request::SetTreePolicy {
+            policy: (self.tree_policy)(),
+            amaf_update: self.amaf_update,
+        }
+    }
+
+    fn spawn_search(&self) -> Result<TreeSearch<RuleSet>, Box<dyn error::Error>> {
+        let mut expansion_pool = expansion::Pool::new();
+        for _ in 0..self.share(self.expansion_workers) {
+            expansion_pool.spawn(self.ruleset.clone())?;
+        }
+        let mut simulation_pool = simulation::Pool::new();
+        let evaluates_leaves = match &self.leaf_evaluator {
+            Some((evaluator, batch_size)) => {
+                simulation_pool.spawn_evaluator(
+                    self.ruleset.clone(),
+                    evaluator.clone(),
+                    *batch_size,
+                )?;
+                true
+            }
+            None => {
+                for _ in 0..self.share(self.simulation_workers) {
+                    simulation_pool.spawn(self.ruleset.clone(), (self.rollout_policy)())?;
+                }
+                false
+            }
+        };
+
+        let worker_name = format!("mcts-master-{}", self.searches.len());
         let ruleset = self.ruleset.clone();
-        let master_request_receiver = self.master_request_receiver.clone();
-        let master_response_sender = self.master_response_sender.clone();
-        let expansion_request_sender = self.expansion_pool.request_sender.clone();
-        let expansion_response_receiver = self.expansion_pool.response_receiver.clone();
-        let simulation_request_sender = self.simulation_pool.request_sender.clone();
-        let simulation_response_receiver = self.simulation_pool.response_receiver.clone();
-        let handle = thread::Builder::new()
-            .name(worker_name.to_string())
-            .spawn(move || {
-                let mut master = master::Master::new(
-                    ruleset,
-                    master_request_receiver,
-                    master_response_sender,
-                    expansion_request_sender,
-                    expansion_response_receiver,
-                    simulation_request_sender,
-                    simulation_response_receiver,
-                );
-                master.run().unwrap();
-            })?;
-        self.master_handle = Some(handle);
+        let (master_request_sender, master_request_receiver) = channel::unbounded();
+        let (master_response_sender, master_response_receiver) = channel::unbounded();
+        let expansion_request_sender = expansion_pool.request_sender.clone();
+        let expansion_response_receiver = expansion_pool.response_receiver.clone();
+        let simulation_request_sender = simulation_pool.request_sender.clone();
+        let simulation_response_receiver = simulation_pool.response_receiver.clone();
+        let master_handle = thread::Builder::new().name(worker_name).spawn(move || {
+            let mut master = master::Master::new(
+                ruleset,
+                master_request_receiver,
+                master_response_sender,
+                expansion_request_sender,
+                expansion_response_receiver,
+                simulation_request_sender,
+                simulation_response_receiver,
+            );
+            master.set_evaluates_leaves(evaluates_leaves);
+            master.run().unwrap();
+        })?;
+
+        let settings = vec![
+            self.tree_policy_request(),
+            requests::Request::SetVirtualLoss(self.virtual_loss),
+            requests::Request::SetParallelism(self.tree_parallelism()),
+            requests::Request::SetTranspositions(self.transpositions),
+        ];
+        for request in settings {
+            master_request_sender.send(request)?;
+        }
+        if let Some(state) = &self.state {
+            master_request_sender.send(requests::Request::SetState(state.clone()))?;
+        }
+        Ok(TreeSearch {
+            master_handle,
+            master_request_sender,
+            master_response_receiver,
+            expansion_pool,
+            simulation_pool,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::rulesets::connectn;
+    use more_asserts::assert_gt;
+
+    macro_rules! search_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<(), Box<dyn error::Error>> {
+                    let (expansions_to_do, simulations_to_do) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let mut orchestrator = Orchestrator::new(ruleset.clone());
+                    orchestrator.start(1, 1)?;
+                    orchestrator.set_state(ruleset.initial_state())?;
+                    orchestrator.search(budget::Budget::iterations(300), expansions_to_do, simulations_to_do)?;
+                    let considerations = orchestrator.ply_considerations()?.unwrap();
+                    orchestrator.stop()?;
+                    assert!(!considerations.is_empty());
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    search_tests! {
+        sequential_search: (0, 0),
+        parallel_search: (1, 1),
+    }
+
+    macro_rules! exhausted_budget_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<(), Box<dyn error::Error>> {
+                    let (expansions_to_do, simulations_to_do) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let mut orchestrator = Orchestrator::new(ruleset.clone());
+                    orchestrator.start(1, 1)?;
+                    orchestrator.set_state(ruleset.initial_state())?;
+                    // The root is expanded even though the budget is spent from the start
+                    orchestrator.search(budget::Budget::nodes(1), expansions_to_do, simulations_to_do)?;
+                    let considerations = orchestrator.ply_considerations()?.unwrap();
+                    orchestrator.stop()?;
+                    assert!(!considerations.is_empty());
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    exhausted_budget_tests! {
+        exhausted_sequential_search: (0, 0),
+        exhausted_parallel_search: (1, 1),
+    }
+
+    #[test]
+    fn test_budget_within_chunk() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let mut orchestrator = Orchestrator::new(ruleset.clone());
+        orchestrator.start(1, 2)?;
+        orchestrator.set_state(ruleset.initial_state())?;
+        orchestrator.search(budget::Budget::nodes(20), 1, 2)?;
+        let mut trees = orchestrator.node_visits()?;
+        orchestrator.stop()?;
+        let visits = trees.pop().unwrap();
+        // The budget is checked between iterations, not only once per chunk of them, so the tree
+        // outgrows it by the expansions in flight at most
+        assert!(visits.len() < 50);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rollout_policy() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let mut orchestrator = Orchestrator::new(ruleset);
+        orchestrator.set_rollout_policy(rollout_policy::Decisive::new);
+        orchestrator.start(1, 2)?;
+        let state = connectn::TicTacToeState::from_indices(&[4, 1], &[0, 5], 1);
+        orchestrator.set_state(state)?;
+        orchestrator.search(budget::Budget::iterations(500), 1, 2)?;
+        let considerations = orchestrator.ply_considerations()?.unwrap();
+        orchestrator.stop()?;
+        assert_eq!(considerations[0].ply, connectn::Ply::new(7));
+        Ok(())
+    }
+
+    #[test]
+    fn test_amaf_tree_policy() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let mut orchestrator = Orchestrator::new(ruleset);
+        orchestrator.set_amaf_tree_policy(tree_policy::Rave::<tree_policy::UCB1>::default)?;
+        orchestrator.start(1, 2)?;
+        let state = connectn::TicTacToeState::from_indices(&[4, 1], &[0, 5], 1);
+        orchestrator.set_state(state)?;
+        orchestrator.search(budget::Budget::iterations(500), 1, 2)?;
+        let considerations = orchestrator.ply_considerations()?.unwrap();
+        orchestrator.stop()?;
+        assert_eq!(considerations[0].ply, connectn::Ply::new(7));
+        Ok(())
+    }
+
+    macro_rules! parallelism_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<(), Box<dyn error::Error>> {
+                    let (parallelism, virtual_loss) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let mut orchestrator = Orchestrator::new(ruleset);
+                    orchestrator.start(1, 2)?;
+                    orchestrator.set_parallelism(parallelism)?;
+                    orchestrator.set_virtual_loss(virtual_loss)?;
+                    let state = connectn::TicTacToeState::from_indices(&[4, 1], &[0, 5], 1);
+                    orchestrator.set_state(state)?;
+                    orchestrator.search(budget::Budget::iterations(1000), 1, 2)?;
+                    let considerations = orchestrator.ply_considerations()?.unwrap();
+                    orchestrator.stop()?;
+                    assert_eq!(considerations[0].ply, connectn::Ply::new(7));
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    parallelism_tests! {
+        tree_constant_loss: (parallelism::Parallelism::Tree, virtual_loss::VirtualLoss::Constant(3.0)),
+        tree_unobserved_samples: (parallelism::Parallelism::Tree, virtual_loss::VirtualLoss::UnobservedSamples),
+        tree_no_loss: (parallelism::Parallelism::Tree, virtual_loss::VirtualLoss::None),
+        leaf: (parallelism::Parallelism::Leaf, virtual_loss::VirtualLoss::default()),
+        root: (parallelism::Parallelism::Root { trees: 3 }, virtual_loss::VirtualLoss::default()),
+    }
+
+    #[test]
+    fn test_root_parallelism() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let mut orchestrator = Orchestrator::new(ruleset);
+        orchestrator.set_parallelism(parallelism::Parallelism::Root { trees: 3 })?;
+        orchestrator.start(1, 3)?;
+        // Each tree has its own master and workers
+        assert_eq!(orchestrator.searches.len(), 3);
+        let state = connectn::TicTacToeState::from_indices(&[4, 1], &[0, 5], 1);
+        orchestrator.set_state(state)?;
+        orchestrator.search(budget::Budget::iterations(900), 1, 3)?;
+        let considerations = orchestrator.ply_considerations()?.unwrap();
+        assert_eq!(considerations[0].ply, connectn::Ply::new(7));
+        // Trees in excess are stopped, the remaining one keeping its statistics
+        orchestrator.set_parallelism(parallelism::Parallelism::Tree)?;
+        assert_eq!(orchestrator.searches.len(), 1);
+        let considerations = orchestrator.ply_considerations()?.unwrap();
+        assert_eq!(considerations[0].ply, connectn::Ply::new(7));
+        let (expansion_count, simulation_count) = orchestrator.stop()?;
+        assert_gt!(expansion_count, 0);
+        assert_gt!(simulation_count, 0);
+        assert!(orchestrator.ply_considerations()?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_transpositions() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let mut orchestrator = Orchestrator::new(ruleset);
+        orchestrator.start(1, 2)?;
+        orchestrator.set_transpositions(true)?;
+        let state = connectn::TicTacToeState::from_indices(&[4, 1], &[0, 5], 1);
+        orchestrator.set_state(state)?;
+        orchestrator.search(budget::Budget::iterations(1000), 1, 2)?;
+        let considerations = orchestrator.ply_considerations()?.unwrap();
+        orchestrator.stop()?;
+        assert_eq!(considerations[0].ply, connectn::Ply::new(7));
+        Ok(())
+    }
+
+    macro_rules! transposition_visits_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<(), Box<dyn error::Error>> {
+                    let (expansions_to_do, simulations_to_do) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let mut orchestrator = Orchestrator::new(ruleset.clone());
+                    orchestrator.start(1, 2)?;
+                    orchestrator.set_transpositions(true)?;
+                    orchestrator.set_state(ruleset.initial_state())?;
+                    orchestrator.search(budget::Budget::iterations(300), expansions_to_do, simulations_to_do)?;
+                    let mut trees = orchestrator.node_visits()?;
+                    orchestrator.stop()?;
+                    let visits = trees.pop().unwrap();
+                    // Iterations are counted once by every node they go through, whatever its parents
+                    assert_eq!(visits[0].0, 300.0);
+                    for (node_visits, edge_visits) in &visits[1..] {
+                        assert_eq!(node_visits, edge_visits);
+                    }
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    transposition_visits_tests! {
+        sequential_transposition_visits: (0, 0),
+        parallel_transposition_visits: (1, 2),
+    }
+
+    struct Uniform;
+
+    impl ai::Policy<connectn::TicTacToe> for Uniform {
+        fn predict(
+            &mut self,
+            _state: &connectn::TicTacToeState,
+        ) -> Result<ai::Prediction<connectn::TicTacToe>, Box<dyn error::Error>> {
+            Ok(ai::Prediction {
+                value: 0.0,
+                probabilities: (0..9)
+                    .map(|index| (connectn::Ply::new(index), 1.0 / 9.0))
+                    .collect(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_leaf_evaluator() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let mut orchestrator = Orchestrator::new(ruleset);
+        orchestrator.set_leaf_evaluator(leaf_evaluator::PolicyEvaluator::new(Uniform), 8);
+        orchestrator.start(0, 1)?;
+        orchestrator.set_tree_policy(tree_policy::PUCT::default)?;
+        let state = connectn::TicTacToeState::from_indices(&[4, 1], &[0, 5], 1);
+        orchestrator.set_state(state.clone())?;
+        orchestrator.search(budget::Budget::iterations(500), 0, 8)?;
+        let considerations = orchestrator.ply_considerations()?.unwrap();
+        let (_, simulation_count) = orchestrator.stop()?;
+        assert_eq!(considerations[0].ply, connectn::Ply::new(7));
+        assert_gt!(simulation_count, 0);
+        // The evaluator is kept for the next start
+        assert!(orchestrator.leaf_evaluator.is_some());
+        orchestrator.start(0, 1)?;
+        orchestrator.set_tree_policy(tree_policy::PUCT::default)?;
+        orchestrator.set_state(state)?;
+        orchestrator.search(budget::Budget::iterations(500), 0, 8)?;
+        let (_, simulation_count) = orchestrator.stop()?;
+        assert_gt!(simulation_count, 0);
         Ok(())
     }
 }
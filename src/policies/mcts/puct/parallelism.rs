@@ -0,0 +1,21 @@
+/// How the iterations of a search are spread over the workers
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Parallelism {
+    /// A single tree, concurrent iterations selecting different leaves
+    #[default]
+    Tree,
+    /// A single tree, the leaf of each iteration being simulated by several workers at once
+    Leaf,
+    /// Independent trees searched from the same state, whose root statistics are merged
+    Root { trees: usize },
+}
+
+impl Parallelism {
+    /// Returns the number of trees searched.
+    pub fn trees(&self) -> usize {
+        match self {
+            Parallelism::Tree | Parallelism::Leaf => 1,
+            Parallelism::Root { trees } => (*trees).max(1),
+        }
+    }
+}
@@ -1,7 +1,20 @@
+use super::super::budget;
+use super::super::rave;
+use super::super::tree_policy;
+use super::super::virtual_loss;
+use super::parallelism;
 use crate::interface::rulesets;
 
-pub enum Request<RuleSet: rulesets::RuleSetTrait> {
+pub enum Request<RuleSet: rulesets::HasStatesWithSymmetries> {
     SetState(RuleSet::State),
+    SetTreePolicy {
+        policy: Box<dyn tree_policy::TreePolicy<RuleSet::State>>,
+        /// Update of the all-moves-as-first statistics the policy reads, if any
+        amaf_update: Option<rave::AmafUpdate<RuleSet>>,
+    },
+    SetVirtualLoss(virtual_loss::VirtualLoss),
+    SetParallelism(parallelism::Parallelism),
+    SetTranspositions(bool),
     IterateSequentially {
         count: usize,
     },
@@ -10,6 +23,12 @@ pub enum Request<RuleSet: rulesets::RuleSetTrait> {
         expansions_to_do: usize,
         simulations_to_do: usize,
     },
+    Search {
+        budget: budget::Budget,
+        expansions_to_do: usize,
+        simulations_to_do: usize,
+    },
     ListConsiderations,
+    ListVisits,
     Stop,
 }
@@ -1,6 +1,10 @@
+use super::super::analysis;
 use crate::interface::ai;
 use crate::interface::rulesets;
 
-pub struct Response<RuleSet: rulesets::RuleSetTrait> {
-    pub considerations: Vec<ai::PlyConsideration<RuleSet::Ply>>,
+pub enum Response<RuleSet: rulesets::RuleSetTrait> {
+    /// Scores of the plies of the root, along with the visits of their node
+    Considerations(Vec<(ai::PlyConsideration<RuleSet::Ply>, f32)>),
+    /// Visits of the nodes of the tree, along with the visits of the edges leading to them
+    Visits(analysis::NodeVisits),
 }
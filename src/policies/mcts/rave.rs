@@ -0,0 +1,194 @@
+use super::edges;
+use super::simulation;
+use crate::interface::rulesets;
+use petgraph::graph;
+use std::collections::HashSet;
+
+/// Updates the all-moves-as-first statistics of a tree, which searchers call once their tree
+/// policy reads them
+pub type AmafUpdate<RuleSet> = fn(
+    &mut edges::Tree<RuleSet>,
+    &RuleSet,
+    &[graph::NodeIndex<u32>],
+    &simulation::Playout<RuleSet>,
+    rulesets::Status,
+);
+
+/// Updates the all-moves-as-first statistics along the path from the root to the leaf a
+/// simulation started from.
+///
+/// Every edge from a node on the path is updated when its ply was played later in the simulation
+/// by the current player of the node, plies being identified by their encoding. The statistics
+/// are kept on the edges, so that the parents of a node merged through transpositions each have
+/// their own.
+pub fn update_amaf<RuleSet>(
+    tree: &mut edges::Tree<RuleSet>,
+    ruleset: &RuleSet,
+    path: &[graph::NodeIndex<u32>],
+    playout: &simulation::Playout<RuleSet>,
+    status: rulesets::Status,
+) where
+    RuleSet: rulesets::EncodableState + rulesets::HasStatesWithSymmetries + rulesets::TurnByTurn,
+{
+    let mut played = playout
+        .iter()
+        .map(|(player, ply)| (*player, ruleset.encode_ply(ply)))
+        .collect::<HashSet<_>>();
+    for (depth, node) in path.iter().enumerate().rev() {
+        let player = tree.node_weight(*node).unwrap().current_player;
+        let mut children = tree.neighbors(*node).detach();
+        while let Some(edge) = children.next_edge(tree) {
+            let edge_weight = tree.edge_weight_mut(edge).unwrap();
+            if played.contains(&(player, ruleset.encode_ply(&edge_weight.ply))) {
+                edge_weight.add_amaf_outcome(status, player);
+            }
+        }
+        if depth == 0 {
+            break;
+        }
+        let parent = path[depth - 1];
+        let edge = tree.find_edge(parent, *node).unwrap();
+        let edge_weight = tree.edge_weight(edge).unwrap();
+        // Plies below an edge merged through transpositions are in the frame of its target
+        if let Some(symmetry) = &edge_weight.symmetry {
+            played = played
+                .into_iter()
+                .map(|(player, ply)| {
+                    let ply = ruleset.swap_ply(&ruleset.decode_ply(ply), symmetry);
+                    (player, ruleset.encode_ply(&ply))
+                })
+                .collect();
+        }
+        played.insert((
+            tree.node_weight(parent).unwrap().current_player,
+            ruleset.encode_ply(&edge_weight.ply),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::nodes;
+    use super::*;
+    use crate::interface::rulesets::HasStatesWithSymmetries;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::interface::rulesets::SymmetryIteratorTrait;
+    use crate::rulesets::connectn;
+
+    type TicTacToeGraph = graph::Graph<
+        nodes::Node<connectn::TicTacToeState>,
+        edges::Edge<connectn::TicTacToePly, connectn::Symmetry>,
+    >;
+
+    fn add_child(
+        tree: &mut TicTacToeGraph,
+        parent: graph::NodeIndex<u32>,
+        p1_indices: &[usize],
+        p2_indices: &[usize],
+        index: u8,
+    ) -> graph::NodeIndex<u32> {
+        let current_player = ((p1_indices.len() + p2_indices.len()) % 2) as u8;
+        let state = connectn::TicTacToeState::from_indices(p1_indices, p2_indices, current_player);
+        let child = tree.add_node(nodes::Node::new(
+            state,
+            rulesets::Status::Ongoing,
+            current_player,
+        ));
+        tree.add_edge(parent, child, edges::Edge::new(connectn::Ply::new(index)));
+        child
+    }
+
+    #[test]
+    fn test_update_amaf() {
+        let ruleset = connectn::TicTacToe::new();
+        let mut tree = TicTacToeGraph::new();
+        let root = tree.add_node(nodes::Node::new(
+            ruleset.initial_state(),
+            rulesets::Status::Ongoing,
+            0,
+        ));
+        let played_later = add_child(&mut tree, root, &[0], &[], 0);
+        let played_by_opponent = add_child(&mut tree, root, &[1], &[], 1);
+        let not_played = add_child(&mut tree, root, &[2], &[], 2);
+        let leaf = add_child(&mut tree, root, &[4], &[], 4);
+        let sibling = add_child(&mut tree, leaf, &[4], &[0], 0);
+        let playout = vec![(1, connectn::Ply::new(1)), (0, connectn::Ply::new(0))];
+        let status = rulesets::Status::Win { player: 0 };
+        update_amaf(&mut tree, &ruleset, &[root, leaf], &playout, status);
+        let amaf = |parent, child| {
+            let weight = tree
+                .edge_weight(tree.find_edge(parent, child).unwrap())
+                .unwrap();
+            (weight.amaf_visits, weight.amaf_score)
+        };
+        assert_eq!(amaf(root, played_later), (1.0, 1.0));
+        assert_eq!(amaf(root, played_by_opponent), (0.0, 0.0));
+        assert_eq!(amaf(root, not_played), (0.0, 0.0));
+        assert_eq!(amaf(root, leaf), (1.0, 1.0));
+        assert_eq!(amaf(leaf, sibling), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_symmetric_edge() {
+        let ruleset = connectn::TicTacToe::new();
+        let mut tree = TicTacToeGraph::new();
+        let root_state = connectn::TicTacToeState::from_indices(&[2], &[], 1);
+        let root = tree.add_node(nodes::Node::new(root_state, rulesets::Status::Ongoing, 1));
+        let mirrored = add_child(&mut tree, root, &[2], &[3], 3);
+        let played_later = add_child(&mut tree, root, &[2], &[5], 5);
+        // The leaf is stored as the mirror of the state its ply leads to
+        let leaf = add_child(&mut tree, root, &[0], &[1], 1);
+        let successor = connectn::TicTacToeState::from_indices(&[2], &[1], 0);
+        let leaf_state = &tree.node_weight(leaf).unwrap().state;
+        let symmetry = connectn::SymmetryIterator::new(&ruleset)
+            .find(|symmetry| ruleset.swap_state(leaf_state, symmetry) == successor);
+        let edge = tree.find_edge(root, leaf).unwrap();
+        tree.edge_weight_mut(edge).unwrap().symmetry = symmetry;
+        let playout = vec![(0, connectn::Ply::new(6)), (1, connectn::Ply::new(3))];
+        let status = rulesets::Status::Win { player: 1 };
+        update_amaf(&mut tree, &ruleset, &[root, leaf], &playout, status);
+        let amaf_visits = |child| {
+            let edge = tree.find_edge(root, child).unwrap();
+            tree.edge_weight(edge).unwrap().amaf_visits
+        };
+        assert_eq!(amaf_visits(played_later), 1.0);
+        assert_eq!(amaf_visits(mirrored), 0.0);
+    }
+
+    #[test]
+    fn test_transposed_node() {
+        let ruleset = connectn::TicTacToe::new();
+        let mut tree = TicTacToeGraph::new();
+        let first_parent = tree.add_node(nodes::Node::new(
+            connectn::TicTacToeState::from_indices(&[0], &[1], 0),
+            rulesets::Status::Ongoing,
+            0,
+        ));
+        let second_parent = tree.add_node(nodes::Node::new(
+            connectn::TicTacToeState::from_indices(&[2], &[1], 0),
+            rulesets::Status::Ongoing,
+            0,
+        ));
+        let shared = add_child(&mut tree, first_parent, &[0, 2], &[1], 2);
+        tree.add_edge(
+            second_parent,
+            shared,
+            edges::Edge::new(connectn::Ply::new(0)),
+        );
+        let status = rulesets::Status::Win { player: 0 };
+        update_amaf(
+            &mut tree,
+            &ruleset,
+            &[first_parent, shared],
+            &Vec::new(),
+            status,
+        );
+        // Only the parent the simulation went through counts it
+        let amaf_visits = |parent| {
+            let edge = tree.find_edge(parent, shared).unwrap();
+            tree.edge_weight(edge).unwrap().amaf_visits
+        };
+        assert_eq!(amaf_visits(first_parent), 1.0);
+        assert_eq!(amaf_visits(second_parent), 0.0);
+    }
+}
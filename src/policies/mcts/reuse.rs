@@ -0,0 +1,255 @@
+use super::edges;
+use super::nodes;
+use crate::interface::rulesets;
+use crate::interface::rulesets::SymmetryIteratorTrait;
+use petgraph::graph;
+use std::collections::VecDeque;
+use std::mem;
+
+/// Number of plies a new root is looked for below the current one: the ply of the searcher and
+/// the reply of its opponent
+pub const MAX_ADVANCE_DEPTH: usize = 2;
+
+/// Looks for the state among the descendants of the root, down to the given depth, and makes
+/// its node the root of the tree, keeping the statistics of its subtree and dropping all the
+/// other nodes.
+///
+/// As expansion only keeps one ply among the symmetric ones, the state may be reached through a
+/// node of a symmetric state, whose subtree is then mapped through the symmetry.
+///
+/// Returns the index of the new root, or `None` when the state was not found, the tree being
+/// left untouched.
+pub fn advance_root<RuleSet>(
+    tree: &mut edges::Tree<RuleSet>,
+    ruleset: &RuleSet,
+    root: graph::NodeIndex<u32>,
+    state: &RuleSet::State,
+    max_depth: usize,
+) -> Option<graph::NodeIndex<u32>>
+where
+    RuleSet: rulesets::HasStatesWithSymmetries + rulesets::TurnByTurn,
+    RuleSet::State: Eq,
+{
+    let mut queue = VecDeque::new();
+    queue.push_back((root, 0));
+    while let Some((node, depth)) = queue.pop_front() {
+        let node_state = &tree.node_weight(node).unwrap().state;
+        let symmetry = if *node_state == *state {
+            Some(None)
+        } else {
+            RuleSet::SymmetryIterator::new(ruleset)
+                .find(|symmetry| ruleset.swap_state(node_state, symmetry) == *state)
+                .map(Some)
+        };
+        if let Some(symmetry) = symmetry {
+            // The tree only holds the subtree of its root, which needs no rebuilding
+            let new_root = if node == root {
+                root
+            } else {
+                retain_subtree(tree, node)
+            };
+            if let Some(symmetry) = symmetry {
+                swap_tree(tree, ruleset, &symmetry);
+            }
+            return Some(new_root);
+        }
+        if depth < max_depth {
+            queue.extend(tree.neighbors(node).map(|child| (child, depth + 1)));
+        }
+    }
+    None
+}
+
+/// Maps the states and plies of the whole tree through the symmetry, the known outcomes
+/// following the players when it switches them, and the symmetries of the edges following the
+/// states.
+fn swap_tree<RuleSet>(
+    tree: &mut edges::Tree<RuleSet>,
+    ruleset: &RuleSet,
+    symmetry: &RuleSet::Symmetry,
+) where
+    RuleSet: rulesets::HasStatesWithSymmetries + rulesets::TurnByTurn,
+    RuleSet::State: Eq,
+{
+    // States the plies of the edges merged through transpositions lead to, once mapped
+    let successors = tree
+        .edge_indices()
+        .filter_map(|edge| {
+            let edge_symmetry = tree.edge_weight(edge).unwrap().symmetry.as_ref()?;
+            let (_, target) = tree.edge_endpoints(edge).unwrap();
+            let target_state = &tree.node_weight(target).unwrap().state;
+            let successor = ruleset.swap_state(target_state, edge_symmetry);
+            Some((edge, target, ruleset.swap_state(&successor, symmetry)))
+        })
+        .collect::<Vec<_>>();
+    for weight in tree.node_weights_mut() {
+        weight.state = ruleset.swap_state(&weight.state, symmetry);
+        let current_player = ruleset.current_player(&weight.state);
+        if current_player == weight.current_player {
+            continue;
+        }
+        weight.current_player = current_player;
+        if let nodes::Status::Terminal { status } | nodes::Status::Proven { status } =
+            &mut weight.status
+        {
+            if let rulesets::Status::Win { player } = *status {
+                *status = rulesets::Status::Win { player: 1 - player };
+            }
+        }
+    }
+    for edge in tree.edge_weights_mut() {
+        edge.ply = ruleset.swap_ply(&edge.ply, symmetry);
+    }
+    for (edge, target, successor) in successors {
+        let target_state = &tree.node_weight(target).unwrap().state;
+        let edge_symmetry = RuleSet::SymmetryIterator::new(ruleset)
+            .find(|edge_symmetry| ruleset.swap_state(target_state, edge_symmetry) == successor);
+        tree.edge_weight_mut(edge).unwrap().symmetry = edge_symmetry;
+    }
+}
+
+/// Rebuilds the graph with the nodes reachable from the given one only, which becomes the
+/// first node, and returns its new index.
+pub fn retain_subtree<State, Edge>(
+    tree: &mut graph::Graph<nodes::Node<State>, Edge>,
+    root: graph::NodeIndex<u32>,
+) -> graph::NodeIndex<u32>
+where
+    State: rulesets::StateTrait,
+{
+    let mut new_indices = vec![None; tree.node_count()];
+    let mut order = vec![root];
+    new_indices[root.index()] = Some(0);
+    let mut current = 0;
+    while current < order.len() {
+        for child in tree.neighbors(order[current]) {
+            if new_indices[child.index()].is_none() {
+                new_indices[child.index()] = Some(order.len());
+                order.push(child);
+            }
+        }
+        current += 1;
+    }
+    let (nodes, edges) = mem::take(tree).into_nodes_edges();
+    let mut weights = Vec::new();
+    weights.resize_with(order.len(), || None);
+    for (index, node) in nodes.into_iter().enumerate() {
+        if let Some(new_index) = new_indices[index] {
+            weights[new_index] = Some(node.weight);
+        }
+    }
+    for weight in weights {
+        tree.add_node(weight.unwrap());
+    }
+    for edge in edges {
+        if let (Some(source), Some(target)) = (
+            new_indices[edge.source().index()],
+            new_indices[edge.target().index()],
+        ) {
+            tree.add_edge(
+                graph::NodeIndex::new(source),
+                graph::NodeIndex::new(target),
+                edge.weight,
+            );
+        }
+    }
+    graph::NodeIndex::new(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::rulesets::Deterministic;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::rulesets::connectn;
+
+    type TicTacToeGraph = graph::Graph<
+        nodes::Node<connectn::TicTacToeState>,
+        edges::Edge<connectn::TicTacToePly, connectn::Symmetry>,
+    >;
+
+    /// Returns the state reached by playing the cells in turn.
+    fn state_from(cells: &[usize]) -> connectn::TicTacToeState {
+        let ruleset = connectn::TicTacToe::new();
+        cells.iter().fold(ruleset.initial_state(), |state, cell| {
+            ruleset
+                .play(&state, &connectn::Ply::new(*cell as u8))
+                .unwrap()
+        })
+    }
+
+    fn add_node(
+        tree: &mut TicTacToeGraph,
+        cells: &[usize],
+        visits: usize,
+    ) -> graph::NodeIndex<u32> {
+        let current_player = (cells.len() % 2) as u8;
+        tree.add_node(nodes::Node::new_visited(
+            state_from(cells),
+            visits,
+            0,
+            0,
+            current_player,
+        ))
+    }
+
+    /// Builds a root with two children, each with one child of its own.
+    fn build_tree() -> (TicTacToeGraph, graph::NodeIndex<u32>) {
+        let mut tree = TicTacToeGraph::new();
+        let root = add_node(&mut tree, &[], 10);
+        for (child_cell, grandchild_cell) in [(0, 1), (4, 8)].iter() {
+            let child = add_node(&mut tree, &[*child_cell], 5);
+            let grandchild = add_node(&mut tree, &[*child_cell, *grandchild_cell], 2);
+            let child_ply = connectn::Ply::new(*child_cell as u8);
+            let grandchild_ply = connectn::Ply::new(*grandchild_cell as u8);
+            tree.add_edge(root, child, edges::Edge::new(child_ply));
+            tree.add_edge(child, grandchild, edges::Edge::new(grandchild_ply));
+        }
+        (tree, root)
+    }
+
+    macro_rules! advance_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (cells, max_depth, expected_nodes, expected_visits): (&[usize], usize, Option<usize>, f32) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let (mut tree, root) = build_tree();
+                    let state = state_from(cells);
+                    let new_root = advance_root(&mut tree, &ruleset, root, &state, max_depth);
+                    match expected_nodes {
+                        Some(expected_nodes) => {
+                            let new_root = new_root.unwrap();
+                            assert_eq!(tree.node_count(), expected_nodes);
+                            assert_eq!(tree.edge_count(), expected_nodes - 1);
+                            let weight = tree.node_weight(new_root).unwrap();
+                            assert_eq!(weight.state, state);
+                            assert_eq!(weight.visits, expected_visits);
+                            // Plies still lead to the states of their children
+                            for edge in tree.raw_edges() {
+                                let source = &tree.node_weight(edge.source()).unwrap().state;
+                                let target = &tree.node_weight(edge.target()).unwrap().state;
+                                assert_eq!(ruleset.play(source, &edge.weight.ply).unwrap(), *target);
+                            }
+                        }
+                        None => {
+                            assert_eq!(new_root, None);
+                            assert_eq!(tree.node_count(), 5);
+                        }
+                    }
+                }
+            )*
+        }
+    }
+
+    advance_tests! {
+        same_root: (&[], 2, Some(5), 10.0),
+        child: (&[4], 2, Some(2), 5.0),
+        grandchild: (&[0, 1], 2, Some(1), 2.0),
+        symmetric_child: (&[8], 2, Some(2), 5.0),
+        symmetric_grandchild: (&[6, 3], 2, Some(1), 2.0),
+        too_deep: (&[0, 1], 1, None, 0.0),
+        unknown_state: (&[1], 2, None, 0.0),
+    }
+}
@@ -0,0 +1,96 @@
+use super::RolloutPolicy;
+use crate::interface::rulesets;
+use crate::tools::plies;
+use rand::rngs;
+use rand::seq::SliceRandom;
+use std::error;
+
+/// Plays an immediately winning ply if any, and otherwise a random ply among the ones leaving
+/// the opponent without an immediate win, falling back to any random ply when all of them do.
+#[derive(Default)]
+pub struct Decisive;
+
+impl Decisive {
+    pub fn new() -> Decisive {
+        Decisive
+    }
+
+    fn winning_ply<RuleSet>(
+        ruleset: &RuleSet,
+        state: &RuleSet::State,
+    ) -> Result<Option<RuleSet::Ply>, Box<dyn error::Error>>
+    where
+        RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    {
+        let player = ruleset.current_player(state);
+        for ply in plies::BasicIterator::new(ruleset, state) {
+            let resulting_state = ruleset.play(state, &ply)?;
+            if ruleset.status(&resulting_state) == (rulesets::Status::Win { player }) {
+                return Ok(Some(ply));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<RuleSet> RolloutPolicy<RuleSet> for Decisive
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+{
+    fn choose_ply(
+        &mut self,
+        ruleset: &RuleSet,
+        state: &RuleSet::State,
+        rng: &mut rngs::StdRng,
+    ) -> Result<RuleSet::Ply, Box<dyn error::Error>> {
+        if let Some(ply) = Decisive::winning_ply(ruleset, state)? {
+            return Ok(ply);
+        }
+        let mut available_plies = plies::BasicIterator::new(ruleset, state).collect::<Vec<_>>();
+        available_plies.shuffle(rng);
+        for ply in &available_plies {
+            let resulting_state = ruleset.play(state, ply)?;
+            if ruleset.status(&resulting_state) != rulesets::Status::Ongoing
+                || Decisive::winning_ply(ruleset, &resulting_state)?.is_none()
+            {
+                return Ok(*ply);
+            }
+        }
+        match available_plies.first() {
+            Some(ply) => Ok(*ply),
+            None => Err("no ply available".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rulesets::connectn;
+    use rand::SeedableRng;
+
+    macro_rules! choose_ply_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<(), Box<dyn error::Error>> {
+                    let (p1_indices, p2_indices, current_player, expected_index) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let state = connectn::TicTacToeState::from_indices(&p1_indices, &p2_indices, current_player);
+                    let mut rng = rngs::StdRng::seed_from_u64(0);
+                    for _ in 0..10 {
+                        let ply = Decisive::new().choose_ply(&ruleset, &state, &mut rng)?;
+                        assert_eq!(ply, connectn::Ply::new(expected_index));
+                    }
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    choose_ply_tests! {
+        immediate_win: ([4, 1, 0], [5, 7, 8], 0, 2),
+        forced_block: ([4, 1], [0, 5], 1, 7),
+        row_block: ([0, 1], [4], 1, 2),
+    }
+}
@@ -0,0 +1,127 @@
+use super::RolloutPolicy;
+use crate::interface::ai;
+use crate::interface::rulesets;
+use crate::tools::plies;
+use rand::rngs;
+use rand::seq::IteratorRandom;
+use rand::Rng;
+use std::error;
+use std::marker;
+
+/// Plays a random ply with the given probability, and otherwise the ply leading to the best
+/// evaluated state, a winning ply if any.
+pub struct EpsilonGreedy<RuleSet, Evaluator>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    Evaluator: ai::Evaluator<RuleSet>,
+{
+    evaluator: Evaluator,
+    epsilon: f64,
+    ruleset: marker::PhantomData<fn() -> RuleSet>,
+}
+
+impl<RuleSet, Evaluator> EpsilonGreedy<RuleSet, Evaluator>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    Evaluator: ai::Evaluator<RuleSet>,
+{
+    /// Fails when epsilon is not a probability.
+    pub fn new(
+        evaluator: Evaluator,
+        epsilon: f64,
+    ) -> Result<EpsilonGreedy<RuleSet, Evaluator>, Box<dyn error::Error>> {
+        if !(0.0..=1.0).contains(&epsilon) {
+            return Err(format!("epsilon {} is not between 0 and 1", epsilon).into());
+        }
+        Ok(EpsilonGreedy {
+            evaluator,
+            epsilon,
+            ruleset: marker::PhantomData,
+        })
+    }
+
+    fn score(&self, ruleset: &RuleSet, player: rulesets::Player, state: &RuleSet::State) -> f32 {
+        match ruleset.status(state) {
+            rulesets::Status::Win { player: winner } if winner == player => 1.0,
+            rulesets::Status::Win { .. } => -1.0,
+            rulesets::Status::Draw => 0.0,
+            rulesets::Status::Ongoing if ruleset.current_player(state) == player => {
+                self.evaluator.evaluate(state)
+            }
+            rulesets::Status::Ongoing => -self.evaluator.evaluate(state),
+        }
+    }
+}
+
+impl<RuleSet, Evaluator> RolloutPolicy<RuleSet> for EpsilonGreedy<RuleSet, Evaluator>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    Evaluator: ai::Evaluator<RuleSet> + Send,
+{
+    fn choose_ply(
+        &mut self,
+        ruleset: &RuleSet,
+        state: &RuleSet::State,
+        rng: &mut rngs::StdRng,
+    ) -> Result<RuleSet::Ply, Box<dyn error::Error>> {
+        let available_plies = plies::BasicIterator::new(ruleset, state);
+        if rng.gen_bool(self.epsilon) {
+            return match available_plies.choose(rng) {
+                Some(ply) => Ok(ply),
+                None => Err("no ply available".into()),
+            };
+        }
+        let player = ruleset.current_player(state);
+        let mut best = None;
+        for ply in available_plies {
+            let resulting_state = ruleset.play(state, &ply)?;
+            let score = self.score(ruleset, player, &resulting_state);
+            match best {
+                Some((_, best_score)) if best_score >= score => (),
+                _ => best = Some((ply, score)),
+            }
+        }
+        match best {
+            Some((ply, _)) => Ok(ply),
+            None => Err("no ply available".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluators;
+    use crate::rulesets::connectn;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_greedy() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let state = connectn::TicTacToeState::from_indices(&[4, 1], &[0, 5], 1);
+        let mut policy = EpsilonGreedy::new(evaluators::ThreatEvaluator::new(&ruleset), 0.0)?;
+        let ply = policy.choose_ply(&ruleset, &state, &mut rngs::StdRng::seed_from_u64(0))?;
+        assert_eq!(ply, connectn::Ply::new(7));
+        Ok(())
+    }
+
+    macro_rules! invalid_epsilon_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let ruleset = connectn::TicTacToe::new();
+                    let evaluator = evaluators::ThreatEvaluator::new(&ruleset);
+                    let policy = EpsilonGreedy::<connectn::TicTacToe, _>::new(evaluator, $value);
+                    assert!(policy.is_err());
+                }
+            )*
+        }
+    }
+
+    invalid_epsilon_tests! {
+        negative: -0.1,
+        above_one: 1.5,
+        not_a_number: f64::NAN,
+    }
+}
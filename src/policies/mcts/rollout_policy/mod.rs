@@ -0,0 +1,41 @@
+//! Policies playing the plies of the simulations
+
+mod decisive;
+mod epsilon_greedy;
+mod policy_driven;
+mod truncated;
+mod uniform;
+
+pub use decisive::Decisive;
+pub use epsilon_greedy::EpsilonGreedy;
+pub use policy_driven::PolicyDriven;
+pub use truncated::Truncated;
+pub use uniform::Uniform;
+
+use crate::interface::rulesets;
+use rand::rngs;
+use std::error;
+
+/// Chooses the plies of a simulation, from its start until the game is over or the policy cuts
+/// it short.
+pub trait RolloutPolicy<RuleSet: rulesets::Deterministic + rulesets::TurnByTurn>: Send {
+    /// Chooses the ply to play in an ongoing state.
+    fn choose_ply(
+        &mut self,
+        ruleset: &RuleSet,
+        state: &RuleSet::State,
+        rng: &mut rngs::StdRng,
+    ) -> Result<RuleSet::Ply, Box<dyn error::Error>>;
+
+    /// Returns the outcome to report for an ongoing state reached after the given number of
+    /// plies, instead of playing on, or `None` to keep playing.
+    fn cutoff(
+        &mut self,
+        _ruleset: &RuleSet,
+        _state: &RuleSet::State,
+        _depth: usize,
+        _rng: &mut rngs::StdRng,
+    ) -> Option<rulesets::Status> {
+        None
+    }
+}
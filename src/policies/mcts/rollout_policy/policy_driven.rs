@@ -0,0 +1,82 @@
+use super::RolloutPolicy;
+use crate::interface::ai;
+use crate::interface::rulesets;
+use rand::distributions;
+use rand::distributions::Distribution;
+use rand::rngs;
+use std::error;
+use std::marker;
+
+/// Samples the plies from the probabilities predicted by a policy, such as a neural network.
+pub struct PolicyDriven<RuleSet, Policy>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    Policy: ai::Policy<RuleSet>,
+{
+    policy: Policy,
+    ruleset: marker::PhantomData<fn() -> RuleSet>,
+}
+
+impl<RuleSet, Policy> PolicyDriven<RuleSet, Policy>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    Policy: ai::Policy<RuleSet>,
+{
+    pub fn new(policy: Policy) -> PolicyDriven<RuleSet, Policy> {
+        PolicyDriven {
+            policy,
+            ruleset: marker::PhantomData,
+        }
+    }
+}
+
+impl<RuleSet, Policy> RolloutPolicy<RuleSet> for PolicyDriven<RuleSet, Policy>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    Policy: ai::Policy<RuleSet> + Send,
+{
+    fn choose_ply(
+        &mut self,
+        _ruleset: &RuleSet,
+        state: &RuleSet::State,
+        rng: &mut rngs::StdRng,
+    ) -> Result<RuleSet::Ply, Box<dyn error::Error>> {
+        let prediction = self.policy.predict(state)?;
+        let weight_index = distributions::WeightedIndex::new(
+            prediction.probabilities.iter().map(|(_, prob)| prob),
+        )?;
+        Ok(prediction.probabilities[weight_index.sample(rng)].0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::rulesets::connectn;
+    use rand::SeedableRng;
+
+    struct Corner;
+
+    impl ai::Policy<connectn::TicTacToe> for Corner {
+        fn predict(
+            &mut self,
+            _state: &connectn::TicTacToeState,
+        ) -> Result<ai::Prediction<connectn::TicTacToe>, Box<dyn error::Error>> {
+            Ok(ai::Prediction {
+                value: 0.0,
+                probabilities: vec![(connectn::Ply::new(0), 1.0), (connectn::Ply::new(4), 0.0)],
+            })
+        }
+    }
+
+    #[test]
+    fn test_sampling() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let state = ruleset.initial_state();
+        let mut policy = PolicyDriven::new(Corner);
+        let ply = policy.choose_ply(&ruleset, &state, &mut rngs::StdRng::seed_from_u64(0))?;
+        assert_eq!(ply, connectn::Ply::new(0));
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use super::RolloutPolicy;
+use crate::interface::ai;
+use crate::interface::rulesets;
+use rand::rngs;
+use rand::Rng;
+use std::error;
+use std::marker;
+
+/// Plays the plies of another policy and stops after the given number of plies, the state
+/// reached being scored by an evaluator.
+///
+/// The outcome reported for a value `v` is a win with probability `max(v, 0)`, a loss with
+/// probability `max(-v, 0)` and a draw otherwise, so that its expected score matches the value.
+pub struct Truncated<RuleSet, Policy, Evaluator>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    Policy: RolloutPolicy<RuleSet>,
+    Evaluator: ai::Evaluator<RuleSet>,
+{
+    policy: Policy,
+    evaluator: Evaluator,
+    depth: usize,
+    ruleset: marker::PhantomData<fn() -> RuleSet>,
+}
+
+impl<RuleSet, Policy, Evaluator> Truncated<RuleSet, Policy, Evaluator>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    Policy: RolloutPolicy<RuleSet>,
+    Evaluator: ai::Evaluator<RuleSet>,
+{
+    pub fn new(
+        policy: Policy,
+        evaluator: Evaluator,
+        depth: usize,
+    ) -> Truncated<RuleSet, Policy, Evaluator> {
+        Truncated {
+            policy,
+            evaluator,
+            depth,
+            ruleset: marker::PhantomData,
+        }
+    }
+}
+
+impl<RuleSet, Policy, Evaluator> RolloutPolicy<RuleSet> for Truncated<RuleSet, Policy, Evaluator>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    Policy: RolloutPolicy<RuleSet>,
+    Evaluator: ai::Evaluator<RuleSet> + Send,
+{
+    fn choose_ply(
+        &mut self,
+        ruleset: &RuleSet,
+        state: &RuleSet::State,
+        rng: &mut rngs::StdRng,
+    ) -> Result<RuleSet::Ply, Box<dyn error::Error>> {
+        self.policy.choose_ply(ruleset, state, rng)
+    }
+
+    fn cutoff(
+        &mut self,
+        ruleset: &RuleSet,
+        state: &RuleSet::State,
+        depth: usize,
+        rng: &mut rngs::StdRng,
+    ) -> Option<rulesets::Status> {
+        if depth < self.depth {
+            return self.policy.cutoff(ruleset, state, depth, rng);
+        }
+        let player = ruleset.current_player(state);
+        let value = self.evaluator.evaluate(state);
+        let draw = rng.gen::<f32>();
+        Some(if draw < value {
+            rulesets::Status::Win { player }
+        } else if draw < -value {
+            rulesets::Status::Win { player: 1 - player }
+        } else {
+            rulesets::Status::Draw
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Uniform;
+    use super::*;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::rulesets::connectn;
+    use rand::SeedableRng;
+
+    macro_rules! cutoff_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (value, depth, expected) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let state = ruleset.initial_state();
+                    let evaluator = move |_: &connectn::TicTacToeState| value;
+                    let mut policy = Truncated::new(Uniform::new(), evaluator, 2);
+                    let status = policy.cutoff(&ruleset, &state, depth, &mut rngs::StdRng::seed_from_u64(0));
+                    assert_eq!(status, expected);
+                }
+            )*
+        }
+    }
+
+    cutoff_tests! {
+        too_shallow: (1.0, 1, None),
+        win: (1.0, 2, Some(rulesets::Status::Win { player: 0 })),
+        loss: (-1.0, 3, Some(rulesets::Status::Win { player: 1 })),
+        draw: (0.0, 2, Some(rulesets::Status::Draw)),
+    }
+}
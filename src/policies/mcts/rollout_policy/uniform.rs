@@ -0,0 +1,33 @@
+use super::RolloutPolicy;
+use crate::interface::rulesets;
+use crate::tools::plies;
+use rand::rngs;
+use rand::seq::IteratorRandom;
+use std::error;
+
+/// Plays uniformly random plies.
+#[derive(Default)]
+pub struct Uniform;
+
+impl Uniform {
+    pub fn new() -> Uniform {
+        Uniform
+    }
+}
+
+impl<RuleSet> RolloutPolicy<RuleSet> for Uniform
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+{
+    fn choose_ply(
+        &mut self,
+        ruleset: &RuleSet,
+        state: &RuleSet::State,
+        rng: &mut rngs::StdRng,
+    ) -> Result<RuleSet::Ply, Box<dyn error::Error>> {
+        match plies::BasicIterator::new(ruleset, state).choose(rng) {
+            Some(ply) => Ok(ply),
+            None => Err("no ply available".into()),
+        }
+    }
+}
@@ -1,34 +1,87 @@
+use super::edges;
 use super::nodes;
-use super::uct_value;
+use super::tree_policy;
+use super::virtual_loss;
 use crate::interface::rulesets;
 use petgraph::graph;
+use petgraph::visit::EdgeRef;
 
-pub fn select<State: rulesets::StateTrait, Edge>(
+/// Descends from the node to the leaf to iterate from, and returns the path leading to it.
+///
+/// Children with several parents, reached through transpositions, are explored according to the
+/// visits of the edge they are reached through rather than their own.
+pub fn select<State: rulesets::StateTrait, Edge: edges::EdgeStatistics>(
     tree: &graph::Graph<nodes::Node<State>, Edge>,
     node: graph::NodeIndex<u32>,
-) -> graph::NodeIndex<u32> {
-    let weight = tree.node_weight(node).unwrap();
-    if !weight.is_visited() {
-        return node;
-    }
-    let best_neighbour = tree
-        .neighbors(node)
-        .filter_map(|child_index| {
-            let child_weight = tree.node_weight(child_index).unwrap();
-            if child_weight.expanding {
-                return None;
+    policy: &dyn tree_policy::TreePolicy<State>,
+    virtual_loss: virtual_loss::VirtualLoss,
+) -> Vec<graph::NodeIndex<u32>> {
+    let mut path = vec![node];
+    let mut node = node;
+    loop {
+        let weight = tree.node_weight(node).unwrap();
+        // The outcome of proven nodes needs no more search
+        if !weight.is_visited() || weight.game_status() != rulesets::Status::Ongoing {
+            return path;
+        }
+        let parent_visits = virtual_loss.visits(weight.visits, weight.pending);
+        // Children without a prior share the probabilities evenly
+        let uniform_prior = 1.0 / tree.neighbors(node).count() as f32;
+        let best_neighbour = tree
+            .edges(node)
+            .filter_map(|edge| {
+                let child_index = edge.target();
+                let child_weight = tree.node_weight(child_index).unwrap();
+                // Proven losses of the player moving into them are never worth playing
+                if child_weight.expanding || child_weight.proven_loss() {
+                    return None;
+                }
+                let is_shared = tree
+                    .neighbors_directed(child_index, petgraph::Direction::Incoming)
+                    .nth(1)
+                    .is_some();
+                let child_visits = match edge.weight().visits() {
+                    Some(visits) if is_shared => visits,
+                    _ => child_weight.visits,
+                };
+                let is_terminal = child_weight.game_status() != rulesets::Status::Ongoing;
+                // Finished or proven outcomes are known, whatever the iterations in progress
+                let (visits, score) = if is_terminal {
+                    (child_visits, child_weight.score())
+                } else {
+                    let score = child_weight.score();
+                    (
+                        virtual_loss.visits(child_visits, child_weight.pending),
+                        virtual_loss.score(child_visits, score, child_weight.pending),
+                    )
+                };
+                let (amaf_visits, amaf_score) = edge.weight().amaf().unwrap_or((0.0, 0.0));
+                let child = tree_policy::Child {
+                    state: &child_weight.state,
+                    visits,
+                    score,
+                    variance: child_weight.variance(),
+                    is_terminal,
+                    amaf_visits,
+                    amaf_score,
+                    prior: edge.weight().prior().unwrap_or(uniform_prior),
+                };
+                if visits == 0.0 {
+                    return Some((child_index, policy.unvisited_value(parent_visits, &child)));
+                }
+                Some((child_index, policy.value(parent_visits, &child)))
+            })
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+        node = match best_neighbour {
+            Some((child_index, _)) => child_index,
+            None => {
+                if let Some(child_index) = tree.neighbors(node).next() {
+                    path.push(child_index);
+                }
+                return path;
             }
-            let value =
-                uct_value::uct_value(weight.visits, child_weight.visits, child_weight.score());
-            Some((child_index, value))
-        })
-        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
-    match best_neighbour {
-        Some((child_index, _)) => select(tree, child_index),
-        None => match tree.neighbors(node).next() {
-            Some(child_index) => child_index,
-            None => node,
-        },
+        };
+        path.push(node);
     }
 }
 
@@ -47,8 +100,13 @@ mod tests {
             rulesets::Status::Ongoing,
             0,
         ));
-        let result = select(&tree, root);
-        assert_eq!(result, root);
+        let result = select(
+            &tree,
+            root,
+            &tree_policy::UCB1::default(),
+            virtual_loss::VirtualLoss::default(),
+        );
+        assert_eq!(*result.last().unwrap(), root);
     }
 
     #[test]
@@ -69,8 +127,13 @@ mod tests {
         ));
         tree.add_edge(root_index, second_index, ());
 
-        let result = select(&tree, root_index);
-        assert_eq!(result, second_index);
+        let result = select(
+            &tree,
+            root_index,
+            &tree_policy::UCB1::default(),
+            virtual_loss::VirtualLoss::default(),
+        );
+        assert_eq!(*result.last().unwrap(), second_index);
     }
 
     #[test]
@@ -88,8 +151,13 @@ mod tests {
         let second_index = tree.add_node(second_weight);
         tree.add_edge(root_index, second_index, ());
 
-        let result = select(&tree, root_index);
-        assert_eq!(result, first_index);
+        let result = select(
+            &tree,
+            root_index,
+            &tree_policy::UCB1::default(),
+            virtual_loss::VirtualLoss::default(),
+        );
+        assert_eq!(*result.last().unwrap(), first_index);
     }
 
     #[test]
@@ -107,7 +175,12 @@ mod tests {
         let second_index = tree.add_node(second_weight);
         tree.add_edge(root_index, second_index, ());
 
-        let result = select(&tree, root_index);
-        assert_eq!(result, first_index);
+        let result = select(
+            &tree,
+            root_index,
+            &tree_policy::UCB1::default(),
+            virtual_loss::VirtualLoss::default(),
+        );
+        assert_eq!(*result.last().unwrap(), first_index);
     }
 }
@@ -1,35 +1,46 @@
 use super::super::edges;
-use super::super::nodes;
+use super::super::rollout_policy;
 use crate::interface::rulesets;
-use crate::tools::plies;
 use petgraph::graph;
 use rand::rngs;
 use rand::seq::IteratorRandom;
+use std::error;
 
-pub fn simulate<RuleSet: rulesets::Deterministic>(
+/// Plies played during a simulation, along with their players
+pub type Playout<RuleSet> = Vec<(rulesets::Player, <RuleSet as rulesets::RuleSetTrait>::Ply)>;
+
+/// Plays the plies of the rollout policy until the game is over or the policy cuts the
+/// simulation short, returning its outcome and the plies played.
+pub fn simulate<RuleSet: rulesets::Deterministic + rulesets::TurnByTurn>(
     ruleset: &RuleSet,
     state: &RuleSet::State,
-    rng: &mut rngs::ThreadRng,
-) -> rulesets::Status {
+    rollout_policy: &mut dyn rollout_policy::RolloutPolicy<RuleSet>,
+    rng: &mut rngs::StdRng,
+) -> Result<(rulesets::Status, Playout<RuleSet>), Box<dyn error::Error>> {
     let mut current_state = state;
     let mut state;
+    let mut playout = Vec::new();
     loop {
         let status = ruleset.status(current_state);
         if let rulesets::Status::Ongoing = status {
-            let available_plies = plies::BasicIterator::new(ruleset, current_state);
-            let ply = available_plies.choose(rng).unwrap();
-            state = ruleset.play(&current_state, &ply).unwrap();
+            if let Some(status) = rollout_policy.cutoff(ruleset, current_state, playout.len(), rng)
+            {
+                return Ok((status, playout));
+            }
+            let ply = rollout_policy.choose_ply(ruleset, current_state, rng)?;
+            playout.push((ruleset.current_player(current_state), ply));
+            state = ruleset.play(&current_state, &ply)?;
             current_state = &state;
         } else {
-            return status;
+            return Ok((status, playout));
         }
     }
 }
 
-pub fn fetch_random_child<RuleSet: rulesets::Deterministic>(
-    tree: &graph::Graph<nodes::Node<RuleSet::State>, edges::Edge<RuleSet::Ply>>,
+pub fn fetch_random_child<RuleSet: rulesets::Deterministic + rulesets::HasStatesWithSymmetries>(
+    tree: &edges::Tree<RuleSet>,
     node_index: graph::NodeIndex<u32>,
-    rng: &mut rngs::ThreadRng,
+    rng: &mut rngs::StdRng,
 ) -> (graph::NodeIndex<u32>, RuleSet::State) {
     let to_simulate = match tree.neighbors(node_index).choose(rng) {
         Some(node) => node,
@@ -0,0 +1,229 @@
+use super::super::expansion;
+use super::super::leaf_evaluator;
+use super::requests;
+use super::responses;
+use crate::interface::rulesets;
+use crate::tools::symmetries;
+use crossbeam::channel;
+use petgraph::graph;
+use std::collections;
+use std::error;
+use std::hash;
+
+/// Evaluates the leaves instead of simulating them, gathering the pending requests into
+/// batches handed to the evaluator at once.
+pub struct EvaluationWorker<RuleSet>
+where
+    RuleSet: rulesets::HasStatesWithSymmetries
+        + rulesets::Deterministic
+        + rulesets::TurnByTurn
+        + 'static,
+    RuleSet::Ply: Eq + Ord + hash::Hash,
+    RuleSet::State: Eq,
+{
+    ruleset: RuleSet,
+    receiver: channel::Receiver<requests::Request<RuleSet>>,
+    sender: channel::Sender<responses::Response<RuleSet>>,
+    evaluator: leaf_evaluator::Shared<RuleSet>,
+    batch_size: usize,
+    pub operation_count: usize,
+}
+
+impl<RuleSet> EvaluationWorker<RuleSet>
+where
+    RuleSet: rulesets::HasStatesWithSymmetries
+        + rulesets::Deterministic
+        + rulesets::TurnByTurn
+        + 'static,
+    RuleSet::Ply: Eq + Ord + hash::Hash,
+    RuleSet::State: Eq,
+{
+    pub fn new(
+        ruleset: RuleSet,
+        receiver: channel::Receiver<requests::Request<RuleSet>>,
+        sender: channel::Sender<responses::Response<RuleSet>>,
+        evaluator: leaf_evaluator::Shared<RuleSet>,
+        batch_size: usize,
+    ) -> EvaluationWorker<RuleSet> {
+        EvaluationWorker {
+            ruleset,
+            receiver,
+            sender,
+            evaluator,
+            batch_size,
+            operation_count: 0,
+        }
+    }
+
+    pub fn run(&mut self) -> Result<(), Box<dyn error::Error>> {
+        while let requests::Request::SimulationRequest { node_index, state } =
+            self.receiver.recv()?
+        {
+            let mut batch = vec![(node_index, state)];
+            let mut stopping = false;
+            while batch.len() < self.batch_size {
+                match self.receiver.try_recv() {
+                    Ok(requests::Request::SimulationRequest { node_index, state }) => {
+                        batch.push((node_index, state))
+                    }
+                    Ok(requests::Request::Stop) => {
+                        stopping = true;
+                        break;
+                    }
+                    Err(channel::TryRecvError::Empty) => break,
+                    Err(error) => return Err(Box::new(error)),
+                }
+            }
+            self.operation_count += batch.len();
+            self.evaluate(batch)?;
+            if stopping {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn evaluate(
+        &mut self,
+        batch: Vec<(graph::NodeIndex<u32>, RuleSet::State)>,
+    ) -> Result<(), Box<dyn error::Error>> {
+        let mut ongoing = Vec::new();
+        for (node_index, state) in batch {
+            let status = self.ruleset.status(&state);
+            if status == rulesets::Status::Ongoing {
+                ongoing.push((node_index, state));
+                continue;
+            }
+            self.sender.send(responses::Response {
+                node_index,
+                status,
+                playout: Vec::new(),
+                evaluation: None,
+            })?;
+        }
+        if ongoing.is_empty() {
+            return Ok(());
+        }
+        let states = ongoing
+            .iter()
+            .map(|(_, state)| state.clone())
+            .collect::<Vec<_>>();
+        let evaluations = self.evaluator.lock().unwrap().evaluate_batch(&states)?;
+        for ((node_index, state), evaluation) in ongoing.into_iter().zip(evaluations) {
+            let evaluation = responses::Evaluation {
+                player: self.ruleset.current_player(&state),
+                value: evaluation.value,
+                successors: self.expand(&state, evaluation.priors),
+            };
+            self.sender.send(responses::Response {
+                node_index,
+                status: rulesets::Status::Ongoing,
+                playout: Vec::new(),
+                evaluation: Some(evaluation),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Lists the successors of the state along with the probabilities of their ply.
+    ///
+    /// Plies equivalent by symmetry are only listed once, the listed ply gathering their
+    /// probabilities, which are normalised over the successors in case the priors gave some to
+    /// illegal plies.
+    fn expand(
+        &self,
+        state: &RuleSet::State,
+        priors: Option<Vec<(RuleSet::Ply, f32)>>,
+    ) -> Vec<(expansion::Play<RuleSet>, Option<f32>)> {
+        let mut iterator = expansion::Expander::new(&self.ruleset, state);
+        let mut successors = Vec::new();
+        while let Some(successor) = iterator.iterate() {
+            successors.push(successor);
+        }
+        let priors = match priors {
+            Some(priors) => priors,
+            None => return successors.into_iter().map(|play| (play, None)).collect(),
+        };
+        let invariant_symmetries = symmetries::invariant_symmetries(&self.ruleset, state);
+        let mut gathered_priors = collections::HashMap::new();
+        for (ply, prior) in priors {
+            let listed_ply =
+                symmetries::equivalent_plies(&self.ruleset, &invariant_symmetries, &ply)[0];
+            *gathered_priors.entry(listed_ply).or_insert(0.0) += prior;
+        }
+        let probabilities = successors
+            .iter()
+            .map(|play| *gathered_priors.get(&play.ply).unwrap_or(&0.0))
+            .collect::<Vec<_>>();
+        let total: f32 = probabilities.iter().sum();
+        successors
+            .into_iter()
+            .zip(probabilities)
+            .map(|(play, probability)| {
+                let prior = if total > 0.0 {
+                    Some(probability / total)
+                } else {
+                    None
+                };
+                (play, prior)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::ai;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::rulesets::connectn;
+    use std::sync;
+
+    struct Uniform;
+
+    impl ai::Policy<connectn::TicTacToe> for Uniform {
+        fn predict(
+            &mut self,
+            _state: &connectn::TicTacToeState,
+        ) -> Result<ai::Prediction<connectn::TicTacToe>, Box<dyn error::Error>> {
+            Ok(ai::Prediction {
+                value: 0.0,
+                probabilities: (0..9)
+                    .map(|index| (connectn::Ply::new(index), 1.0 / 9.0))
+                    .collect(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_symmetric_priors() {
+        let ruleset = connectn::TicTacToe::new();
+        let (_, receiver) = channel::unbounded();
+        let (sender, _) = channel::unbounded();
+        let evaluator = leaf_evaluator::PolicyEvaluator::new(Uniform);
+        let worker = EvaluationWorker::new(
+            ruleset.clone(),
+            receiver,
+            sender,
+            sync::Arc::new(sync::Mutex::new(evaluator)),
+            1,
+        );
+        let state = ruleset.initial_state();
+        let priors = (0..9)
+            .map(|index| (connectn::Ply::new(index), 1.0 / 9.0))
+            .collect();
+        let mut successors = worker
+            .expand(&state, Some(priors))
+            .into_iter()
+            .map(|(play, prior)| (play.ply.index, prior.unwrap()))
+            .collect::<Vec<_>>();
+        successors.sort_by_key(|(index, _)| *index);
+        // A corner, an edge and the center, the first two standing for four plies each
+        let expected = [(0, 4.0 / 9.0), (1, 4.0 / 9.0), (4, 1.0 / 9.0)];
+        assert_eq!(successors.len(), expected.len());
+        for ((index, prior), (expected_index, expected_prior)) in successors.iter().zip(&expected) {
+            assert_eq!(index, expected_index);
+            assert!((prior - expected_prior).abs() < 1e-6);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 mod algo;
+mod evaluation_worker;
 mod pool;
 mod requests;
 mod responses;
@@ -6,6 +7,7 @@ mod worker;
 
 pub use algo::fetch_random_child;
 pub use algo::simulate;
+pub use algo::Playout;
 pub use pool::Pool;
 pub use requests::Request;
 pub use responses::Response;
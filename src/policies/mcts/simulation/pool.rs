@@ -1,20 +1,24 @@
+use super::super::leaf_evaluator;
+use super::super::rollout_policy;
+use super::evaluation_worker;
 use super::requests;
 use super::responses;
 use super::worker;
 use crate::interface::rulesets;
 use crossbeam::channel;
 use std::error;
+use std::hash;
 use std::thread;
 
-pub struct Pool<RuleSet: rulesets::Deterministic + 'static> {
+pub struct Pool<RuleSet: rulesets::Deterministic + rulesets::TurnByTurn + 'static> {
     workers: Vec<thread::JoinHandle<usize>>,
     request_receiver: channel::Receiver<requests::Request<RuleSet>>,
     pub request_sender: channel::Sender<requests::Request<RuleSet>>,
-    pub response_receiver: channel::Receiver<responses::Response>,
-    response_sender: channel::Sender<responses::Response>,
+    pub response_receiver: channel::Receiver<responses::Response<RuleSet>>,
+    response_sender: channel::Sender<responses::Response<RuleSet>>,
 }
 
-impl<RuleSet: rulesets::Deterministic + 'static> Pool<RuleSet> {
+impl<RuleSet: rulesets::Deterministic + rulesets::TurnByTurn + 'static> Pool<RuleSet> {
     pub fn new() -> Pool<RuleSet> {
         let (request_sender, request_receiver) = channel::unbounded();
         let (response_sender, response_receiver) = channel::unbounded();
@@ -27,14 +31,47 @@ impl<RuleSet: rulesets::Deterministic + 'static> Pool<RuleSet> {
         }
     }
 
-    pub fn spawn(&mut self, ruleset: RuleSet) -> Result<(), Box<dyn error::Error>> {
+    pub fn spawn(
+        &mut self,
+        ruleset: RuleSet,
+        rollout_policy: Box<dyn rollout_policy::RolloutPolicy<RuleSet>>,
+    ) -> Result<(), Box<dyn error::Error>> {
         let worker_name = format!("mcts-simu-{}", self.workers.len());
         let receiver = self.request_receiver.clone();
         let sender = self.response_sender.clone();
         let handle = thread::Builder::new()
             .name(worker_name)
             .spawn(move || -> usize {
-                let mut worker = worker::Worker::new(ruleset, receiver, sender);
+                let mut worker = worker::Worker::new(ruleset, receiver, sender, rollout_policy);
+                worker.run().unwrap();
+                worker.operation_count
+            })?;
+        self.workers.push(handle);
+        Ok(())
+    }
+
+    /// Spawns a worker evaluating the leaves by batches of at most the given size, instead of
+    /// simulating them.
+    pub fn spawn_evaluator(
+        &mut self,
+        ruleset: RuleSet,
+        evaluator: leaf_evaluator::Shared<RuleSet>,
+        batch_size: usize,
+    ) -> Result<(), Box<dyn error::Error>>
+    where
+        RuleSet: rulesets::HasStatesWithSymmetries,
+        RuleSet::Ply: Eq + Ord + hash::Hash,
+        RuleSet::State: Eq,
+    {
+        let worker_name = format!("mcts-eval-{}", self.workers.len());
+        let receiver = self.request_receiver.clone();
+        let sender = self.response_sender.clone();
+        let handle = thread::Builder::new()
+            .name(worker_name)
+            .spawn(move || -> usize {
+                let mut worker = evaluation_worker::EvaluationWorker::new(
+                    ruleset, receiver, sender, evaluator, batch_size,
+                );
                 worker.run().unwrap();
                 worker.operation_count
             })?;
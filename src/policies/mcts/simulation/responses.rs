@@ -1,7 +1,22 @@
+use super::super::expansion;
+use super::algo;
 use crate::interface::rulesets;
 use petgraph::graph;
 
-pub struct Response {
+pub struct Response<RuleSet: rulesets::RuleSetTrait> {
     pub node_index: graph::NodeIndex<u32>,
     pub status: rulesets::Status,
+    pub playout: algo::Playout<RuleSet>,
+    /// Evaluation of the node, when an ongoing leaf was evaluated instead of simulated
+    pub evaluation: Option<Evaluation<RuleSet>>,
+}
+
+/// Evaluation of a leaf, which was expanded along the way
+pub struct Evaluation<RuleSet: rulesets::RuleSetTrait> {
+    /// Player whose point of view the value is given from
+    pub player: rulesets::Player,
+    /// Expected outcome for the player, within [-1, 1]
+    pub value: f32,
+    /// Successors of the leaf along with their prior, when the evaluator predicts them
+    pub successors: Vec<(expansion::Play<RuleSet>, Option<f32>)>,
 }
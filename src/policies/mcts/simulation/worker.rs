@@ -1,30 +1,35 @@
+use super::super::rollout_policy;
 use super::algo;
 use super::requests;
 use super::responses;
 use crate::interface::rulesets;
 use crossbeam::channel;
 use rand::rngs;
+use rand::SeedableRng;
 use std::error;
 
-pub struct Worker<RuleSet: rulesets::Deterministic> {
+pub struct Worker<RuleSet: rulesets::Deterministic + rulesets::TurnByTurn + 'static> {
     ruleset: RuleSet,
     receiver: channel::Receiver<requests::Request<RuleSet>>,
-    sender: channel::Sender<responses::Response>,
-    rng: rngs::ThreadRng,
+    sender: channel::Sender<responses::Response<RuleSet>>,
+    rollout_policy: Box<dyn rollout_policy::RolloutPolicy<RuleSet>>,
+    rng: rngs::StdRng,
     pub operation_count: usize,
 }
 
-impl<RuleSet: rulesets::Deterministic> Worker<RuleSet> {
+impl<RuleSet: rulesets::Deterministic + rulesets::TurnByTurn + 'static> Worker<RuleSet> {
     pub fn new(
         ruleset: RuleSet,
         receiver: channel::Receiver<requests::Request<RuleSet>>,
-        sender: channel::Sender<responses::Response>,
+        sender: channel::Sender<responses::Response<RuleSet>>,
+        rollout_policy: Box<dyn rollout_policy::RolloutPolicy<RuleSet>>,
     ) -> Worker<RuleSet> {
         Worker {
             ruleset,
             receiver,
             sender,
-            rng: rand::thread_rng(),
+            rollout_policy,
+            rng: rngs::StdRng::from_entropy(),
             operation_count: 0,
         }
     }
@@ -34,9 +39,18 @@ impl<RuleSet: rulesets::Deterministic> Worker<RuleSet> {
             self.receiver.recv()?
         {
             self.operation_count += 1;
-            let status = algo::simulate(&self.ruleset, &state, &mut self.rng);
-            self.sender
-                .send(responses::Response { node_index, status })?;
+            let (status, playout) = algo::simulate(
+                &self.ruleset,
+                &state,
+                self.rollout_policy.as_mut(),
+                &mut self.rng,
+            )?;
+            self.sender.send(responses::Response {
+                node_index,
+                status,
+                playout,
+                evaluation: None,
+            })?;
         }
         Ok(())
     }
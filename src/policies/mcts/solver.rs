@@ -0,0 +1,92 @@
+use super::nodes;
+use crate::interface::rulesets;
+use petgraph::graph;
+
+/// Proves the outcome of an ongoing node from its children: the node is won by its current
+/// player as soon as one child is, and otherwise takes the best outcome of its children once all
+/// of them are known.
+///
+/// Returns whether the node got proven.
+pub fn update_proof<State: rulesets::StateTrait, Edge>(
+    tree: &mut graph::Graph<nodes::Node<State>, Edge>,
+    node: graph::NodeIndex<u32>,
+) -> bool {
+    let weight = tree.node_weight(node).unwrap();
+    if let nodes::Status::Terminal { .. } | nodes::Status::Proven { .. } = weight.status {
+        return false;
+    }
+    let player = weight.current_player;
+    let mut best = None;
+    let mut all_known = true;
+    for child in tree.neighbors(node) {
+        let status = tree.node_weight(child).unwrap().game_status();
+        let rank = match status.player_pov(player) {
+            rulesets::PlayerStatus::Win => {
+                best = Some((2, status));
+                break;
+            }
+            rulesets::PlayerStatus::Draw => 1,
+            rulesets::PlayerStatus::Loss => 0,
+            rulesets::PlayerStatus::Ongoing => {
+                all_known = false;
+                continue;
+            }
+        };
+        match best {
+            Some((best_rank, _)) if best_rank >= rank => (),
+            _ => best = Some((rank, status)),
+        }
+    }
+    match best {
+        Some((2, status)) => tree.node_weight_mut(node).unwrap().prove(status),
+        Some((_, status)) if all_known => tree.node_weight_mut(node).unwrap().prove(status),
+        _ => return false,
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests;
+
+    type EmptyStateGraph = graph::Graph<nodes::Node<tests::EmptyState>, ()>;
+
+    macro_rules! update_proof_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (child_statuses, expected): (&[rulesets::Status], Option<rulesets::Status>) = $value;
+                    let mut tree = EmptyStateGraph::new();
+                    let root = tree.add_node(nodes::Node::new_visited(tests::EmptyState::new(), 10, 0, 0, 0));
+                    for status in child_statuses {
+                        let child = tree.add_node(nodes::Node::new(tests::EmptyState::new(), *status, 1));
+                        tree.add_edge(root, child, ());
+                    }
+                    let proven = update_proof(&mut tree, root);
+                    assert_eq!(proven, expected.is_some());
+                    let weight = tree.node_weight(root).unwrap();
+                    assert_eq!(weight.game_status(), expected.unwrap_or(rulesets::Status::Ongoing));
+                }
+            )*
+        }
+    }
+
+    update_proof_tests! {
+        no_children: (&[], None),
+        one_win: (
+            &[rulesets::Status::Ongoing, rulesets::Status::Win { player: 0 }],
+            Some(rulesets::Status::Win { player: 0 }),
+        ),
+        unknown_child: (&[rulesets::Status::Draw, rulesets::Status::Ongoing], None),
+        all_losses: (
+            &[rulesets::Status::Win { player: 1 }, rulesets::Status::Win { player: 1 }],
+            Some(rulesets::Status::Win { player: 1 }),
+        ),
+        draw_over_loss: (
+            &[rulesets::Status::Win { player: 1 }, rulesets::Status::Draw],
+            Some(rulesets::Status::Draw),
+        ),
+    }
+}
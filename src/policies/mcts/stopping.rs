@@ -0,0 +1,155 @@
+use super::budget;
+use super::nodes;
+use crate::interface::rulesets;
+use petgraph::graph;
+
+/// Tells whether a search should stop, once its budget is spent or the outcome of the root is
+/// proven, or, with early stopping, once the decision at the root cannot change anymore: a child
+/// proves the root, or its most visited child cannot be overtaken with the iterations left.
+///
+/// An ongoing root is searched until expanded whatever the budget, so that a ply can be played.
+pub fn should_stop<State: rulesets::StateTrait, Edge>(
+    tree: &graph::Graph<nodes::Node<State>, Edge>,
+    root: graph::NodeIndex<u32>,
+    tracker: &budget::Tracker,
+) -> bool {
+    if tree.node_weight(root).unwrap().game_status() != rulesets::Status::Ongoing {
+        return true;
+    }
+    if tree.neighbors(root).next().is_none() {
+        return false;
+    }
+    if tracker.is_exhausted(tree.node_count()) {
+        return true;
+    }
+    if !tracker.early_stop() {
+        return false;
+    }
+    if is_proven(tree, root) {
+        return true;
+    }
+    let mut visits = tree
+        .neighbors(root)
+        .map(|child| tree.node_weight(child).unwrap().visits)
+        .collect::<Vec<_>>();
+    visits.sort_by(|visits_a, visits_b| visits_b.partial_cmp(visits_a).unwrap());
+    match (visits.len(), tracker.remaining_iterations()) {
+        (0, _) => false,
+        (1, _) => true,
+        (_, Some(remaining)) => visits[0] - visits[1] > remaining as f32,
+        (_, None) => false,
+    }
+}
+
+/// Tells whether the outcome of the root is known: one of its children is a finished or proven
+/// game won by its current player, or all of them are.
+fn is_proven<State: rulesets::StateTrait, Edge>(
+    tree: &graph::Graph<nodes::Node<State>, Edge>,
+    root: graph::NodeIndex<u32>,
+) -> bool {
+    let player = tree.node_weight(root).unwrap().current_player;
+    let mut children = tree.neighbors(root).peekable();
+    if children.peek().is_none() {
+        return false;
+    }
+    let mut all_finished = true;
+    for child in children {
+        match tree
+            .node_weight(child)
+            .unwrap()
+            .game_status()
+            .player_pov(player)
+        {
+            rulesets::PlayerStatus::Win => return true,
+            rulesets::PlayerStatus::Ongoing => all_finished = false,
+            _ => (),
+        }
+    }
+    all_finished
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests;
+
+    type EmptyStateGraph = graph::Graph<nodes::Node<tests::EmptyState>, ()>;
+
+    fn build_tree(
+        child_visits: &[usize],
+        child_statuses: &[rulesets::Status],
+    ) -> (EmptyStateGraph, graph::NodeIndex<u32>) {
+        let mut tree = EmptyStateGraph::new();
+        let root = tree.add_node(nodes::Node::new_visited(
+            tests::EmptyState::new(),
+            100,
+            0,
+            0,
+            0,
+        ));
+        for visits in child_visits {
+            let child = tree.add_node(nodes::Node::new_visited(
+                tests::EmptyState::new(),
+                *visits,
+                0,
+                0,
+                1,
+            ));
+            tree.add_edge(root, child, ());
+        }
+        for status in child_statuses {
+            let child = tree.add_node(nodes::Node::new(tests::EmptyState::new(), *status, 1));
+            tree.add_edge(root, child, ());
+        }
+        (tree, root)
+    }
+
+    macro_rules! should_stop_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (child_visits, child_statuses, budget, spent, expected): (&[usize], &[rulesets::Status], budget::Budget, usize, bool) = $value;
+                    let (tree, root) = build_tree(child_visits, child_statuses);
+                    let mut tracker = budget::Tracker::new(budget);
+                    tracker.add_iterations(spent);
+                    assert_eq!(should_stop(&tree, root, &tracker), expected);
+                }
+            )*
+        }
+    }
+
+    const EARLY_STOP: budget::Budget = budget::Budget {
+        iterations: Some(100),
+        time: None,
+        nodes: None,
+        early_stop: true,
+    };
+
+    should_stop_tests! {
+        exhausted: (&[60, 40], &[], budget::Budget::iterations(100), 100, true),
+        ongoing: (&[60, 40], &[], budget::Budget::iterations(100), 50, false),
+        node_limit: (&[60, 40], &[], budget::Budget::nodes(3), 0, true),
+        catchable: (&[30, 20], &[], EARLY_STOP, 50, false),
+        out_of_reach: (&[40, 10], &[], EARLY_STOP, 80, true),
+        without_early_stop: (&[40, 10], &[], budget::Budget::iterations(100), 80, false),
+        single_ply: (&[10], &[], EARLY_STOP, 10, true),
+        winning_ply: (&[5, 5], &[rulesets::Status::Win { player: 0 }], EARLY_STOP, 10, true),
+        losing_ply: (&[5, 5], &[rulesets::Status::Win { player: 1 }], EARLY_STOP, 10, false),
+        all_finished: (&[], &[rulesets::Status::Draw, rulesets::Status::Win { player: 1 }], EARLY_STOP, 10, true),
+        not_expanded: (&[], &[], EARLY_STOP, 0, false),
+        exhausted_before_expansion: (&[], &[], budget::Budget::iterations(0), 0, false),
+        node_limit_before_expansion: (&[], &[], budget::Budget::nodes(1), 0, false),
+    }
+
+    #[test]
+    fn test_proven_root() {
+        let (mut tree, root) = build_tree(&[5, 5], &[]);
+        let tracker = budget::Tracker::new(budget::Budget::iterations(100));
+        assert!(!should_stop(&tree, root, &tracker));
+        tree.node_weight_mut(root)
+            .unwrap()
+            .prove(rulesets::Status::Win { player: 0 });
+        assert!(should_stop(&tree, root, &tracker));
+    }
+}
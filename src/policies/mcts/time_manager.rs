@@ -0,0 +1,82 @@
+use super::budget;
+use std::time;
+
+/// Default number of moves the remaining time is spread over
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+/// Share of the increment spent on each move
+const INCREMENT_SHARE: f64 = 0.8;
+/// Time kept aside on the clock for the overhead of each move
+const SAFETY_MARGIN: time::Duration = time::Duration::from_millis(50);
+/// Shortest budget, so that a move is still searched on a nearly flagged clock
+const MINIMUM_BUDGET: time::Duration = time::Duration::from_millis(10);
+
+/// Splits the time left on a clock into per-move budgets.
+///
+/// Each move gets an even share of the remaining time, as if the game were to last a fixed number
+/// of moves more, plus most of the increment. Budgets stop early once the decision is settled,
+/// which saves time for the next moves.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeManager {
+    moves_to_go: u32,
+}
+
+impl TimeManager {
+    pub fn new() -> TimeManager {
+        TimeManager {
+            moves_to_go: DEFAULT_MOVES_TO_GO,
+        }
+    }
+
+    pub fn set_moves_to_go(&mut self, moves_to_go: u32) {
+        self.moves_to_go = moves_to_go.max(1);
+    }
+
+    /// Returns the budget of the next move, given the time left on the clock and the increment
+    /// added after each move.
+    pub fn budget(&self, remaining: time::Duration, increment: time::Duration) -> budget::Budget {
+        let available = remaining.checked_sub(SAFETY_MARGIN).unwrap_or_default();
+        let share = available / self.moves_to_go + increment.mul_f64(INCREMENT_SHARE);
+        budget::Budget {
+            early_stop: true,
+            ..budget::Budget::time(share.min(available).max(MINIMUM_BUDGET))
+        }
+    }
+}
+
+impl Default for TimeManager {
+    fn default() -> TimeManager {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! budget_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (moves_to_go, remaining, increment, expected) = $value;
+                    let mut manager = TimeManager::new();
+                    manager.set_moves_to_go(moves_to_go);
+                    let budget = manager.budget(
+                        time::Duration::from_millis(remaining),
+                        time::Duration::from_millis(increment),
+                    );
+                    assert_eq!(budget.time, Some(time::Duration::from_millis(expected)));
+                    assert!(budget.early_stop);
+                }
+            )*
+        }
+    }
+
+    budget_tests! {
+        sudden_death: (10, 10_050, 0, 1000),
+        increment: (10, 10_050, 1000, 1800),
+        short_clock: (10, 500, 1000, 450),
+        flagging: (10, 20, 1000, 10),
+        flagged: (10, 20, 0, 10),
+    }
+}
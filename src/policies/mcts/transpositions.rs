@@ -0,0 +1,182 @@
+use super::edges;
+use super::expansion;
+use crate::interface::rulesets;
+use crate::interface::rulesets::SymmetryIteratorTrait;
+use petgraph::graph;
+use std::collections::HashMap;
+use std::hash;
+
+/// Index of the nodes of a graph by their state, so that plies reaching a state already in the
+/// graph through another move order lead to its node, the tree becoming a directed acyclic graph.
+///
+/// States are indexed under their canonical form, the smallest among their symmetries, so that
+/// plies reaching a symmetric state also lead to its node, the edge storing the symmetry between
+/// both. Symmetries switching players are left out, as the outcomes stored in the nodes refer to
+/// the players of their state. Rulesets whose states may repeat within a game would make cycles,
+/// and are not supported.
+pub struct Transpositions<RuleSet: rulesets::RuleSetTrait> {
+    nodes: HashMap<RuleSet::State, graph::NodeIndex<u32>>,
+}
+
+impl<RuleSet> Transpositions<RuleSet>
+where
+    RuleSet: rulesets::HasStatesWithSymmetries + rulesets::TurnByTurn,
+    RuleSet::State: Eq + Ord + hash::Hash,
+{
+    pub fn new() -> Transpositions<RuleSet> {
+        Transpositions {
+            nodes: HashMap::new(),
+        }
+    }
+
+    /// Indexes every node of the graph from scratch, as needed once its indices changed.
+    pub fn rebuild(&mut self, ruleset: &RuleSet, tree: &edges::Tree<RuleSet>) {
+        self.nodes.clear();
+        for index in tree.node_indices() {
+            let state = &tree.node_weight(index).unwrap().state;
+            self.nodes
+                .entry(canonical_state(ruleset, state))
+                .or_insert(index);
+        }
+    }
+
+    /// Saves a successor of the node, linking the node to the existing node of the state of the
+    /// successor or of a symmetric one when there is one, and returns the index of the child.
+    pub fn save_expansion(
+        &mut self,
+        ruleset: &RuleSet,
+        tree: &mut edges::Tree<RuleSet>,
+        node_index: graph::NodeIndex<u32>,
+        successor: expansion::Play<RuleSet>,
+    ) -> graph::NodeIndex<u32> {
+        let key = canonical_state(ruleset, &successor.state);
+        if let Some(child_index) = self.nodes.get(&key) {
+            tree.node_weight_mut(node_index).unwrap().expanding = false;
+            if tree.find_edge(node_index, *child_index).is_none() {
+                let child_state = &tree.node_weight(*child_index).unwrap().state;
+                let mut edge = edges::Edge::new(successor.ply);
+                if *child_state != successor.state {
+                    edge.symmetry = RuleSet::SymmetryIterator::new(ruleset).find(|symmetry| {
+                        ruleset.swap_state(child_state, symmetry) == successor.state
+                    });
+                }
+                tree.add_edge(node_index, *child_index, edge);
+            }
+            return *child_index;
+        }
+        let child_index = expansion::save_expansion(tree, node_index, successor);
+        self.nodes.insert(key, child_index);
+        child_index
+    }
+}
+
+/// Returns the smallest state among the symmetries of the given one which keep its current player.
+fn canonical_state<RuleSet>(ruleset: &RuleSet, state: &RuleSet::State) -> RuleSet::State
+where
+    RuleSet: rulesets::HasStatesWithSymmetries + rulesets::TurnByTurn,
+    RuleSet::State: Ord,
+{
+    let player = ruleset.current_player(state);
+    RuleSet::SymmetryIterator::new(ruleset)
+        .map(|symmetry| ruleset.swap_state(state, &symmetry))
+        .filter(|swapped| ruleset.current_player(swapped) == player)
+        .min()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::nodes;
+    use super::*;
+    use crate::interface::rulesets::HasStatesWithSymmetries;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::rulesets::connectn;
+
+    type TicTacToeGraph = graph::Graph<
+        nodes::Node<connectn::TicTacToeState>,
+        edges::Edge<connectn::TicTacToePly, connectn::Symmetry>,
+    >;
+
+    fn play(
+        p1_indices: &[usize],
+        p2_indices: &[usize],
+        index: u8,
+    ) -> expansion::Play<connectn::TicTacToe> {
+        let current_player = ((p1_indices.len() + p2_indices.len()) % 2) as u8;
+        expansion::Play {
+            ply: connectn::Ply::new(index),
+            state: connectn::TicTacToeState::from_indices(p1_indices, p2_indices, current_player),
+            status: rulesets::Status::Ongoing,
+            current_player,
+        }
+    }
+
+    #[test]
+    fn test_merge_transpositions() {
+        let ruleset = connectn::TicTacToe::new();
+        let mut tree = TicTacToeGraph::new();
+        let root = tree.add_node(nodes::Node::new(
+            ruleset.initial_state(),
+            rulesets::Status::Ongoing,
+            0,
+        ));
+        let mut transpositions = Transpositions::<connectn::TicTacToe>::new();
+        transpositions.rebuild(&ruleset, &tree);
+        let first = transpositions.save_expansion(&ruleset, &mut tree, root, play(&[0], &[], 0));
+        let second = transpositions.save_expansion(&ruleset, &mut tree, root, play(&[1], &[], 1));
+        let from_first =
+            transpositions.save_expansion(&ruleset, &mut tree, first, play(&[0], &[4], 4));
+        let from_second =
+            transpositions.save_expansion(&ruleset, &mut tree, second, play(&[1], &[4], 4));
+        assert_ne!(from_first, from_second);
+        // Both move orders lead to the same state
+        let first_grandchild =
+            transpositions.save_expansion(&ruleset, &mut tree, from_first, play(&[0, 1], &[4], 1));
+        let second_grandchild =
+            transpositions.save_expansion(&ruleset, &mut tree, from_second, play(&[0, 1], &[4], 0));
+        assert_eq!(first_grandchild, second_grandchild);
+        assert_eq!(tree.node_count(), 6);
+        assert_eq!(
+            tree.neighbors_directed(first_grandchild, petgraph::Direction::Incoming)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_merge_symmetric_states() {
+        let ruleset = connectn::TicTacToe::new();
+        let mut tree = TicTacToeGraph::new();
+        let root = tree.add_node(nodes::Node::new(
+            ruleset.initial_state(),
+            rulesets::Status::Ongoing,
+            0,
+        ));
+        let mut transpositions = Transpositions::<connectn::TicTacToe>::new();
+        transpositions.rebuild(&ruleset, &tree);
+        let first = transpositions.save_expansion(&ruleset, &mut tree, root, play(&[0], &[], 0));
+        let first = transpositions.save_expansion(&ruleset, &mut tree, first, play(&[0], &[4], 4));
+        let from_first =
+            transpositions.save_expansion(&ruleset, &mut tree, first, play(&[0, 1], &[4], 1));
+        let second = transpositions.save_expansion(&ruleset, &mut tree, root, play(&[1], &[], 1));
+        let second =
+            transpositions.save_expansion(&ruleset, &mut tree, second, play(&[1], &[4], 4));
+        // Both states are mirrors of each other
+        let successor = play(&[1, 2], &[4], 2);
+        let successor_state = successor.state.clone();
+        let from_second = transpositions.save_expansion(&ruleset, &mut tree, second, successor);
+        assert_eq!(from_first, from_second);
+        assert_eq!(tree.node_count(), 6);
+        let first_edge = tree.find_edge(first, from_first).unwrap();
+        assert!(tree.edge_weight(first_edge).unwrap().symmetry.is_none());
+        let second_edge = tree.find_edge(second, from_second).unwrap();
+        let symmetry = tree
+            .edge_weight(second_edge)
+            .unwrap()
+            .symmetry
+            .as_ref()
+            .unwrap();
+        let child_state = &tree.node_weight(from_second).unwrap().state;
+        assert_eq!(ruleset.swap_state(child_state, symmetry), successor_state);
+    }
+}
@@ -0,0 +1,67 @@
+//! Policies selecting the child to descend into during the selection phase
+//!
+//! # References
+//!
+//! * [Finite-time analysis of the multiarmed bandit problem](https://link.springer.com/content/pdf/10.1023/A:1013689704352.pdf)
+//! * [Exploration-exploitation tradeoff using variance estimates](https://hal.inria.fr/hal-00711069/document)
+//! * [Monte-Carlo tree search and rapid action value estimation](https://www.davidsilver.uk/wp-content/uploads/2020/03/mcts_rave.pdf)
+//! * [Mastering the game of Go without human knowledge](https://www.nature.com/articles/nature24270)
+//! * [Progressive strategies for Monte-Carlo tree search](https://dke.maastrichtuniversity.nl/m.winands/documents/pMCTS.pdf)
+
+mod progressive_bias;
+mod puct;
+mod rave;
+mod ucb1;
+mod ucb1_tuned;
+mod ucbv;
+
+pub use progressive_bias::ProgressiveBias;
+pub use puct::PUCT;
+pub use rave::Rave;
+pub use ucb1::UCB1;
+pub use ucb1_tuned::UCB1Tuned;
+pub use ucbv::UCBV;
+
+/// Statistics of a child considered for selection, the outcomes being counted from the point of
+/// view of the player moving into it.
+pub struct Child<'a, State> {
+    pub state: &'a State,
+    pub visits: f32,
+    /// Mean outcome, within [0, 1]
+    pub score: f32,
+    /// Variance of the outcomes
+    pub variance: f32,
+    pub is_terminal: bool,
+    /// Number of simulations through the parent in which the ply of the child was played later
+    pub amaf_visits: f32,
+    /// Mean outcome of those simulations
+    pub amaf_score: f32,
+    /// Probability of playing the ply of the child given by a policy, uniform without one
+    pub prior: f32,
+}
+
+/// Values the children of a node, the most valued one being selected.
+///
+/// Children without visits are valued separately, being selected before any visited child by
+/// default.
+pub trait TreePolicy<State>: Send {
+    fn value(&self, parent_visits: f32, child: &Child<State>) -> f32;
+
+    fn unvisited_value(&self, _parent_visits: f32, _child: &Child<State>) -> f32 {
+        f32::INFINITY
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn child(visits: f32, score: f32, variance: f32) -> Child<'static, ()> {
+    Child {
+        state: &(),
+        visits,
+        score,
+        variance,
+        is_terminal: false,
+        amaf_visits: 0.0,
+        amaf_score: 0.0,
+        prior: 1.0,
+    }
+}
@@ -0,0 +1,105 @@
+use super::Child;
+use super::TreePolicy;
+use crate::interface::ai;
+use crate::interface::rulesets;
+use std::marker;
+
+/// Adds to the value of another policy a heuristic value of the child, whose weight fades as
+/// the child gets visited.
+///
+/// The heuristic comes from an evaluator, which can be any function of the state such as the
+/// prior of a policy network.
+pub struct ProgressiveBias<RuleSet, Policy, Evaluator>
+where
+    RuleSet: rulesets::RuleSetTrait,
+    Policy: TreePolicy<RuleSet::State>,
+    Evaluator: ai::Evaluator<RuleSet>,
+{
+    policy: Policy,
+    evaluator: Evaluator,
+    weight: f32,
+    ruleset: marker::PhantomData<fn() -> RuleSet>,
+}
+
+impl<RuleSet, Policy, Evaluator> ProgressiveBias<RuleSet, Policy, Evaluator>
+where
+    RuleSet: rulesets::RuleSetTrait,
+    Policy: TreePolicy<RuleSet::State>,
+    Evaluator: ai::Evaluator<RuleSet>,
+{
+    pub fn new(
+        policy: Policy,
+        evaluator: Evaluator,
+        weight: f32,
+    ) -> ProgressiveBias<RuleSet, Policy, Evaluator> {
+        ProgressiveBias {
+            policy,
+            evaluator,
+            weight,
+            ruleset: marker::PhantomData,
+        }
+    }
+}
+
+impl<RuleSet, Policy, Evaluator> TreePolicy<RuleSet::State>
+    for ProgressiveBias<RuleSet, Policy, Evaluator>
+where
+    RuleSet: rulesets::RuleSetTrait,
+    Policy: TreePolicy<RuleSet::State>,
+    Evaluator: ai::Evaluator<RuleSet> + Send,
+{
+    fn value(&self, parent_visits: f32, child: &Child<RuleSet::State>) -> f32 {
+        // Evaluators only value ongoing states, from the point of view of their current player
+        let heuristic = if child.is_terminal {
+            child.score
+        } else {
+            (1.0 - self.evaluator.evaluate(child.state)) / 2.0
+        };
+        self.policy.value(parent_visits, child) + self.weight * heuristic / (child.visits + 1.0)
+    }
+
+    fn unvisited_value(&self, parent_visits: f32, child: &Child<RuleSet::State>) -> f32 {
+        self.policy.unvisited_value(parent_visits, child)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::UCB1;
+    use super::*;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::rulesets::connectn;
+    use more_asserts::assert_gt;
+
+    fn child(state: &connectn::TicTacToeState, visits: f32) -> Child<'_, connectn::TicTacToeState> {
+        Child {
+            state,
+            visits,
+            score: 0.5,
+            variance: 0.25,
+            is_terminal: false,
+            amaf_visits: 0.0,
+            amaf_score: 0.0,
+            prior: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_bias() {
+        let ruleset = connectn::TicTacToe::new();
+        let good_state = ruleset.initial_state();
+        let bad_state = connectn::TicTacToeState::from_indices(&[0], &[], 1);
+        // Favours states where the player to move lags behind
+        let evaluator =
+            |state: &connectn::TicTacToeState| if state.is_empty(0) { -1.0 } else { 1.0 };
+        let policy =
+            ProgressiveBias::<connectn::TicTacToe, _, _>::new(UCB1::default(), evaluator, 1.0);
+        let value_a = policy.value(10.0, &child(&good_state, 2.0));
+        let value_b = policy.value(10.0, &child(&bad_state, 2.0));
+        assert_gt!(value_a, value_b);
+        // The bias fades with the visits
+        let visited_a = policy.value(10000.0, &child(&good_state, 5000.0));
+        let visited_b = policy.value(10000.0, &child(&bad_state, 5000.0));
+        assert_gt!(value_a - value_b, visited_a - visited_b);
+    }
+}
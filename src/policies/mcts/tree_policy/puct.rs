@@ -0,0 +1,78 @@
+use super::Child;
+use super::TreePolicy;
+
+const DEFAULT_EXPLORATION: f32 = 1.5;
+
+/// Score of unvisited children, which have no outcome yet
+const UNVISITED_SCORE: f32 = 0.5;
+
+/// Predictor upper confidence bound of AlphaZero, the exploration of each child being weighted
+/// by its prior.
+///
+/// Unvisited children are valued as well, so that the prior decides which ones to try first.
+pub struct PUCT {
+    exploration: f32,
+}
+
+impl PUCT {
+    pub fn new(exploration: f32) -> PUCT {
+        PUCT { exploration }
+    }
+}
+
+impl Default for PUCT {
+    fn default() -> PUCT {
+        PUCT::new(DEFAULT_EXPLORATION)
+    }
+}
+
+impl<State> TreePolicy<State> for PUCT {
+    fn value(&self, parent_visits: f32, child: &Child<State>) -> f32 {
+        let score = if child.visits == 0.0 && !child.is_terminal {
+            UNVISITED_SCORE
+        } else {
+            child.score
+        };
+        score + self.exploration * child.prior * parent_visits.sqrt() / (1.0 + child.visits)
+    }
+
+    fn unvisited_value(&self, parent_visits: f32, child: &Child<State>) -> f32 {
+        self.value(parent_visits, child)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::child;
+    use super::*;
+    use more_asserts::assert_gt;
+
+    #[test]
+    fn test_favour_priors() {
+        let policy = PUCT::default();
+        let mut child_a = child(0.0, 0.0, 0.0);
+        child_a.prior = 0.7;
+        let mut child_b = child(0.0, 0.0, 0.0);
+        child_b.prior = 0.3;
+        assert_gt!(
+            policy.unvisited_value(10.0, &child_a),
+            policy.unvisited_value(10.0, &child_b)
+        );
+    }
+
+    #[test]
+    fn test_favour_wins() {
+        let policy = PUCT::default();
+        let value_a = policy.value(100.0, &child(50.0, 0.75, 0.0));
+        let value_b = policy.value(100.0, &child(50.0, 0.45, 0.0));
+        assert_gt!(value_a, value_b);
+    }
+
+    #[test]
+    fn test_exploration() {
+        let policy = PUCT::default();
+        let value_a = policy.value(100.0, &child(90.0, 0.6, 0.0));
+        let value_b = policy.value(100.0, &child(1.0, 0.5, 0.0));
+        assert_gt!(value_b, value_a);
+    }
+}
@@ -0,0 +1,88 @@
+use super::Child;
+use super::TreePolicy;
+
+const DEFAULT_BIAS: f32 = 0.05;
+
+/// Blends the score of the children with their all-moves-as-first score before handing them to
+/// another policy, following the schedule of MC-RAVE minimising the mean squared error of the
+/// blend.
+///
+/// The bias is the expected difference between both scores: the lower it is, the longer the
+/// all-moves-as-first score is trusted.
+///
+/// Searchers only gather the all-moves-as-first statistics once the policy reading them is set
+/// with `set_amaf_tree_policy`.
+pub struct Rave<Policy> {
+    policy: Policy,
+    bias: f32,
+}
+
+impl<Policy> Rave<Policy> {
+    pub fn new(policy: Policy, bias: f32) -> Rave<Policy> {
+        Rave { policy, bias }
+    }
+
+    /// Weight of the all-moves-as-first score in the blend.
+    fn beta(&self, visits: f32, amaf_visits: f32) -> f32 {
+        amaf_visits / (visits + amaf_visits + 4.0 * self.bias * self.bias * visits * amaf_visits)
+    }
+}
+
+impl<Policy: Default> Default for Rave<Policy> {
+    fn default() -> Rave<Policy> {
+        Rave::new(Policy::default(), DEFAULT_BIAS)
+    }
+}
+
+impl<State, Policy: TreePolicy<State>> TreePolicy<State> for Rave<Policy> {
+    fn value(&self, parent_visits: f32, child: &Child<State>) -> f32 {
+        if child.amaf_visits == 0.0 {
+            return self.policy.value(parent_visits, child);
+        }
+        let beta = self.beta(child.visits, child.amaf_visits);
+        let blended = Child {
+            state: child.state,
+            visits: child.visits,
+            score: (1.0 - beta) * child.score + beta * child.amaf_score,
+            variance: child.variance,
+            is_terminal: child.is_terminal,
+            amaf_visits: child.amaf_visits,
+            amaf_score: child.amaf_score,
+            prior: child.prior,
+        };
+        self.policy.value(parent_visits, &blended)
+    }
+
+    fn unvisited_value(&self, parent_visits: f32, child: &Child<State>) -> f32 {
+        self.policy.unvisited_value(parent_visits, child)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::child;
+    use super::super::UCB1;
+    use super::*;
+    use more_asserts::assert_gt;
+    use more_asserts::assert_lt;
+
+    #[test]
+    fn test_beta_schedule() {
+        let policy = Rave::new(UCB1::default(), 0.05);
+        assert_eq!(policy.beta(0.0, 10.0), 1.0);
+        assert_gt!(policy.beta(10.0, 100.0), 0.5);
+        assert_lt!(policy.beta(10000.0, 10000.0), 0.01);
+    }
+
+    #[test]
+    fn test_favour_amaf_wins() {
+        let policy = Rave::new(UCB1::new(0.0), 0.05);
+        let mut child_a = child(5.0, 0.4, 0.0);
+        child_a.amaf_visits = 50.0;
+        child_a.amaf_score = 0.9;
+        let mut child_b = child(5.0, 0.5, 0.0);
+        child_b.amaf_visits = 50.0;
+        child_b.amaf_score = 0.1;
+        assert_gt!(policy.value(10.0, &child_a), policy.value(10.0, &child_b));
+    }
+}
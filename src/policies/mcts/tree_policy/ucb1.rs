@@ -0,0 +1,60 @@
+use super::Child;
+use super::TreePolicy;
+
+const DEFAULT_EXPLORATION: f32 = 1.41;
+
+/// Upper confidence bound of the mean outcome, the exploration constant weighting the width of
+/// the confidence interval.
+pub struct UCB1 {
+    exploration: f32,
+}
+
+impl UCB1 {
+    pub fn new(exploration: f32) -> UCB1 {
+        UCB1 { exploration }
+    }
+}
+
+impl Default for UCB1 {
+    fn default() -> UCB1 {
+        UCB1::new(DEFAULT_EXPLORATION)
+    }
+}
+
+impl<State> TreePolicy<State> for UCB1 {
+    fn value(&self, parent_visits: f32, child: &Child<State>) -> f32 {
+        debug_assert!(parent_visits != 0.0);
+        child.score + self.exploration * (parent_visits.ln() / child.visits).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::child;
+    use super::*;
+    use more_asserts::assert_gt;
+
+    #[test]
+    fn test_favour_not_visited() {
+        let policy = UCB1::default();
+        let value_a = policy.value(10.0, &child(9.0, 0.8, 0.0));
+        let value_b = policy.value(10.0, &child(1.0, 0.0, 0.0));
+        assert_gt!(value_b, value_a);
+    }
+
+    #[test]
+    fn test_favour_wins() {
+        let policy = UCB1::default();
+        let value_a = policy.value(100.0, &child(50.0, 0.75, 0.0));
+        let value_b = policy.value(100.0, &child(50.0, 0.45, 0.0));
+        assert_gt!(value_a, value_b);
+    }
+
+    #[test]
+    fn test_exploration() {
+        let greedy = UCB1::new(0.0);
+        let value_a = greedy.value(10.0, &child(9.0, 0.8, 0.0));
+        let value_b = greedy.value(10.0, &child(1.0, 0.0, 0.0));
+        assert_gt!(value_a, value_b);
+    }
+}
@@ -0,0 +1,50 @@
+use super::Child;
+use super::TreePolicy;
+
+/// Variance of a Bernoulli variable with a probability of 1/2, the largest possible for outcomes
+/// within [0, 1]
+const MAX_VARIANCE: f32 = 0.25;
+
+/// UCB1 with a confidence interval narrowed by an upper bound of the variance of the outcomes,
+/// which explores less the children with steady outcomes.
+#[derive(Default)]
+pub struct UCB1Tuned;
+
+impl UCB1Tuned {
+    pub fn new() -> UCB1Tuned {
+        UCB1Tuned
+    }
+}
+
+impl<State> TreePolicy<State> for UCB1Tuned {
+    fn value(&self, parent_visits: f32, child: &Child<State>) -> f32 {
+        debug_assert!(parent_visits != 0.0);
+        let log_ratio = parent_visits.ln() / child.visits;
+        let variance_bound = child.variance + (2.0 * log_ratio).sqrt();
+        child.score + (log_ratio * variance_bound.min(MAX_VARIANCE)).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::child;
+    use super::super::UCB1;
+    use super::*;
+    use more_asserts::assert_gt;
+    use more_asserts::assert_lt;
+
+    #[test]
+    fn test_favour_variance() {
+        let policy = UCB1Tuned::new();
+        let value_a = policy.value(10000.0, &child(5000.0, 0.5, 0.25));
+        let value_b = policy.value(10000.0, &child(5000.0, 0.5, 0.0));
+        assert_gt!(value_a, value_b);
+    }
+
+    #[test]
+    fn test_tighter_than_ucb1() {
+        let value = UCB1Tuned::new().value(100.0, &child(10.0, 0.5, 0.25));
+        let ucb1_value = UCB1::default().value(100.0, &child(10.0, 0.5, 0.25));
+        assert_lt!(value, ucb1_value);
+    }
+}
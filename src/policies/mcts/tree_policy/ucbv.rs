@@ -0,0 +1,60 @@
+use super::Child;
+use super::TreePolicy;
+
+const DEFAULT_EXPLORATION: f32 = 1.0;
+const DEFAULT_ZETA: f32 = 1.2;
+
+/// Upper confidence bound from the empirical Bernstein inequality, whose width grows with the
+/// variance of the outcomes and shrinks faster than UCB1 with the visits when it is low.
+///
+/// The exploration function is `zeta * ln(parent_visits)`, the exploration constant weighting
+/// the term bounding the range of the outcomes.
+pub struct UCBV {
+    exploration: f32,
+    zeta: f32,
+}
+
+impl UCBV {
+    pub fn new(exploration: f32, zeta: f32) -> UCBV {
+        UCBV { exploration, zeta }
+    }
+}
+
+impl Default for UCBV {
+    fn default() -> UCBV {
+        UCBV::new(DEFAULT_EXPLORATION, DEFAULT_ZETA)
+    }
+}
+
+impl<State> TreePolicy<State> for UCBV {
+    fn value(&self, parent_visits: f32, child: &Child<State>) -> f32 {
+        debug_assert!(parent_visits != 0.0);
+        let exploration_ratio = self.zeta * parent_visits.ln() / child.visits;
+        child.score
+            + (2.0 * child.variance * exploration_ratio).sqrt()
+            + 3.0 * self.exploration * exploration_ratio
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::child;
+    use super::*;
+    use more_asserts::assert_gt;
+
+    #[test]
+    fn test_favour_variance() {
+        let policy = UCBV::default();
+        let value_a = policy.value(100.0, &child(50.0, 0.5, 0.25));
+        let value_b = policy.value(100.0, &child(50.0, 0.5, 0.0));
+        assert_gt!(value_a, value_b);
+    }
+
+    #[test]
+    fn test_favour_wins() {
+        let policy = UCBV::default();
+        let value_a = policy.value(100.0, &child(50.0, 0.75, 0.1));
+        let value_b = policy.value(100.0, &child(50.0, 0.45, 0.1));
+        assert_gt!(value_a, value_b);
+    }
+}
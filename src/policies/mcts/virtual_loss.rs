@@ -0,0 +1,73 @@
+/// Adjustment of the statistics of the nodes that iterations still in progress went through,
+/// steering concurrent selections away from the paths already being explored.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VirtualLoss {
+    /// Statistics are left untouched, concurrent selections following the same path
+    None,
+    /// Each iteration in progress counts as the given number of visits lost by the player moving
+    /// into the node
+    Constant(f32),
+    /// Iterations in progress count as visits whose outcome is not known yet, the score being left
+    /// untouched, as in WU-UCT
+    UnobservedSamples,
+}
+
+impl VirtualLoss {
+    /// Returns the visits of a node, counting the given number of iterations in progress through
+    /// it.
+    pub fn visits(&self, visits: f32, pending: f32) -> f32 {
+        match self {
+            VirtualLoss::None => visits,
+            VirtualLoss::Constant(loss) => visits + loss * pending,
+            VirtualLoss::UnobservedSamples => visits + pending,
+        }
+    }
+
+    /// Returns the score of a node, counting the given number of iterations in progress through
+    /// it.
+    pub fn score(&self, visits: f32, score: f32, pending: f32) -> f32 {
+        match self {
+            VirtualLoss::Constant(_) => {
+                let virtual_visits = self.visits(visits, pending);
+                if virtual_visits == 0.0 {
+                    score
+                } else {
+                    score * visits / virtual_visits
+                }
+            }
+            VirtualLoss::None | VirtualLoss::UnobservedSamples => score,
+        }
+    }
+}
+
+impl Default for VirtualLoss {
+    fn default() -> VirtualLoss {
+        VirtualLoss::Constant(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! virtual_loss_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (virtual_loss, pending, expected_visits, expected_score) = $value;
+                    assert_eq!(virtual_loss.visits(4.0, pending), expected_visits);
+                    assert_eq!(virtual_loss.score(4.0, 0.75, pending), expected_score);
+                }
+            )*
+        }
+    }
+
+    virtual_loss_tests! {
+        none: (VirtualLoss::None, 2.0, 4.0, 0.75),
+        constant: (VirtualLoss::Constant(1.0), 2.0, 6.0, 0.5),
+        heavy_constant: (VirtualLoss::Constant(2.0), 2.0, 8.0, 0.375),
+        unobserved_samples: (VirtualLoss::UnobservedSamples, 2.0, 6.0, 0.75),
+        nothing_pending: (VirtualLoss::Constant(1.0), 0.0, 4.0, 0.75),
+    }
+}
@@ -0,0 +1,603 @@
+use super::limits;
+use super::ordering;
+use super::shared_transpositions;
+use super::state;
+use super::statistics;
+use super::transpositions;
+use crate::interface::ai;
+use crate::interface::rulesets;
+use crate::tools::plies;
+use std::error;
+use std::f32;
+use std::hash;
+use std::sync::atomic;
+use std::time;
+
+/// Half-width of the aspiration window around the score of the previous iteration, evaluations
+/// being expected within [-1, 1]
+const ASPIRATION_WINDOW: f32 = 0.1;
+/// Width of the null windows, small compared to the differences between evaluations
+const NULL_WINDOW: f32 = 1e-4;
+/// Deepest iteration, `u8::MAX` marking the results of exhaustive searches
+const MAX_DEPTH: u8 = u8::MAX - 1;
+/// Number of nodes between two checks of the clock, minus one
+const CLOCK_CHECK_MASK: u64 = 1023;
+
+pub struct SearchResult<Ply: Copy> {
+    pub state: state::State<Ply>,
+    pub depth: u8,
+    pub statistics: statistics::SearchStatistics,
+    pub elapsed: time::Duration,
+}
+
+/// Depth-limited principal variation search, deepened iteratively until the limits are reached.
+///
+/// Positions at the horizon are scored by the evaluator, from the point of view of their current
+/// player.
+pub struct IterativeDeepening<'a, RuleSet, Evaluator>
+where
+    RuleSet: rulesets::Deterministic
+        + rulesets::EncodableState
+        + rulesets::HasStatesWithSymmetries
+        + rulesets::TurnByTurn,
+    RuleSet::State: Eq + hash::Hash + Ord,
+    RuleSet::Ply: PartialEq,
+    Evaluator: ai::Evaluator<RuleSet>,
+{
+    ruleset: &'a RuleSet,
+    evaluator: Evaluator,
+    limits: limits::SearchLimits,
+    transpositions: Option<Box<dyn transpositions::Transpositions<RuleSet> + 'a>>,
+    ordering: Option<ordering::MoveOrdering<RuleSet>>,
+    statistics: statistics::SearchStatistics,
+    stop: Option<&'a atomic::AtomicBool>,
+    skipped_depths: u8,
+    iteration_depth: u8,
+    deadline: Option<time::Instant>,
+    can_abort: bool,
+    aborted: bool,
+    horizon_reached: bool,
+}
+
+impl<'a, RuleSet, Evaluator> IterativeDeepening<'a, RuleSet, Evaluator>
+where
+    RuleSet: rulesets::Deterministic
+        + rulesets::EncodableState
+        + rulesets::HasStatesWithSymmetries
+        + rulesets::TurnByTurn,
+    RuleSet::State: Eq + hash::Hash + Ord,
+    RuleSet::Ply: PartialEq,
+    Evaluator: ai::Evaluator<RuleSet>,
+{
+    pub fn new(
+        ruleset: &'a RuleSet,
+        evaluator: Evaluator,
+        limits: limits::SearchLimits,
+    ) -> IterativeDeepening<'a, RuleSet, Evaluator> {
+        IterativeDeepening {
+            ruleset,
+            evaluator,
+            limits,
+            transpositions: None,
+            ordering: Some(ordering::MoveOrdering::new()),
+            statistics: statistics::SearchStatistics::default(),
+            stop: None,
+            skipped_depths: 0,
+            iteration_depth: 0,
+            deadline: None,
+            can_abort: false,
+            aborted: false,
+            horizon_reached: false,
+        }
+    }
+
+    /// Stores search results in a transposition table of the given number of entries, shared by
+    /// the following searches.
+    pub fn set_transpositions(&mut self, capacity: usize) {
+        self.transpositions = Some(Box::new(transpositions::TranspositionTable::new(
+            self.ruleset,
+            capacity,
+        )));
+    }
+
+    /// Stores search results in a table shared with concurrent searches, whose owner is in charge
+    /// of its generations.
+    pub fn set_shared_transpositions(
+        &mut self,
+        table: &'a shared_transpositions::SharedTranspositionTable<RuleSet>,
+    ) {
+        self.transpositions = Some(Box::new(table));
+    }
+
+    pub fn transpositions(&self) -> Option<&(dyn transpositions::Transpositions<RuleSet> + 'a)> {
+        self.transpositions.as_deref()
+    }
+
+    /// Makes the search a helper of a parallel search: it starts deeper than the main search so
+    /// that both explore different trees, and stops once the flag is raised.
+    pub(super) fn set_helper(&mut self, stop: &'a atomic::AtomicBool, skipped_depths: u8) {
+        self.stop = Some(stop);
+        self.skipped_depths = skipped_depths;
+    }
+
+    /// Enables or disables the ordering of plies by killer and history heuristics, the hash ply
+    /// being searched first in any case.
+    pub fn set_move_ordering(&mut self, enabled: bool) {
+        self.ordering = if enabled {
+            Some(ordering::MoveOrdering::new())
+        } else {
+            None
+        };
+    }
+
+    /// Searches the state, returning the result of the last completed iteration.
+    ///
+    /// The first iteration always completes, so that a ply is found whatever the limits.
+    pub fn search(&mut self, state: &RuleSet::State) -> SearchResult<RuleSet::Ply> {
+        let start = time::Instant::now();
+        self.statistics = statistics::SearchStatistics::default();
+        self.deadline = self.limits.time.map(|duration| start + duration);
+        // Results of helpers are discarded, so they may be stopped during their first iteration
+        self.can_abort = self.stop.is_some();
+        self.aborted = false;
+        if let Some(table) = &mut self.transpositions {
+            table.new_generation();
+        }
+        if let Some(ordering) = &mut self.ordering {
+            ordering.age();
+        }
+        let mut result = SearchResult {
+            state: state::State::Unset,
+            depth: 0,
+            statistics: statistics::SearchStatistics::default(),
+            elapsed: time::Duration::default(),
+        };
+        let mut depth = self.skipped_depths;
+        while depth < MAX_DEPTH && !matches!(self.limits.depth, Some(limit) if depth >= limit) {
+            depth += 1;
+            self.iteration_depth = depth;
+            self.horizon_reached = false;
+            let iteration_state = self.aspiration_search(state, depth, &result.state);
+            if self.aborted {
+                break;
+            }
+            result.state = iteration_state;
+            result.depth = depth;
+            self.can_abort = true;
+            // Proven outcomes and exhausted game trees cannot change with deeper searches
+            if result.state.score().is_infinite() || !self.horizon_reached {
+                break;
+            }
+            if let (Some(deadline), Some(duration)) = (self.deadline, self.limits.time) {
+                // The next iteration would most likely not complete in the remaining time
+                if time::Instant::now() + duration / 2 > deadline {
+                    break;
+                }
+            }
+        }
+        result.statistics = self.statistics;
+        result.elapsed = start.elapsed();
+        result
+    }
+
+    fn aspiration_search(
+        &mut self,
+        state: &RuleSet::State,
+        depth: u8,
+        previous: &state::State<RuleSet::Ply>,
+    ) -> state::State<RuleSet::Ply> {
+        let principal_variation = previous.plies();
+        let previous_score = previous.score();
+        let (mut alpha, mut beta) = if previous_score.is_finite() {
+            (
+                previous_score - ASPIRATION_WINDOW,
+                previous_score + ASPIRATION_WINDOW,
+            )
+        } else {
+            (f32::NEG_INFINITY, f32::INFINITY)
+        };
+        loop {
+            let result = self.iterate(state, depth, alpha, beta, &principal_variation);
+            let score = result.score();
+            if self.aborted {
+                return result;
+            } else if score <= alpha && alpha > f32::NEG_INFINITY {
+                alpha = f32::NEG_INFINITY;
+            } else if score >= beta && beta < f32::INFINITY {
+                beta = f32::INFINITY;
+            } else {
+                return result;
+            }
+        }
+    }
+
+    fn should_abort(&self) -> bool {
+        if !self.can_abort {
+            return false;
+        }
+        if matches!(self.stop, Some(stop) if stop.load(atomic::Ordering::Relaxed)) {
+            return true;
+        }
+        if let Some(nodes) = self.limits.nodes {
+            if self.statistics.nodes >= nodes {
+                return true;
+            }
+        }
+        match self.deadline {
+            Some(deadline) if self.statistics.nodes & CLOCK_CHECK_MASK == 0 => {
+                time::Instant::now() >= deadline
+            }
+            _ => false,
+        }
+    }
+
+    fn iterate(
+        &mut self,
+        state: &RuleSet::State,
+        depth: u8,
+        alpha: f32,
+        beta: f32,
+        principal_variation: &[RuleSet::Ply],
+    ) -> state::State<RuleSet::Ply> {
+        self.statistics.nodes += 1;
+        if self.should_abort() {
+            self.aborted = true;
+            return state::State::Unset;
+        }
+        match self.ruleset.status(state) {
+            rulesets::Status::Win { player: winner } => {
+                if winner == self.ruleset.current_player(state) {
+                    state::State::Win
+                } else {
+                    state::State::Loss
+                }
+            }
+            rulesets::Status::Draw => state::State::Draw,
+            rulesets::Status::Ongoing if depth == 0 => {
+                self.horizon_reached = true;
+                state::State::Heuristic {
+                    value: self.evaluator.evaluate(state),
+                }
+            }
+            rulesets::Status::Ongoing => {
+                let mut preferred_ply = principal_variation.first().cloned();
+                if let Some(entry) = self.probe(state) {
+                    self.statistics.transposition_hits += 1;
+                    if depth < self.iteration_depth && entry.depth >= depth {
+                        let cutoff = match entry.bound {
+                            transpositions::Bound::Exact => true,
+                            transpositions::Bound::Lower => entry.value >= beta,
+                            transpositions::Bound::Upper => entry.value <= alpha,
+                        };
+                        if cutoff {
+                            self.statistics.transposition_cutoffs += 1;
+                            self.horizon_reached |= entry.depth != u8::MAX;
+                            return state::State::Heuristic { value: entry.value };
+                        }
+                    }
+                    preferred_ply = preferred_ply.or(entry.ply);
+                }
+                let outer_horizon_reached = self.horizon_reached;
+                self.horizon_reached = false;
+                let result = self.iterate_plies(
+                    state,
+                    depth,
+                    alpha,
+                    beta,
+                    principal_variation,
+                    preferred_ply,
+                );
+                if !self.aborted {
+                    // Null windows are narrow enough for a score to be both bounds
+                    let bound = if result.score() >= beta {
+                        transpositions::Bound::Lower
+                    } else if result.score() <= alpha {
+                        transpositions::Bound::Upper
+                    } else {
+                        transpositions::Bound::Exact
+                    };
+                    let entry = transpositions::Entry {
+                        value: result.score(),
+                        bound,
+                        depth: if self.horizon_reached { depth } else { u8::MAX },
+                        ply: result.plies().first().cloned(),
+                    };
+                    if let Some(table) = &mut self.transpositions {
+                        table.insert(state, entry);
+                    }
+                }
+                self.horizon_reached |= outer_horizon_reached;
+                result
+            }
+        }
+    }
+
+    fn probe(&self, state: &RuleSet::State) -> Option<transpositions::Entry<RuleSet::Ply>> {
+        self.transpositions.as_ref()?.get(state)
+    }
+
+    fn iterate_plies(
+        &mut self,
+        state: &RuleSet::State,
+        depth: u8,
+        mut alpha: f32,
+        beta: f32,
+        principal_variation: &[RuleSet::Ply],
+        preferred_ply: Option<RuleSet::Ply>,
+    ) -> state::State<RuleSet::Ply> {
+        let mut available_plies =
+            plies::BasicIterator::new(self.ruleset, state).collect::<Vec<_>>();
+        let distance = (self.iteration_depth - depth) as usize;
+        // The principal variation of the previous iteration, or else the best ply stored in the
+        // transposition table, is searched first
+        match &self.ordering {
+            Some(ordering) => {
+                ordering.order(self.ruleset, &mut available_plies, preferred_ply, distance)
+            }
+            None => {
+                if let Some(position) = available_plies
+                    .iter()
+                    .position(|ply| Some(*ply) == preferred_ply)
+                {
+                    available_plies.swap(0, position);
+                }
+            }
+        }
+        let mut current_state = state::State::Unset;
+        for (index, ply) in available_plies.iter().enumerate() {
+            let resulting_state = self.ruleset.play(state, ply).unwrap();
+            let child_variation = match principal_variation.first() {
+                Some(principal_ply) if principal_ply == ply => &principal_variation[1..],
+                _ => &[],
+            };
+            // Null windows need a finite bound to be built around
+            let iteration_state = if index == 0 || alpha == f32::NEG_INFINITY {
+                self.iterate(&resulting_state, depth - 1, -beta, -alpha, child_variation)
+            } else {
+                // Later plies are expected to be worse, which a null window proves faster
+                let iteration_state = self.iterate(
+                    &resulting_state,
+                    depth - 1,
+                    -alpha - NULL_WINDOW,
+                    -alpha,
+                    &[],
+                );
+                let score = -iteration_state.score();
+                if !self.aborted && score > alpha && score < beta {
+                    self.statistics.researches += 1;
+                    self.iterate(&resulting_state, depth - 1, -beta, -alpha, &[])
+                } else {
+                    iteration_state
+                }
+            };
+            if self.aborted {
+                return state::State::Unset;
+            }
+            if iteration_state.should_replace(&current_state) {
+                current_state = state::State::tree_search(*ply, iteration_state);
+                if current_state.score() >= beta {
+                    self.statistics.cutoffs += 1;
+                    if index == 0 {
+                        self.statistics.first_ply_cutoffs += 1;
+                    }
+                    if let Some(ordering) = &mut self.ordering {
+                        ordering.record_cutoff(self.ruleset, ply, distance, depth);
+                    }
+                    break;
+                }
+                alpha = alpha.max(current_state.score());
+            }
+        }
+        current_state
+    }
+}
+
+impl<'a, RuleSet, Evaluator> ai::Agent<RuleSet> for IterativeDeepening<'a, RuleSet, Evaluator>
+where
+    RuleSet: rulesets::Deterministic
+        + rulesets::EncodableState
+        + rulesets::HasStatesWithSymmetries
+        + rulesets::TurnByTurn,
+    RuleSet::State: Eq + hash::Hash + Ord,
+    RuleSet::Ply: PartialEq,
+    Evaluator: ai::Evaluator<RuleSet>,
+{
+    fn play(&mut self, state: &RuleSet::State) -> Result<RuleSet::Ply, Box<dyn error::Error>> {
+        match self.search(state).state {
+            state::State::TreeSearch { ply, .. } => Ok(ply),
+            _ => Err("no ply to play on a finished game".into()),
+        }
+    }
+}
+
+impl<'a, RuleSet, Evaluator> ai::Policy<RuleSet> for IterativeDeepening<'a, RuleSet, Evaluator>
+where
+    RuleSet: rulesets::Deterministic
+        + rulesets::EncodableState
+        + rulesets::HasStatesWithSymmetries
+        + rulesets::TurnByTurn,
+    RuleSet::State: Eq + hash::Hash + Ord,
+    RuleSet::Ply: PartialEq,
+    Evaluator: ai::Evaluator<RuleSet>,
+{
+    fn predict(
+        &mut self,
+        state: &RuleSet::State,
+    ) -> Result<ai::Prediction<RuleSet>, Box<dyn error::Error>> {
+        let result = self.search(state);
+        match result.state {
+            state::State::TreeSearch { ply, .. } => Ok(ai::Prediction {
+                value: result.state.score().clamp(-1.0, 1.0),
+                probabilities: vec![(ply, 1.0)],
+            }),
+            _ => Err("no ply to play on a finished game".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluators;
+    use crate::interface::rulesets::Deterministic;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::policies::minimax;
+    use crate::rulesets::connectn;
+    use crate::rulesets::reversi;
+
+    fn zero_evaluator<State>(_state: &State) -> f32 {
+        0.0
+    }
+
+    macro_rules! search_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (p1_indices, p2_indices, current_player) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let state = connectn::TicTacToeState::from_indices(&p1_indices, &p2_indices, current_player);
+                    let mut algo = IterativeDeepening::new(&ruleset, zero_evaluator, limits::SearchLimits::default());
+                    let result = algo.search(&state);
+                    let expected = minimax::Negamax::new(&ruleset).compute(&state);
+                    assert_eq!(result.state.score(), expected.score());
+                    algo.set_transpositions(1 << 12);
+                    let result = algo.search(&state);
+                    assert_eq!(result.state.score(), expected.score());
+                }
+            )*
+        }
+    }
+
+    search_tests! {
+        initial_state: ([], [], 0),
+        p1_winning_move: ([4, 1, 0], [5, 7, 8], 0),
+        second_player: ([0, 4], [8], 1),
+        drawing_game: ([4, 1, 6, 5], [8, 7, 2], 1),
+        won_game: ([4, 1, 0, 2], [5, 7, 8], 1),
+    }
+
+    #[test]
+    fn test_winning_move() {
+        let ruleset = connectn::TicTacToe::new();
+        let state = connectn::TicTacToeState::from_indices(&[4, 1, 0], &[5, 7, 8], 0);
+        let mut algo =
+            IterativeDeepening::new(&ruleset, zero_evaluator, limits::SearchLimits::default());
+        let result = algo.search(&state);
+        assert_eq!(result.state.plies(), vec![connectn::Ply::new(2)]);
+        assert_eq!(result.depth, 1);
+    }
+
+    #[test]
+    fn test_transpositions() {
+        let ruleset = connectn::TicTacToe::new();
+        let state = ruleset.initial_state();
+        let mut algo =
+            IterativeDeepening::new(&ruleset, zero_evaluator, limits::SearchLimits::default());
+        let nodes = algo.search(&state).statistics.nodes;
+        algo.set_transpositions(1 << 12);
+        let result = algo.search(&state);
+        assert_eq!(result.state.score(), 0.0);
+        assert!(result.statistics.nodes < nodes);
+        assert!(algo.transpositions().unwrap().len() < 500);
+    }
+
+    macro_rules! null_window_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let plies = $value;
+                    let ruleset = reversi::Reversi::<reversi::Classic>::new();
+                    let mut state = ruleset.initial_state();
+                    for index in plies.iter() {
+                        let ply = plies::BasicIterator::new(&ruleset, &state).nth(*index).unwrap();
+                        state = ruleset.play(&state, &ply).unwrap();
+                    }
+                    let search = |transpositions: bool| {
+                        let evaluator = evaluators::ReversiEvaluator::new();
+                        let mut algo = IterativeDeepening::new(&ruleset, evaluator, limits::SearchLimits::depth(4));
+                        // Unordered plies fail high on their null window more often
+                        algo.set_move_ordering(false);
+                        if transpositions {
+                            algo.set_transpositions(1 << 14);
+                        }
+                        algo.search(&state)
+                    };
+                    let expected = search(false);
+                    let result = search(true);
+                    assert!(expected.statistics.researches > 0);
+                    assert!(result.statistics.transposition_cutoffs > 0);
+                    assert_eq!(result.state.score(), expected.state.score());
+                }
+            )*
+        }
+    }
+
+    null_window_tests! {
+        reversi_opening: [],
+        reversi_early: [0, 1],
+        reversi_middle: [0, 1, 2, 0, 3, 1],
+        reversi_late: [1, 0, 1, 1, 0, 1, 0, 0, 1, 1],
+        reversi_later: [0, 0, 1, 0, 1, 1, 1, 0, 0, 1, 0, 1],
+    }
+
+    #[test]
+    fn test_move_ordering() {
+        let ruleset = connectn::TicTacToe::new();
+        let state = ruleset.initial_state();
+        let mut algo =
+            IterativeDeepening::new(&ruleset, zero_evaluator, limits::SearchLimits::default());
+        algo.set_move_ordering(false);
+        let unordered = algo.search(&state).statistics;
+        algo.set_move_ordering(true);
+        let ordered = algo.search(&state).statistics;
+        assert!(ordered.nodes < unordered.nodes);
+        assert!(ordered.ordering_efficiency() > unordered.ordering_efficiency());
+    }
+
+    #[test]
+    fn test_reversi_evaluator() {
+        let ruleset = reversi::Reversi::<reversi::Classic>::new();
+        let state = ruleset.initial_state();
+        let evaluator = evaluators::ReversiEvaluator::new();
+        let mut algo = IterativeDeepening::new(&ruleset, evaluator, limits::SearchLimits::depth(3));
+        let result = algo.search(&state);
+        assert_eq!(result.depth, 3);
+        assert!(result.state.score().abs() <= 1.0);
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let ruleset = connectn::Gomoku::new();
+        let state = ruleset.initial_state();
+        let mut algo =
+            IterativeDeepening::new(&ruleset, zero_evaluator, limits::SearchLimits::depth(2));
+        let result = algo.search(&state);
+        assert_eq!(result.depth, 2);
+        assert_eq!(result.state.plies().len(), 2);
+    }
+
+    #[test]
+    fn test_node_limit() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::Gomoku::new();
+        let state = ruleset.initial_state();
+        let mut algo =
+            IterativeDeepening::new(&ruleset, zero_evaluator, limits::SearchLimits::nodes(1000));
+        let result = algo.search(&state);
+        assert!(result.statistics.nodes <= 1000);
+        assert!(ai::Agent::play(&mut algo, &state).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let ruleset = connectn::Gomoku::new();
+        let state = ruleset.initial_state();
+        let limits = limits::SearchLimits::time(time::Duration::from_millis(50));
+        let mut algo = IterativeDeepening::new(&ruleset, zero_evaluator, limits);
+        let result = algo.search(&state);
+        assert!(result.depth >= 1);
+        assert!(result.elapsed < time::Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,240 @@
+use super::iterative_deepening;
+use super::limits;
+use super::shared_transpositions;
+use super::state;
+use crate::interface::ai;
+use crate::interface::rulesets;
+use std::error;
+use std::hash;
+use std::sync::atomic;
+use std::thread;
+use std::time;
+
+/// Default number of entries of the shared transposition table
+const DEFAULT_CAPACITY: usize = 1 << 20;
+
+/// Parallel iterative deepening, following the Lazy SMP scheme.
+///
+/// Every thread runs its own iterative deepening search of the same state, all of them sharing a
+/// lock-free transposition table. Helper threads start one iteration deeper every other thread,
+/// so that they fill the table with results the main thread looks up later on. The result is the
+/// one of the main thread, the helpers being stopped as soon as it completes.
+///
+/// The node limit applies to each thread separately.
+pub struct LazySmp<'a, RuleSet, Evaluator>
+where
+    RuleSet: rulesets::Deterministic
+        + rulesets::EncodableState
+        + rulesets::HasStatesWithSymmetries
+        + rulesets::TurnByTurn
+        + Sync,
+    RuleSet::State: Eq + hash::Hash + Ord + Sync,
+    RuleSet::Ply: PartialEq,
+    Evaluator: ai::Evaluator<RuleSet> + Clone + Send,
+{
+    ruleset: &'a RuleSet,
+    evaluator: Evaluator,
+    limits: limits::SearchLimits,
+    threads: usize,
+    transpositions: shared_transpositions::SharedTranspositionTable<RuleSet>,
+}
+
+impl<'a, RuleSet, Evaluator> LazySmp<'a, RuleSet, Evaluator>
+where
+    RuleSet: rulesets::Deterministic
+        + rulesets::EncodableState
+        + rulesets::HasStatesWithSymmetries
+        + rulesets::TurnByTurn
+        + Sync,
+    RuleSet::State: Eq + hash::Hash + Ord + Sync,
+    RuleSet::Ply: PartialEq,
+    Evaluator: ai::Evaluator<RuleSet> + Clone + Send,
+{
+    /// Creates a search running one thread per available core.
+    pub fn new(
+        ruleset: &'a RuleSet,
+        evaluator: Evaluator,
+        limits: limits::SearchLimits,
+    ) -> LazySmp<'a, RuleSet, Evaluator> {
+        LazySmp {
+            ruleset,
+            evaluator,
+            limits,
+            threads: thread::available_parallelism().map_or(1, |count| count.get()),
+            transpositions: shared_transpositions::SharedTranspositionTable::new(
+                ruleset,
+                DEFAULT_CAPACITY,
+            ),
+        }
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// Replaces the shared transposition table by an empty one of the given number of entries.
+    pub fn set_transpositions(&mut self, capacity: usize) {
+        self.transpositions =
+            shared_transpositions::SharedTranspositionTable::new(self.ruleset, capacity);
+    }
+
+    pub fn transpositions(&self) -> &shared_transpositions::SharedTranspositionTable<RuleSet> {
+        &self.transpositions
+    }
+
+    /// Searches the state, returning the result of the main thread along with the statistics
+    /// summed over all threads.
+    pub fn search(
+        &mut self,
+        state: &RuleSet::State,
+    ) -> iterative_deepening::SearchResult<RuleSet::Ply> {
+        let start = time::Instant::now();
+        self.transpositions.new_generation();
+        let stop = atomic::AtomicBool::new(false);
+        let (ruleset, limits, transpositions) = (self.ruleset, self.limits, &self.transpositions);
+        let mut result = crossbeam::scope(|scope| {
+            let helpers = (1..self.threads)
+                .map(|index| {
+                    let evaluator = self.evaluator.clone();
+                    let stop = &stop;
+                    scope.spawn(move |_| {
+                        let mut search = iterative_deepening::IterativeDeepening::new(
+                            ruleset, evaluator, limits,
+                        );
+                        search.set_shared_transpositions(transpositions);
+                        search.set_helper(stop, (index % 2) as u8);
+                        search.search(state).statistics
+                    })
+                })
+                .collect::<Vec<_>>();
+            let mut search = iterative_deepening::IterativeDeepening::new(
+                ruleset,
+                self.evaluator.clone(),
+                limits,
+            );
+            search.set_shared_transpositions(transpositions);
+            let mut result = search.search(state);
+            stop.store(true, atomic::Ordering::Relaxed);
+            for helper in helpers {
+                result.statistics += helper.join().unwrap();
+            }
+            result
+        })
+        .unwrap();
+        result.elapsed = start.elapsed();
+        result
+    }
+}
+
+impl<'a, RuleSet, Evaluator> ai::Agent<RuleSet> for LazySmp<'a, RuleSet, Evaluator>
+where
+    RuleSet: rulesets::Deterministic
+        + rulesets::EncodableState
+        + rulesets::HasStatesWithSymmetries
+        + rulesets::TurnByTurn
+        + Sync,
+    RuleSet::State: Eq + hash::Hash + Ord + Sync,
+    RuleSet::Ply: PartialEq,
+    Evaluator: ai::Evaluator<RuleSet> + Clone + Send,
+{
+    fn play(&mut self, state: &RuleSet::State) -> Result<RuleSet::Ply, Box<dyn error::Error>> {
+        match self.search(state).state {
+            state::State::TreeSearch { ply, .. } => Ok(ply),
+            _ => Err("no ply to play on a finished game".into()),
+        }
+    }
+}
+
+impl<'a, RuleSet, Evaluator> ai::Policy<RuleSet> for LazySmp<'a, RuleSet, Evaluator>
+where
+    RuleSet: rulesets::Deterministic
+        + rulesets::EncodableState
+        + rulesets::HasStatesWithSymmetries
+        + rulesets::TurnByTurn
+        + Sync,
+    RuleSet::State: Eq + hash::Hash + Ord + Sync,
+    RuleSet::Ply: PartialEq,
+    Evaluator: ai::Evaluator<RuleSet> + Clone + Send,
+{
+    fn predict(
+        &mut self,
+        state: &RuleSet::State,
+    ) -> Result<ai::Prediction<RuleSet>, Box<dyn error::Error>> {
+        let result = self.search(state);
+        match result.state {
+            state::State::TreeSearch { ply, .. } => Ok(ai::Prediction {
+                value: result.state.score().clamp(-1.0, 1.0),
+                probabilities: vec![(ply, 1.0)],
+            }),
+            _ => Err("no ply to play on a finished game".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluators;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::policies::minimax;
+    use crate::rulesets::connectn;
+    use crate::rulesets::reversi;
+
+    fn zero_evaluator<State>(_state: &State) -> f32 {
+        0.0
+    }
+
+    macro_rules! search_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (p1_indices, p2_indices, current_player) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let state = connectn::TicTacToeState::from_indices(&p1_indices, &p2_indices, current_player);
+                    let mut algo = LazySmp::new(&ruleset, zero_evaluator, limits::SearchLimits::default());
+                    algo.set_threads(4);
+                    algo.set_transpositions(1 << 12);
+                    let result = algo.search(&state);
+                    let expected = minimax::Negamax::new(&ruleset).compute(&state);
+                    assert_eq!(result.state.score(), expected.score());
+                }
+            )*
+        }
+    }
+
+    search_tests! {
+        initial_state: ([], [], 0),
+        p1_winning_move: ([4, 1, 0], [5, 7, 8], 0),
+        second_player: ([0, 4], [8], 1),
+        drawing_game: ([4, 1, 6, 5], [8, 7, 2], 1),
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let ruleset = reversi::Reversi::<reversi::Mini>::new();
+        let state = ruleset.initial_state();
+        let evaluator = evaluators::ReversiEvaluator::new();
+        let mut algo = LazySmp::new(&ruleset, evaluator, limits::SearchLimits::depth(4));
+        algo.set_threads(3);
+        algo.set_transpositions(1 << 14);
+        let result = algo.search(&state);
+        assert_eq!(result.depth, 4);
+        assert!(result.state.score().abs() <= 1.0);
+        assert!(!algo.transpositions().is_empty());
+        assert!(ai::Agent::play(&mut algo, &state).is_ok());
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let ruleset = connectn::Gomoku::new();
+        let state = ruleset.initial_state();
+        let limits = limits::SearchLimits::time(time::Duration::from_millis(50));
+        let mut algo = LazySmp::new(&ruleset, zero_evaluator, limits);
+        algo.set_threads(2);
+        algo.set_transpositions(1 << 12);
+        let result = algo.search(&state);
+        assert!(result.depth >= 1);
+        assert!(result.elapsed < time::Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,32 @@
+use std::time;
+
+/// Budget of a search, the search stopping as soon as any of the limits is reached.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub time: Option<time::Duration>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u8) -> SearchLimits {
+        SearchLimits {
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+
+    pub fn nodes(nodes: u64) -> SearchLimits {
+        SearchLimits {
+            nodes: Some(nodes),
+            ..Default::default()
+        }
+    }
+
+    pub fn time(time: time::Duration) -> SearchLimits {
+        SearchLimits {
+            time: Some(time),
+            ..Default::default()
+        }
+    }
+}
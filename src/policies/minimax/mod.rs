@@ -1,5 +1,23 @@
+mod iterative_deepening;
+mod lazy_smp;
+mod limits;
 mod negamax;
+mod ordering;
+mod shared_transpositions;
 mod state;
+mod statistics;
+mod transpositions;
 
+pub use iterative_deepening::IterativeDeepening;
+pub use iterative_deepening::SearchResult;
+pub use lazy_smp::LazySmp;
+pub use limits::SearchLimits;
 pub use negamax::Negamax;
+pub use ordering::MoveOrdering;
+pub use shared_transpositions::SharedTranspositionTable;
 pub use state::State;
+pub use statistics::SearchStatistics;
+pub use transpositions::Bound;
+pub use transpositions::Entry;
+pub use transpositions::TranspositionTable;
+pub use transpositions::Transpositions;
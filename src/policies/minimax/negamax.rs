@@ -1,35 +1,103 @@
+use super::ordering;
 use super::state;
+use super::statistics;
 use crate::interface::ai;
 use crate::interface::rulesets;
 use crate::tools::plies;
 use std::error;
 use std::f32;
 
+/// Exhaustive alpha-beta search, ordering its plies by killer and history heuristics.
 pub struct Negamax<'a, RuleSet>
 where
-    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    RuleSet: rulesets::Deterministic + rulesets::EncodableState + rulesets::TurnByTurn,
+    RuleSet::Ply: PartialEq,
 {
     ruleset: &'a RuleSet,
+    ordering: Option<ordering::MoveOrdering<RuleSet>>,
+    statistics: statistics::SearchStatistics,
 }
 
 impl<'a, RuleSet> Negamax<'a, RuleSet>
 where
-    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    RuleSet: rulesets::Deterministic + rulesets::EncodableState + rulesets::TurnByTurn,
+    RuleSet::Ply: PartialEq,
 {
     pub fn new(ruleset: &'a RuleSet) -> Negamax<RuleSet> {
-        Negamax { ruleset }
+        Negamax {
+            ruleset,
+            ordering: Some(ordering::MoveOrdering::new()),
+            statistics: statistics::SearchStatistics::default(),
+        }
+    }
+
+    /// Enables or disables the ordering of plies by killer and history heuristics, plies being
+    /// searched in the order of the ruleset otherwise.
+    pub fn set_move_ordering(&mut self, enabled: bool) {
+        self.ordering = if enabled {
+            Some(ordering::MoveOrdering::new())
+        } else {
+            None
+        };
     }
 
-    pub fn compute(&self, state: &RuleSet::State) -> state::State<RuleSet::Ply> {
-        self.iterate(state, f32::NEG_INFINITY, f32::INFINITY)
+    /// Returns the counters of the last call to `compute` or `analyse`.
+    pub fn statistics(&self) -> statistics::SearchStatistics {
+        self.statistics
+    }
+
+    pub fn compute(&mut self, state: &RuleSet::State) -> state::State<RuleSet::Ply> {
+        self.statistics = statistics::SearchStatistics::default();
+        self.iterate(state, 0, f32::NEG_INFINITY, f32::INFINITY)
+    }
+
+    /// Searches every available ply with a full window, returning their exact values from the
+    /// point of view of the current player, best first, along with their principal variations.
+    ///
+    /// Scores follow the scale of `MCTS::play_scores`: 1 for a win, 0.5 for a draw, 0 for a loss.
+    pub fn analyse(&mut self, state: &RuleSet::State) -> Vec<ai::PlyConsideration<RuleSet::Ply>> {
+        self.statistics = statistics::SearchStatistics::default();
+        if self.ruleset.status(state) != rulesets::Status::Ongoing {
+            return Vec::new();
+        }
+        let available_plies = plies::BasicIterator::new(self.ruleset, state).collect::<Vec<_>>();
+        let mut considerations = available_plies
+            .into_iter()
+            .map(|ply| {
+                let resulting_state = self.ruleset.play(state, &ply).unwrap();
+                let result = self.iterate(&resulting_state, 1, f32::NEG_INFINITY, f32::INFINITY);
+                let (score, win_rate, draw_rate) = match -result.score() {
+                    value if value > 0.0 => (1.0, 1.0, 0.0),
+                    value if value < 0.0 => (0.0, 0.0, 0.0),
+                    _ => (0.5, 0.0, 1.0),
+                };
+                ai::PlyConsideration {
+                    ply,
+                    score,
+                    win_rate,
+                    draw_rate,
+                    follow_up: result.plies(),
+                }
+            })
+            .collect::<Vec<_>>();
+        considerations.sort_by(|consideration_a, consideration_b| {
+            consideration_a
+                .score
+                .partial_cmp(&consideration_b.score)
+                .unwrap()
+                .reverse()
+        });
+        considerations
     }
 
     fn iterate(
-        &self,
+        &mut self,
         state: &RuleSet::State,
+        distance: usize,
         mut alpha: f32,
         beta: f32,
     ) -> state::State<RuleSet::Ply> {
+        self.statistics.nodes += 1;
         match self.ruleset.status(&state) {
             rulesets::Status::Win { player: winner } => {
                 if winner == self.ruleset.current_player(&state) {
@@ -40,15 +108,28 @@ where
             }
             rulesets::Status::Draw => state::State::Draw,
             rulesets::Status::Ongoing => {
-                let available_plies = plies::BasicIterator::new(self.ruleset, &state);
+                let mut available_plies =
+                    plies::BasicIterator::new(self.ruleset, &state).collect::<Vec<_>>();
+                if let Some(ordering) = &self.ordering {
+                    ordering.order(self.ruleset, &mut available_plies, None, distance);
+                }
                 let mut current_state = state::State::Unset;
-                for ply in available_plies {
-                    let resulting_state = self.ruleset.play(&state, &ply).unwrap();
-                    let iteration_state = self.iterate(&resulting_state, -beta, -alpha);
+                for (index, ply) in available_plies.iter().enumerate() {
+                    let resulting_state = self.ruleset.play(&state, ply).unwrap();
+                    let iteration_state =
+                        self.iterate(&resulting_state, distance + 1, -beta, -alpha);
                     if iteration_state.should_replace(&current_state) {
-                        current_state = state::State::tree_search(ply, iteration_state);
+                        current_state = state::State::tree_search(*ply, iteration_state);
                         alpha = alpha.max(current_state.score());
                         if alpha >= beta {
+                            self.statistics.cutoffs += 1;
+                            if index == 0 {
+                                self.statistics.first_ply_cutoffs += 1;
+                            }
+                            // The search has no depth limit, so every cutoff weighs the same
+                            if let Some(ordering) = &mut self.ordering {
+                                ordering.record_cutoff(self.ruleset, ply, distance, 1);
+                            }
                             break;
                         }
                     }
@@ -61,7 +142,8 @@ where
 
 impl<'a, RuleSet> ai::Agent<RuleSet> for Negamax<'a, RuleSet>
 where
-    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    RuleSet: rulesets::Deterministic + rulesets::EncodableState + rulesets::TurnByTurn,
+    RuleSet::Ply: PartialEq,
 {
     fn play(&mut self, state: &RuleSet::State) -> Result<RuleSet::Ply, Box<dyn error::Error>> {
         match self.compute(state) {
@@ -73,26 +155,45 @@ where
 
 impl<'a, RuleSet> ai::Policy<RuleSet> for Negamax<'a, RuleSet>
 where
-    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    RuleSet: rulesets::Deterministic + rulesets::EncodableState + rulesets::TurnByTurn,
+    RuleSet::Ply: PartialEq,
 {
+    /// Gives the exact outcome as value, and spreads the probabilities evenly over the plies
+    /// keeping it.
     fn predict(
         &mut self,
         state: &RuleSet::State,
     ) -> Result<ai::Prediction<RuleSet>, Box<dyn error::Error>> {
-        match self.compute(state) {
-            state::State::TreeSearch { ply, .. } => Ok(ai::Prediction {
-                value: 0.0,
-                probabilities: vec![(ply, 1.0)],
-            }),
-            _ => unreachable!(),
-        }
+        let considerations = self.analyse(state);
+        let best = match considerations.first() {
+            Some(consideration) => consideration.score,
+            None => return Err("no ply to play on a finished game".into()),
+        };
+        let optimal_plies = considerations
+            .into_iter()
+            .filter(|consideration| consideration.score == best)
+            .map(|consideration| consideration.ply)
+            .collect::<Vec<_>>();
+        let probability = 1.0 / optimal_plies.len() as f32;
+        Ok(ai::Prediction {
+            value: best * 2.0 - 1.0,
+            probabilities: optimal_plies
+                .into_iter()
+                .map(|ply| (ply, probability))
+                .collect(),
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Negamax;
+    use crate::interface::ai;
+    use crate::interface::rulesets;
+    use crate::interface::rulesets::Deterministic;
+    use crate::interface::rulesets::RuleSetTrait;
     use crate::rulesets::connectn;
+    use std::error;
     use std::f32;
 
     macro_rules! iterate_tests {
@@ -103,7 +204,7 @@ mod tests {
                     let (p1_indices, p2_indices, current_player, expected_indices, expected_score) = $value;
                     let ruleset = connectn::TicTacToe::new();
                     let state = connectn::TicTacToeState::from_indices(&p1_indices, &p2_indices, current_player);
-                    let algo = Negamax{ruleset: &ruleset};
+                    let mut algo = Negamax::new(&ruleset);
                     let result = algo.compute(&state);
                     assert_eq!(result.score(), expected_score);
                     let expected_plies: Vec<connectn::TicTacToePly> = expected_indices.iter().map(
@@ -126,4 +227,86 @@ mod tests {
         draw_p2_pov: ([4, 1, 6, 5], [8, 7, 2, 3], 1, vec![], 0.0),
         drawing_game: ([4, 1, 6, 5], [8, 7, 2], 1, vec![3], 0.0),
     }
+
+    macro_rules! analyse_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (p1_indices, p2_indices, current_player, expected_scores) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let state = connectn::TicTacToeState::from_indices(&p1_indices, &p2_indices, current_player);
+                    let mut algo = Negamax::new(&ruleset);
+                    let considerations = algo.analyse(&state);
+                    let mut scores = considerations
+                        .iter()
+                        .map(|consideration| (consideration.ply.index, consideration.score))
+                        .collect::<Vec<_>>();
+                    scores.sort_by_key(|(index, _)| *index);
+                    assert_eq!(scores, expected_scores);
+                    assert!(considerations.windows(2).all(|pair| pair[0].score >= pair[1].score));
+                    // Principal variations lead to the announced outcome
+                    for consideration in &considerations {
+                        let mut current = ruleset.play(&state, &consideration.ply).unwrap();
+                        for ply in &consideration.follow_up {
+                            current = ruleset.play(&current, ply).unwrap();
+                        }
+                        let expected_status = match consideration.score {
+                            score if score == 1.0 => rulesets::Status::Win { player: current_player },
+                            score if score == 0.0 => rulesets::Status::Win { player: 1 - current_player },
+                            _ => rulesets::Status::Draw,
+                        };
+                        assert_eq!(ruleset.status(&current), expected_status);
+                    }
+                }
+            )*
+        }
+    }
+
+    analyse_tests! {
+        winning_plies: ([4, 1, 0], [5, 7, 8], 0, vec![(2, 1.0), (3, 0.0), (6, 0.0)]),
+        drawing_ply: ([4, 1, 6, 5], [8, 7, 2], 1, vec![(0, 0.0), (3, 0.5)]),
+        finished_game: ([4, 1, 0, 2], [5, 7, 8], 1, vec![]),
+    }
+
+    macro_rules! predict_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<(), Box<dyn error::Error>> {
+                    let (p1_indices, p2_indices, current_player, expected_value, expected_plies) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let state = connectn::TicTacToeState::from_indices(&p1_indices, &p2_indices, current_player);
+                    let mut algo = Negamax::new(&ruleset);
+                    let prediction = ai::Policy::predict(&mut algo, &state)?;
+                    assert_eq!(prediction.value, expected_value);
+                    let mut plies = prediction.probabilities.iter().map(|(ply, _)| ply.index).collect::<Vec<_>>();
+                    plies.sort();
+                    assert_eq!(plies, expected_plies);
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    predict_tests! {
+        predict_win: ([4, 1, 0], [5, 7, 8], 0, 1.0, vec![2]),
+        predict_draw: ([4, 1, 6, 5], [8, 7, 2], 1, 0.0, vec![3]),
+        predict_loss: ([4, 1, 0], [5, 7], 1, -1.0, vec![2, 3, 6, 8]),
+    }
+
+    #[test]
+    fn test_move_ordering() {
+        let ruleset = connectn::TicTacToe::new();
+        let state = ruleset.initial_state();
+        let mut algo = Negamax::new(&ruleset);
+        algo.set_move_ordering(false);
+        let unordered_result = algo.compute(&state);
+        let unordered = algo.statistics();
+        algo.set_move_ordering(true);
+        let ordered_result = algo.compute(&state);
+        let ordered = algo.statistics();
+        assert_eq!(ordered_result.score(), unordered_result.score());
+        assert!(ordered.nodes < unordered.nodes);
+    }
 }
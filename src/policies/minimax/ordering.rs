@@ -0,0 +1,133 @@
+use crate::interface::rulesets;
+use std::cmp;
+
+const KILLER_COUNT: usize = 2;
+
+/// Orders plies so that the ones most likely to cause a cutoff are searched first: the hash ply,
+/// then the killer plies that caused a cutoff at the same distance from the root, then the other
+/// plies by decreasing history score.
+pub struct MoveOrdering<RuleSet: rulesets::EncodableState> {
+    killers: Vec<[Option<RuleSet::Ply>; KILLER_COUNT]>,
+    history: Vec<u32>,
+}
+
+impl<RuleSet: rulesets::EncodableState> MoveOrdering<RuleSet>
+where
+    RuleSet::Ply: PartialEq,
+{
+    pub fn new() -> MoveOrdering<RuleSet> {
+        MoveOrdering {
+            killers: Vec::new(),
+            history: vec![0; RuleSet::PLY_COUNT],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.killers.clear();
+        self.history.iter_mut().for_each(|score| *score = 0);
+    }
+
+    /// Halves the history scores, so that recent cutoffs weigh more than older ones.
+    pub fn age(&mut self) {
+        self.history.iter_mut().for_each(|score| *score /= 2);
+    }
+
+    pub fn order(
+        &self,
+        ruleset: &RuleSet,
+        plies: &mut [RuleSet::Ply],
+        hash_ply: Option<RuleSet::Ply>,
+        distance: usize,
+    ) {
+        let killers = self.killers.get(distance);
+        plies.sort_by_key(|ply| {
+            let rank = if Some(*ply) == hash_ply {
+                0
+            } else {
+                match killers
+                    .and_then(|killers| killers.iter().position(|killer| *killer == Some(*ply)))
+                {
+                    Some(position) => position + 1,
+                    None => KILLER_COUNT + 1,
+                }
+            };
+            (rank, cmp::Reverse(self.history[ruleset.encode_ply(ply)]))
+        });
+    }
+
+    /// Records a ply causing a cutoff at the given distance from the root with the given remaining
+    /// depth.
+    pub fn record_cutoff(
+        &mut self,
+        ruleset: &RuleSet,
+        ply: &RuleSet::Ply,
+        distance: usize,
+        depth: u8,
+    ) {
+        if self.killers.len() <= distance {
+            self.killers.resize(distance + 1, [None; KILLER_COUNT]);
+        }
+        let killers = &mut self.killers[distance];
+        if killers[0] != Some(*ply) {
+            killers.rotate_right(1);
+            killers[0] = Some(*ply);
+        }
+        let score = &mut self.history[ruleset.encode_ply(ply)];
+        *score = score.saturating_add(depth as u32 * depth as u32);
+    }
+}
+
+impl<RuleSet: rulesets::EncodableState> Default for MoveOrdering<RuleSet>
+where
+    RuleSet::Ply: PartialEq,
+{
+    fn default() -> MoveOrdering<RuleSet> {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rulesets::connectn;
+
+    fn build_plies(indices: &[u8]) -> Vec<connectn::TicTacToePly> {
+        indices
+            .iter()
+            .map(|index| connectn::Ply::new(*index))
+            .collect()
+    }
+
+    #[test]
+    fn test_order() {
+        let ruleset = connectn::TicTacToe::new();
+        let mut ordering = MoveOrdering::new();
+        ordering.record_cutoff(&ruleset, &connectn::Ply::new(8), 0, 3);
+        ordering.record_cutoff(&ruleset, &connectn::Ply::new(7), 1, 1);
+        ordering.record_cutoff(&ruleset, &connectn::Ply::new(6), 1, 2);
+        let mut plies = build_plies(&[0, 1, 2, 3, 4, 5, 6, 7, 8]);
+        ordering.order(&ruleset, &mut plies, Some(connectn::Ply::new(4)), 1);
+        assert_eq!(plies, build_plies(&[4, 6, 7, 8, 0, 1, 2, 3, 5]));
+    }
+
+    #[test]
+    fn test_age() {
+        let ruleset = connectn::TicTacToe::new();
+        let mut ordering = MoveOrdering::new();
+        ordering.record_cutoff(&ruleset, &connectn::Ply::new(8), 0, 2);
+        ordering.record_cutoff(&ruleset, &connectn::Ply::new(7), 0, 1);
+        ordering.age();
+        assert_eq!(ordering.history[8], 2);
+        assert_eq!(ordering.history[7], 0);
+        let mut plies = build_plies(&[7, 8]);
+        ordering.order(&ruleset, &mut plies, None, 1);
+        assert_eq!(plies, build_plies(&[8, 7]));
+        ordering.age();
+        assert_eq!(ordering.history[8], 1);
+        // Recent cutoffs outweigh the aged ones
+        ordering.record_cutoff(&ruleset, &connectn::Ply::new(7), 0, 2);
+        let mut plies = build_plies(&[8, 7]);
+        ordering.order(&ruleset, &mut plies, None, 1);
+        assert_eq!(plies, build_plies(&[7, 8]));
+    }
+}
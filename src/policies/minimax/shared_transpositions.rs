@@ -0,0 +1,269 @@
+use super::transpositions;
+use crate::interface::rulesets;
+use crate::tools::symmetries;
+use crate::utils::hashing;
+use std::hash;
+use std::sync::atomic;
+
+const VALUE_BITS: u32 = 32;
+const BOUND_SHIFT: u32 = VALUE_BITS;
+const DEPTH_SHIFT: u32 = BOUND_SHIFT + 2;
+const PLY_SHIFT: u32 = DEPTH_SHIFT + 8;
+const GENERATION_SHIFT: u32 = PLY_SHIFT + 16;
+const GENERATION_MASK: u64 = 0x3f;
+/// Encoded ply of entries without any
+const NO_PLY: u64 = 0xffff;
+
+/// Fixed-size table of search results, shared by concurrent searches without locking.
+///
+/// Each slot holds two atomic words: the packed entry, and its key xored with the entry. A slot
+/// torn by concurrent writes does not match its key anymore, and is read as empty. Keys are the
+/// FNV hashes of the canonical states, hash collisions between distinct states going undetected.
+///
+/// As in `TranspositionTable`, plies are stored in the frame of the canonical state, and the
+/// replacement favours deeper searches over older generations.
+pub struct SharedTranspositionTable<RuleSet>
+where
+    RuleSet: rulesets::EncodableState + rulesets::HasStatesWithSymmetries,
+    RuleSet::State: Eq + hash::Hash + Ord,
+{
+    ruleset: RuleSet,
+    slots: Vec<[atomic::AtomicU64; 2]>,
+    generation: atomic::AtomicU64,
+}
+
+impl<RuleSet> SharedTranspositionTable<RuleSet>
+where
+    RuleSet: rulesets::EncodableState + rulesets::HasStatesWithSymmetries,
+    RuleSet::State: Eq + hash::Hash + Ord,
+{
+    pub fn new(ruleset: &RuleSet, capacity: usize) -> SharedTranspositionTable<RuleSet> {
+        debug_assert!(
+            (RuleSet::PLY_COUNT as u64) < NO_PLY,
+            "too many plies to be packed in a shared transposition table"
+        );
+        SharedTranspositionTable {
+            ruleset: ruleset.clone(),
+            slots: (0..capacity.max(1))
+                .map(|_| [atomic::AtomicU64::new(0), atomic::AtomicU64::new(0)])
+                .collect(),
+            generation: atomic::AtomicU64::new(0),
+        }
+    }
+
+    /// Ages the entries, so that they are replaced first.
+    pub fn new_generation(&self) {
+        self.generation.fetch_add(1, atomic::Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot[0].store(0, atomic::Ordering::Relaxed);
+            slot[1].store(0, atomic::Ordering::Relaxed);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot[1].load(atomic::Ordering::Relaxed) != 0)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, state: &RuleSet::State) -> Option<transpositions::Entry<RuleSet::Ply>> {
+        let (canonical_state, _) = symmetries::canonicalize(&self.ruleset, state);
+        let key = hashing::hash(&canonical_state);
+        let data = self.load(key)?;
+        let mut entry = self.unpack(data)?;
+        entry.ply = entry
+            .ply
+            .map(|ply| transpositions::restore_ply(&self.ruleset, &canonical_state, state, &ply));
+        Some(entry)
+    }
+
+    pub fn insert(&self, state: &RuleSet::State, mut entry: transpositions::Entry<RuleSet::Ply>) {
+        let (canonical_state, symmetry) = symmetries::canonicalize(&self.ruleset, state);
+        entry.ply = entry.ply.map(|ply| self.ruleset.swap_ply(&ply, &symmetry));
+        let key = hashing::hash(&canonical_state);
+        let slot = &self.slots[self.index(key)];
+        let current_data = slot[1].load(atomic::Ordering::Relaxed);
+        let current_key = slot[0].load(atomic::Ordering::Relaxed) ^ current_data;
+        let generation = self.generation.load(atomic::Ordering::Relaxed) & GENERATION_MASK;
+        let replace = current_data == 0
+            || current_key == key
+            || (current_data >> GENERATION_SHIFT) & GENERATION_MASK != generation
+            || (current_data >> DEPTH_SHIFT) as u8 <= entry.depth;
+        if replace {
+            let data = self.pack(&entry, generation);
+            slot[0].store(key ^ data, atomic::Ordering::Relaxed);
+            slot[1].store(data, atomic::Ordering::Relaxed);
+        }
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.slots.len() as u64) as usize
+    }
+
+    fn load(&self, key: u64) -> Option<u64> {
+        let slot = &self.slots[self.index(key)];
+        let data = slot[1].load(atomic::Ordering::Relaxed);
+        if data == 0 || slot[0].load(atomic::Ordering::Relaxed) ^ data != key {
+            return None;
+        }
+        Some(data)
+    }
+
+    fn pack(&self, entry: &transpositions::Entry<RuleSet::Ply>, generation: u64) -> u64 {
+        // Bounds are encoded from 1, so that packed entries are never 0, the empty slot
+        let bound = match entry.bound {
+            transpositions::Bound::Exact => 1,
+            transpositions::Bound::Lower => 2,
+            transpositions::Bound::Upper => 3,
+        };
+        let ply = match &entry.ply {
+            Some(ply) => self.ruleset.encode_ply(ply) as u64,
+            None => NO_PLY,
+        };
+        entry.value.to_bits() as u64
+            | bound << BOUND_SHIFT
+            | (entry.depth as u64) << DEPTH_SHIFT
+            | ply << PLY_SHIFT
+            | generation << GENERATION_SHIFT
+    }
+
+    fn unpack(&self, data: u64) -> Option<transpositions::Entry<RuleSet::Ply>> {
+        let bound = match (data >> BOUND_SHIFT) & 0x3 {
+            1 => transpositions::Bound::Exact,
+            2 => transpositions::Bound::Lower,
+            3 => transpositions::Bound::Upper,
+            _ => return None,
+        };
+        let ply = match (data >> PLY_SHIFT) & NO_PLY {
+            NO_PLY => None,
+            index => Some(self.ruleset.decode_ply(index as usize)),
+        };
+        Some(transpositions::Entry {
+            value: f32::from_bits(data as u32),
+            bound,
+            depth: (data >> DEPTH_SHIFT) as u8,
+            ply,
+        })
+    }
+}
+
+/// Searches borrowing the table share it, its generation being managed by its owner.
+impl<RuleSet> transpositions::Transpositions<RuleSet> for &SharedTranspositionTable<RuleSet>
+where
+    RuleSet: rulesets::EncodableState + rulesets::HasStatesWithSymmetries,
+    RuleSet::State: Eq + hash::Hash + Ord,
+{
+    fn new_generation(&mut self) {}
+
+    fn len(&self) -> usize {
+        SharedTranspositionTable::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        SharedTranspositionTable::is_empty(self)
+    }
+
+    fn get(&self, state: &RuleSet::State) -> Option<transpositions::Entry<RuleSet::Ply>> {
+        SharedTranspositionTable::get(self, state)
+    }
+
+    fn insert(&mut self, state: &RuleSet::State, entry: transpositions::Entry<RuleSet::Ply>) {
+        SharedTranspositionTable::insert(self, state, entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rulesets::connectn;
+
+    fn build_entry(ply: Option<u8>) -> transpositions::Entry<connectn::TicTacToePly> {
+        transpositions::Entry {
+            value: -0.25,
+            bound: transpositions::Bound::Upper,
+            depth: 3,
+            ply: ply.map(connectn::Ply::new),
+        }
+    }
+
+    #[test]
+    fn test_symmetric_states() {
+        let ruleset = connectn::TicTacToe::new();
+        let table = SharedTranspositionTable::new(&ruleset, 64);
+        let state = connectn::TicTacToeState::from_indices(&[0], &[1], 0);
+        table.insert(&state, build_entry(Some(2)));
+        let mirrored = connectn::TicTacToeState::from_indices(&[2], &[1], 0);
+        assert_eq!(table.get(&mirrored), Some(build_entry(Some(0))));
+        let other = connectn::TicTacToeState::from_indices(&[0], &[4], 0);
+        assert_eq!(table.get(&other), None);
+        assert_eq!(table.len(), 1);
+        table.clear();
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn test_replacement() {
+        let ruleset = connectn::TicTacToe::new();
+        let table = SharedTranspositionTable::new(&ruleset, 1);
+        let state = connectn::TicTacToeState::from_indices(&[0], &[1], 0);
+        let other = connectn::TicTacToeState::from_indices(&[0], &[4], 0);
+        table.insert(&state, build_entry(None));
+        let shallow_entry = transpositions::Entry {
+            depth: 1,
+            ..build_entry(None)
+        };
+        table.insert(&other, shallow_entry);
+        assert_eq!(table.get(&other), None);
+        table.new_generation();
+        table.insert(&other, shallow_entry);
+        assert_eq!(table.get(&other), Some(shallow_entry));
+        assert_eq!(table.get(&state), None);
+    }
+
+    #[test]
+    fn test_concurrent_insertions() {
+        let ruleset = connectn::TicTacToe::new();
+        let table = SharedTranspositionTable::new(&ruleset, 16);
+        let states = (0..9)
+            .map(|index| connectn::TicTacToeState::from_indices(&[index], &[], 1))
+            .collect::<Vec<_>>();
+        crossbeam::scope(|scope| {
+            for thread_index in 0..4 {
+                let table = &table;
+                let states = &states;
+                scope.spawn(move |_| {
+                    for _ in 0..100 {
+                        for (index, state) in states.iter().enumerate() {
+                            let entry = transpositions::Entry {
+                                value: index as f32,
+                                bound: transpositions::Bound::Exact,
+                                depth: thread_index,
+                                ply: None,
+                            };
+                            table.insert(state, entry);
+                        }
+                    }
+                });
+            }
+        })
+        .unwrap();
+        // Whatever the interleaving, entries are either missing or written for a symmetric state
+        for state in &states {
+            if let Some(entry) = table.get(state) {
+                let writer = &states[entry.value as usize];
+                assert_eq!(
+                    symmetries::canonicalize(&ruleset, writer).0,
+                    symmetries::canonicalize(&ruleset, state).0,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::ops;
+
+/// Counters of a search, to measure the efficiency of its pruning.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SearchStatistics {
+    pub nodes: u64,
+    pub cutoffs: u64,
+    /// Cutoffs caused by the first searched ply
+    pub first_ply_cutoffs: u64,
+    /// Plies searched again with a full window after failing high on a null window
+    pub researches: u64,
+    pub transposition_hits: u64,
+    pub transposition_cutoffs: u64,
+}
+
+impl SearchStatistics {
+    /// Share of the cutoffs caused by the first searched ply, 1 for a perfect ordering.
+    pub fn ordering_efficiency(&self) -> f32 {
+        if self.cutoffs == 0 {
+            return 0.0;
+        }
+        self.first_ply_cutoffs as f32 / self.cutoffs as f32
+    }
+}
+
+/// Sums the counters of concurrent searches.
+impl ops::AddAssign for SearchStatistics {
+    fn add_assign(&mut self, other: SearchStatistics) {
+        self.nodes += other.nodes;
+        self.cutoffs += other.cutoffs;
+        self.first_ply_cutoffs += other.first_ply_cutoffs;
+        self.researches += other.researches;
+        self.transposition_hits += other.transposition_hits;
+        self.transposition_cutoffs += other.transposition_cutoffs;
+    }
+}
@@ -0,0 +1,219 @@
+use crate::interface::rulesets;
+use crate::interface::rulesets::SymmetryIteratorTrait;
+use crate::tools::symmetries;
+use std::collections::hash_map;
+use std::hash;
+use std::hash::Hash;
+use std::hash::Hasher;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Bound {
+    Exact,
+    /// The value is at least the stored one
+    Lower,
+    /// The value is at most the stored one
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Entry<Ply: Copy> {
+    pub value: f32,
+    pub bound: Bound,
+    /// Remaining depth of the search the value comes from, `u8::MAX` for exhaustive searches
+    pub depth: u8,
+    pub ply: Option<Ply>,
+}
+
+/// Storage of search results, looked up by the searches before exploring a state.
+pub trait Transpositions<RuleSet: rulesets::RuleSetTrait> {
+    /// Ages the entries, so that they are replaced first.
+    fn new_generation(&mut self);
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool;
+    fn get(&self, state: &RuleSet::State) -> Option<Entry<RuleSet::Ply>>;
+    fn insert(&mut self, state: &RuleSet::State, entry: Entry<RuleSet::Ply>);
+}
+
+/// Maps a ply stored in the frame of the canonical state back to the frame of the given state.
+pub(super) fn restore_ply<RuleSet: rulesets::HasStatesWithSymmetries>(
+    ruleset: &RuleSet,
+    canonical_state: &RuleSet::State,
+    state: &RuleSet::State,
+    ply: &RuleSet::Ply,
+) -> RuleSet::Ply
+where
+    RuleSet::State: Eq,
+{
+    let symmetry = RuleSet::SymmetryIterator::new(ruleset)
+        .find(|symmetry| ruleset.swap_state(canonical_state, symmetry) == *state)
+        .unwrap();
+    ruleset.swap_ply(ply, &symmetry)
+}
+
+struct Slot<RuleSet: rulesets::RuleSetTrait> {
+    state: RuleSet::State,
+    entry: Entry<RuleSet::Ply>,
+    generation: u8,
+}
+
+/// Fixed-size table of search results.
+///
+/// States are stored under their canonical form among their symmetries, the smallest one, so that
+/// symmetric positions share their entry. Plies are stored in the frame of the canonical state,
+/// and mapped back to the frame of the requested state.
+///
+/// A slot is replaced by a new entry for the same state, for a deeper or equally deep search, or
+/// when it comes from a previous generation.
+pub struct TranspositionTable<RuleSet: rulesets::HasStatesWithSymmetries>
+where
+    RuleSet::State: Eq + hash::Hash + Ord,
+{
+    ruleset: RuleSet,
+    slots: Vec<Option<Slot<RuleSet>>>,
+    generation: u8,
+}
+
+impl<RuleSet: rulesets::HasStatesWithSymmetries> TranspositionTable<RuleSet>
+where
+    RuleSet::State: Eq + hash::Hash + Ord,
+{
+    pub fn new(ruleset: &RuleSet, capacity: usize) -> TranspositionTable<RuleSet> {
+        TranspositionTable {
+            ruleset: ruleset.clone(),
+            slots: (0..capacity.max(1)).map(|_| None).collect(),
+            generation: 0,
+        }
+    }
+
+    /// Ages the entries, so that they are replaced first.
+    pub fn new_generation(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.iter().all(|slot| slot.is_none())
+    }
+
+    pub fn get(&self, state: &RuleSet::State) -> Option<Entry<RuleSet::Ply>> {
+        let (canonical_state, _) = symmetries::canonicalize(&self.ruleset, state);
+        let slot = self.slots[self.index(&canonical_state)].as_ref()?;
+        if slot.state != canonical_state {
+            return None;
+        }
+        let mut entry = slot.entry;
+        entry.ply = entry
+            .ply
+            .map(|ply| restore_ply(&self.ruleset, &canonical_state, state, &ply));
+        Some(entry)
+    }
+
+    pub fn insert(&mut self, state: &RuleSet::State, mut entry: Entry<RuleSet::Ply>) {
+        let (canonical_state, symmetry) = symmetries::canonicalize(&self.ruleset, state);
+        entry.ply = entry.ply.map(|ply| self.ruleset.swap_ply(&ply, &symmetry));
+        let index = self.index(&canonical_state);
+        let replace = match &self.slots[index] {
+            None => true,
+            Some(slot) => {
+                slot.state == canonical_state
+                    || slot.generation != self.generation
+                    || slot.entry.depth <= entry.depth
+            }
+        };
+        if replace {
+            self.slots[index] = Some(Slot {
+                state: canonical_state,
+                entry,
+                generation: self.generation,
+            });
+        }
+    }
+
+    fn index(&self, state: &RuleSet::State) -> usize {
+        let mut hasher = hash_map::DefaultHasher::new();
+        state.hash(&mut hasher);
+        (hasher.finish() % self.slots.len() as u64) as usize
+    }
+}
+
+impl<RuleSet: rulesets::HasStatesWithSymmetries> Transpositions<RuleSet>
+    for TranspositionTable<RuleSet>
+where
+    RuleSet::State: Eq + hash::Hash + Ord,
+{
+    fn new_generation(&mut self) {
+        TranspositionTable::new_generation(self)
+    }
+
+    fn len(&self) -> usize {
+        TranspositionTable::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        TranspositionTable::is_empty(self)
+    }
+
+    fn get(&self, state: &RuleSet::State) -> Option<Entry<RuleSet::Ply>> {
+        TranspositionTable::get(self, state)
+    }
+
+    fn insert(&mut self, state: &RuleSet::State, entry: Entry<RuleSet::Ply>) {
+        TranspositionTable::insert(self, state, entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rulesets::connectn;
+
+    fn build_entry(ply: Option<u8>) -> Entry<connectn::TicTacToePly> {
+        Entry {
+            value: 1.0,
+            bound: Bound::Exact,
+            depth: 2,
+            ply: ply.map(connectn::Ply::new),
+        }
+    }
+
+    #[test]
+    fn test_symmetric_states() {
+        let ruleset = connectn::TicTacToe::new();
+        let mut table = TranspositionTable::new(&ruleset, 64);
+        let state = connectn::TicTacToeState::from_indices(&[0], &[1], 0);
+        table.insert(&state, build_entry(Some(2)));
+        let mirrored = connectn::TicTacToeState::from_indices(&[2], &[1], 0);
+        assert_eq!(table.get(&mirrored), Some(build_entry(Some(0))));
+        let rotated = connectn::TicTacToeState::from_indices(&[6], &[3], 0);
+        assert_eq!(table.get(&rotated), Some(build_entry(Some(0))));
+        let other = connectn::TicTacToeState::from_indices(&[0], &[4], 0);
+        assert_eq!(table.get(&other), None);
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn test_replacement() {
+        let ruleset = connectn::TicTacToe::new();
+        let mut table = TranspositionTable::new(&ruleset, 1);
+        let state = connectn::TicTacToeState::from_indices(&[0], &[1], 0);
+        let other = connectn::TicTacToeState::from_indices(&[0], &[4], 0);
+        table.insert(&state, build_entry(None));
+        let shallow_entry = Entry {
+            depth: 1,
+            ..build_entry(None)
+        };
+        table.insert(&other, shallow_entry);
+        assert_eq!(table.get(&other), None);
+        table.new_generation();
+        table.insert(&other, shallow_entry);
+        assert_eq!(table.get(&other), Some(shallow_entry));
+        assert_eq!(table.get(&state), None);
+    }
+}
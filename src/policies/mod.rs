@@ -1,5 +1,9 @@
+pub mod endgame;
 pub mod mcts;
 pub mod minimax;
 pub mod neural;
 pub mod policy_neural;
+pub mod proof_number;
 pub mod puct;
+pub mod retrograde;
+pub mod threat_space;
@@ -76,22 +76,52 @@ where
         let encoded_state = Implementation::encode_state(state);
         let allowed_plies = self.compute_allowed_plies(state);
         let (value, raw_probs) = self.network.predict(&encoded_state, &allowed_plies)?;
-        let probabilities = raw_probs
+        Ok(to_prediction::<RuleSet, Implementation>(value, &raw_probs))
+    }
+
+    fn predict_batch(
+        &mut self,
+        states: &[RuleSet::State],
+    ) -> Result<Vec<ai::Prediction<RuleSet>>, Box<dyn error::Error>> {
+        let encoded_states = states
             .iter()
-            .enumerate()
-            .filter_map(|(index, probability)| {
-                if *probability == 0.0 {
-                    None
-                } else {
-                    Some((Implementation::decode_ply(index), *probability))
-                }
-            })
-            .collect();
-        let result = ai::Prediction {
-            value,
-            probabilities,
-        };
-        Ok(result)
+            .map(|state| Implementation::encode_state(state))
+            .collect::<Vec<_>>();
+        let allowed_plies = states
+            .iter()
+            .map(|state| self.compute_allowed_plies(state))
+            .collect::<Vec<_>>();
+        let outputs = self
+            .network
+            .predict_batch(&encoded_states, &allowed_plies)?;
+        Ok(outputs
+            .iter()
+            .map(|(value, raw_probs)| to_prediction::<RuleSet, Implementation>(*value, raw_probs))
+            .collect())
+    }
+}
+
+/// Builds a prediction from the outputs of the network, leaving out the plies it does not
+/// consider.
+fn to_prediction<RuleSet, Implementation>(value: f32, raw_probs: &[f32]) -> ai::Prediction<RuleSet>
+where
+    RuleSet: rulesets::RuleSetTrait,
+    Implementation: implementations::Implementation<RuleSet>,
+{
+    let probabilities = raw_probs
+        .iter()
+        .enumerate()
+        .filter_map(|(index, probability)| {
+            if *probability == 0.0 {
+                None
+            } else {
+                Some((Implementation::decode_ply(index), *probability))
+            }
+        })
+        .collect();
+    ai::Prediction {
+        value,
+        probabilities,
     }
 }
 
@@ -10,6 +10,9 @@ use tensorflow as tf;
 const MODEL_FILENAME: &str = "model.pb";
 const VARIABLES_FOLDER: &str = "variables";
 
+/// Value of a state along with the probabilities of its plies
+pub type NetworkOutput = (f32, Vec<f32>);
+
 pub struct Network {
     state_dimensions: Vec<u64>,
     session: tf::Session,
@@ -52,7 +55,7 @@ impl Network {
         &self,
         state: &[f32],
         allowed_plies: &[f32],
-    ) -> Result<(f32, Vec<f32>), Box<dyn error::Error>> {
+    ) -> Result<NetworkOutput, Box<dyn error::Error>> {
         let state_value = tf::Tensor::new(&self.state_dimensions[..]).with_values(&state)?;
         let allowed_plies_value =
             tf::Tensor::new(&[1, self.ply_count][..]).with_values(&allowed_plies)?;
@@ -73,6 +76,38 @@ impl Network {
         Ok((value_value, probabilities))
     }
 
+    /// Predicts several states in a single run of the network.
+    pub fn predict_batch(
+        &self,
+        states: &[Vec<f32>],
+        allowed_plies: &[Vec<f32>],
+    ) -> Result<Vec<NetworkOutput>, Box<dyn error::Error>> {
+        let batch_size = states.len() as u64;
+        let mut state_dimensions = self.state_dimensions.clone();
+        state_dimensions[0] = batch_size;
+        let states_value = tf::Tensor::new(&state_dimensions[..]).with_values(&states.concat())?;
+        let allowed_plies_value = tf::Tensor::new(&[batch_size, self.ply_count][..])
+            .with_values(&allowed_plies.concat())?;
+        let training_value = tf::Tensor::new(&[][..]).with_values(&[false])?;
+        let mut run_args = tf::SessionRunArgs::new();
+        run_args.add_feed(&self.fields.state_in, 0, &states_value);
+        run_args.add_feed(&self.fields.allowed_plies_in, 0, &allowed_plies_value);
+        run_args.add_feed(&self.fields.is_training_in, 0, &training_value);
+        let probs_fetch = run_args.request_fetch(&self.fields.probs_out, 0);
+        let value_fetch = run_args.request_fetch(&self.fields.value_out, 0);
+        self.session.run(&mut run_args)?;
+        let values_value = run_args.fetch::<f32>(value_fetch)?;
+        let probs_value = run_args.fetch::<f32>(probs_fetch)?;
+        let ply_count = self.ply_count as usize;
+        Ok((0..states.len())
+            .map(|index| {
+                let probabilities =
+                    probs_value[index * ply_count..(index + 1) * ply_count].to_vec();
+                (values_value[index], probabilities)
+            })
+            .collect())
+    }
+
     pub fn train(
         &self,
         sample: &samples::TrainSample,
@@ -156,4 +156,15 @@ where
         let qvalue = self.network.get_qvalue(&encoded_state)?;
         Ok(qvalue)
     }
+
+    fn evaluate_batch(
+        &mut self,
+        states: &[RuleSet::State],
+    ) -> Result<Vec<f32>, Box<dyn error::Error>> {
+        let encoded_states = states
+            .iter()
+            .map(|state| self.ruleset.encode_state(state))
+            .collect::<Vec<_>>();
+        self.network.get_qvalues(&encoded_states)
+    }
 }
@@ -75,6 +75,20 @@ impl Network {
         Ok(qvalue)
     }
 
+    /// Evaluates several states in a single run of the network.
+    pub fn get_qvalues(&self, states: &[Vec<f32>]) -> Result<Vec<f32>, Box<dyn error::Error>> {
+        let state_value = tf::Tensor::new(&[states.len() as u64, self.state_size][..])
+            .with_values(&states.concat())?;
+
+        let mut run_args = tf::SessionRunArgs::new();
+        run_args.add_feed(&self.fields.state_in, 0, &state_value);
+        let qvalue_fetch = run_args.request_fetch(&self.fields.qvalue_out, 0);
+        self.session.run(&mut run_args)?;
+
+        let qvalues = run_args.fetch::<f32>(qvalue_fetch)?;
+        Ok(qvalues[..states.len()].to_vec())
+    }
+
     pub fn get_probabilities(
         &self,
         state: &[f32],
@@ -0,0 +1,82 @@
+use super::df_pn;
+use super::results;
+use crate::interface::ai;
+use crate::interface::rulesets;
+use std::error;
+use std::hash;
+
+/// Plays the first ply of a proven win, found by a depth-first proof-number search, and lets
+/// another agent play when the search fails to prove one.
+pub struct ProvingAgent<'a, RuleSet, Fallback>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    RuleSet::State: Eq + hash::Hash,
+    Fallback: ai::Agent<RuleSet>,
+{
+    search: df_pn::DfPn<'a, RuleSet>,
+    fallback: Fallback,
+}
+
+impl<'a, RuleSet, Fallback> ProvingAgent<'a, RuleSet, Fallback>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    RuleSet::State: Eq + hash::Hash,
+    Fallback: ai::Agent<RuleSet>,
+{
+    /// Creates an agent searching at most the given number of nodes at each turn.
+    pub fn new(
+        ruleset: &'a RuleSet,
+        node_limit: u64,
+        fallback: Fallback,
+    ) -> ProvingAgent<'a, RuleSet, Fallback> {
+        let mut search = df_pn::DfPn::new(ruleset);
+        search.set_node_limit(node_limit);
+        ProvingAgent { search, fallback }
+    }
+}
+
+impl<'a, RuleSet, Fallback> ai::Agent<RuleSet> for ProvingAgent<'a, RuleSet, Fallback>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    RuleSet::State: Eq + hash::Hash,
+    Fallback: ai::Agent<RuleSet>,
+{
+    fn play(&mut self, state: &RuleSet::State) -> Result<RuleSet::Ply, Box<dyn error::Error>> {
+        let result = self.search.search(state);
+        match (result.proof, result.line.first()) {
+            (results::Proof::Proven, Some(ply)) => Ok(*ply),
+            _ => self.fallback.play(state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents;
+    use crate::rulesets::connectn;
+
+    macro_rules! play_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<(), Box<dyn error::Error>> {
+                    let (p1_indices, p2_indices, current_player, expected_plies) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let state = connectn::TicTacToeState::from_indices(&p1_indices, &p2_indices, current_player);
+                    let mut agent = ProvingAgent::new(&ruleset, 1000, agents::Random::new(&ruleset));
+                    let ply = ai::Agent::play(&mut agent, &state)?;
+                    let expected_plies = expected_plies.iter().cloned().map(connectn::Ply::new).collect::<Vec<_>>();
+                    assert!(expected_plies.contains(&ply));
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    play_tests! {
+        immediate_win: ([4, 1, 0], [5, 7, 8], 0, [2]),
+        double_threat: ([4, 0], [8, 7], 0, [2, 3, 6]),
+        fallback: ([], [], 0, [0, 1, 2, 3, 4, 5, 6, 7, 8]),
+    }
+}
@@ -0,0 +1,260 @@
+use super::numbers;
+use super::results;
+use crate::interface::rulesets;
+use crate::tools::plies;
+use std::collections::HashMap;
+use std::hash;
+
+/// Default maximum number of searched nodes
+const DEFAULT_NODE_LIMIT: u64 = 1_000_000;
+
+/// Depth-first proof-number search.
+///
+/// It explores the same nodes as the best-first search, in a depth-first manner: the most-proving
+/// child is searched until its numbers exceed thresholds derived from the second best child. The
+/// numbers of the searched states are stored in a transposition table, from the point of view of
+/// their current player: `phi` is the proof number of OR nodes and the disproof number of AND
+/// nodes, `delta` the other one.
+///
+/// Memory only holds the transposition table, which makes it suited to deeper proofs.
+pub struct DfPn<'a, RuleSet>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    RuleSet::State: Eq + hash::Hash,
+{
+    ruleset: &'a RuleSet,
+    node_limit: u64,
+    nodes: u64,
+    transpositions: HashMap<RuleSet::State, (u64, u64)>,
+    attacker: rulesets::Player,
+}
+
+impl<'a, RuleSet> DfPn<'a, RuleSet>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    RuleSet::State: Eq + hash::Hash,
+{
+    pub fn new(ruleset: &'a RuleSet) -> DfPn<'a, RuleSet> {
+        DfPn {
+            ruleset,
+            node_limit: DEFAULT_NODE_LIMIT,
+            nodes: 0,
+            transpositions: HashMap::new(),
+            attacker: 0,
+        }
+    }
+
+    pub fn set_node_limit(&mut self, node_limit: u64) {
+        self.node_limit = node_limit;
+    }
+
+    /// Tries to prove that the current player of the state can force a win.
+    pub fn search(&mut self, state: &RuleSet::State) -> results::ProofResult<RuleSet::Ply> {
+        self.nodes = 0;
+        self.transpositions.clear();
+        self.attacker = self.ruleset.current_player(state);
+        let (phi, delta) =
+            self.multiple_iterative_deepening(state, numbers::INFINITY, numbers::INFINITY);
+        let proof = if phi == 0 {
+            results::Proof::Proven
+        } else if delta == 0 {
+            results::Proof::Disproven
+        } else {
+            results::Proof::Unknown
+        };
+        let line = if proof == results::Proof::Proven {
+            self.winning_line(state)
+        } else {
+            Vec::new()
+        };
+        results::ProofResult {
+            proof,
+            line,
+            nodes: self.nodes,
+        }
+    }
+
+    /// Returns the numbers of a state from the point of view of its current player, unsearched
+    /// states counting as 1.
+    fn lookup(&self, state: &RuleSet::State) -> (u64, u64) {
+        match self.transpositions.get(state) {
+            Some(numbers) => *numbers,
+            None => (1, 1),
+        }
+    }
+
+    /// Returns the numbers of a finished game from the point of view of its current player.
+    fn terminal_numbers(&self, state: &RuleSet::State) -> Option<(u64, u64)> {
+        let (proof, disproof) =
+            numbers::terminal_numbers(self.ruleset.status(state), self.attacker)?;
+        if self.ruleset.current_player(state) == self.attacker {
+            Some((proof, disproof))
+        } else {
+            Some((disproof, proof))
+        }
+    }
+
+    fn multiple_iterative_deepening(
+        &mut self,
+        state: &RuleSet::State,
+        phi_threshold: u64,
+        delta_threshold: u64,
+    ) -> (u64, u64) {
+        self.nodes += 1;
+        if let Some(numbers) = self.terminal_numbers(state) {
+            self.transpositions.insert(state.clone(), numbers);
+            return numbers;
+        }
+        let children = plies::BasicIterator::new(self.ruleset, state)
+            .map(|ply| self.ruleset.play(state, &ply).unwrap())
+            .collect::<Vec<_>>();
+        // Children are stored on the first visit, so that the status of each state is computed once
+        for child in &children {
+            if !self.transpositions.contains_key(child) {
+                let numbers = self.terminal_numbers(child).unwrap_or((1, 1));
+                self.transpositions.insert(child.clone(), numbers);
+            }
+        }
+        loop {
+            let child_numbers = children
+                .iter()
+                .map(|child| self.lookup(child))
+                .collect::<Vec<_>>();
+            let phi = child_numbers
+                .iter()
+                .map(|(_, delta)| *delta)
+                .min()
+                .unwrap_or(numbers::INFINITY);
+            let delta = numbers::saturating_sum(child_numbers.iter().map(|(phi, _)| *phi));
+            self.transpositions.insert(state.clone(), (phi, delta));
+            if phi >= phi_threshold || delta >= delta_threshold || self.nodes >= self.node_limit {
+                return (phi, delta);
+            }
+            // The most-proving child has the smallest delta, the thresholds making sure its search
+            // stops once another child becomes more promising
+            let mut best = 0;
+            let mut second_delta = numbers::INFINITY;
+            for (index, (_, child_delta)) in child_numbers.iter().enumerate().skip(1) {
+                if *child_delta < child_numbers[best].1 {
+                    second_delta = child_numbers[best].1;
+                    best = index;
+                } else if *child_delta < second_delta {
+                    second_delta = *child_delta;
+                }
+            }
+            let (best_phi, _) = child_numbers[best];
+            let child_phi_threshold = if delta_threshold >= numbers::INFINITY {
+                numbers::INFINITY
+            } else {
+                delta_threshold + best_phi - delta
+            };
+            let child_delta_threshold = phi_threshold.min(second_delta + 1);
+            self.multiple_iterative_deepening(
+                &children[best],
+                child_phi_threshold,
+                child_delta_threshold,
+            );
+        }
+    }
+
+    /// Follows the children proven for the attacker, through the transposition table.
+    fn winning_line(&self, state: &RuleSet::State) -> Vec<RuleSet::Ply> {
+        let mut line = Vec::new();
+        let mut current = state.clone();
+        while self.ruleset.status(&current) == rulesets::Status::Ongoing {
+            let attacker_to_play = self.ruleset.current_player(&current) == self.attacker;
+            // Proven children have a null delta when the attacker played, a null phi otherwise
+            let next = plies::BasicIterator::new(self.ruleset, &current).find_map(|ply| {
+                let child = self.ruleset.play(&current, &ply).unwrap();
+                let (phi, delta) = self.transpositions.get(&child)?;
+                let proven = if attacker_to_play {
+                    *delta == 0
+                } else {
+                    *phi == 0
+                };
+                if proven {
+                    Some((ply, child))
+                } else {
+                    None
+                }
+            });
+            match next {
+                Some((ply, child)) => {
+                    line.push(ply);
+                    current = child;
+                }
+                None => break,
+            }
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::rulesets::connectn;
+
+    fn replay<RuleSet: rulesets::Deterministic>(
+        ruleset: &RuleSet,
+        state: &RuleSet::State,
+        line: &[RuleSet::Ply],
+    ) -> RuleSet::State {
+        let mut result = state.clone();
+        for ply in line {
+            result = ruleset.play(&result, ply).unwrap();
+        }
+        result
+    }
+
+    macro_rules! tictactoe_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (p1_indices, p2_indices, current_player, expected_proof) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let state = connectn::TicTacToeState::from_indices(&p1_indices, &p2_indices, current_player);
+                    let mut search = DfPn::new(&ruleset);
+                    let result = search.search(&state);
+                    assert_eq!(result.proof, expected_proof);
+                    if expected_proof == results::Proof::Proven {
+                        let final_state = replay(&ruleset, &state, &result.line);
+                        assert_eq!(ruleset.status(&final_state), rulesets::Status::Win { player: current_player });
+                    } else {
+                        assert!(result.line.is_empty());
+                    }
+                }
+            )*
+        }
+    }
+
+    tictactoe_tests! {
+        initial_state: ([], [], 0, results::Proof::Disproven),
+        immediate_win: ([4, 1, 0], [5, 7, 8], 0, results::Proof::Proven),
+        double_threat: ([4, 0], [8, 1], 0, results::Proof::Proven),
+        second_player: ([4, 0], [8], 1, results::Proof::Disproven),
+        lost_game: ([4, 1, 0], [5, 7], 1, results::Proof::Disproven),
+    }
+
+    #[test]
+    fn test_gomoku_open_four() {
+        let ruleset = connectn::Gomoku::new();
+        let state = connectn::GomokuState::from_indices(&[111, 112, 113, 114], &[0, 14], 0);
+        let mut search = DfPn::new(&ruleset);
+        let result = search.search(&state);
+        assert_eq!(result.proof, results::Proof::Proven);
+        assert_eq!(result.line, vec![connectn::Ply::new(110)]);
+    }
+
+    #[test]
+    fn test_node_limit() {
+        let ruleset = connectn::TicTacToe::new();
+        let state = ruleset.initial_state();
+        let mut search = DfPn::new(&ruleset);
+        search.set_node_limit(20);
+        let result = search.search(&state);
+        assert_eq!(result.proof, results::Proof::Unknown);
+    }
+}
@@ -0,0 +1,11 @@
+mod agent;
+mod df_pn;
+mod numbers;
+mod pn_search;
+mod results;
+
+pub use agent::ProvingAgent;
+pub use df_pn::DfPn;
+pub use pn_search::PnSearch;
+pub use results::Proof;
+pub use results::ProofResult;
@@ -0,0 +1,20 @@
+use crate::interface::rulesets;
+
+/// Proof or disproof number of a solved state, saturating sums of numbers
+pub const INFINITY: u64 = 1 << 48;
+
+pub fn saturating_sum<Numbers: Iterator<Item = u64>>(numbers: Numbers) -> u64 {
+    numbers.fold(0, |sum, number| (sum + number).min(INFINITY))
+}
+
+/// Returns the proof and disproof numbers of a finished game, `None` for an ongoing one.
+pub fn terminal_numbers(
+    status: rulesets::Status,
+    attacker: rulesets::Player,
+) -> Option<(u64, u64)> {
+    match status {
+        rulesets::Status::Ongoing => None,
+        rulesets::Status::Win { player } if player == attacker => Some((0, INFINITY)),
+        _ => Some((INFINITY, 0)),
+    }
+}
@@ -0,0 +1,263 @@
+use super::numbers;
+use super::results;
+use crate::interface::rulesets;
+use crate::tools::plies;
+use std::collections::HashMap;
+use std::hash;
+
+/// Default maximum number of nodes in the search tree
+const DEFAULT_NODE_LIMIT: u64 = 1_000_000;
+
+struct Node<RuleSet: rulesets::RuleSetTrait> {
+    state: RuleSet::State,
+    ply: Option<RuleSet::Ply>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    proof: u64,
+    disproof: u64,
+    /// Whether the attacker is to play, the node proof number being the minimum of its children
+    or_node: bool,
+}
+
+/// Best-first proof-number search.
+///
+/// The whole search tree is kept in memory, the most-proving leaf, reached by following the
+/// children with the smallest proof numbers at OR nodes and disproof numbers at AND nodes, being
+/// expanded at each step. The solved states are kept in a transposition table, so that they are
+/// not searched again when reached by another path.
+pub struct PnSearch<'a, RuleSet>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    RuleSet::State: Eq + hash::Hash,
+{
+    ruleset: &'a RuleSet,
+    node_limit: u64,
+    nodes: Vec<Node<RuleSet>>,
+    /// Whether the solved states are proven, along with the index of an expanded node of theirs
+    solved: HashMap<RuleSet::State, (bool, usize)>,
+    attacker: rulesets::Player,
+}
+
+impl<'a, RuleSet> PnSearch<'a, RuleSet>
+where
+    RuleSet: rulesets::Deterministic + rulesets::TurnByTurn,
+    RuleSet::State: Eq + hash::Hash,
+{
+    pub fn new(ruleset: &'a RuleSet) -> PnSearch<'a, RuleSet> {
+        PnSearch {
+            ruleset,
+            node_limit: DEFAULT_NODE_LIMIT,
+            nodes: Vec::new(),
+            solved: HashMap::new(),
+            attacker: 0,
+        }
+    }
+
+    pub fn set_node_limit(&mut self, node_limit: u64) {
+        self.node_limit = node_limit;
+    }
+
+    /// Tries to prove that the current player of the state can force a win.
+    pub fn search(&mut self, state: &RuleSet::State) -> results::ProofResult<RuleSet::Ply> {
+        self.nodes.clear();
+        self.solved.clear();
+        self.attacker = self.ruleset.current_player(state);
+        self.add_node(state.clone(), None, None);
+        while !self.is_solved(0) && (self.nodes.len() as u64) < self.node_limit {
+            let leaf = self.select_most_proving();
+            self.expand(leaf);
+            self.update_ancestors(leaf);
+        }
+        let root = &self.nodes[0];
+        let proof = if root.proof == 0 {
+            results::Proof::Proven
+        } else if root.disproof == 0 {
+            results::Proof::Disproven
+        } else {
+            results::Proof::Unknown
+        };
+        let line = if proof == results::Proof::Proven {
+            self.winning_line()
+        } else {
+            Vec::new()
+        };
+        results::ProofResult {
+            proof,
+            line,
+            nodes: self.nodes.len() as u64,
+        }
+    }
+
+    fn is_solved(&self, index: usize) -> bool {
+        self.nodes[index].proof == 0 || self.nodes[index].disproof == 0
+    }
+
+    fn add_node(
+        &mut self,
+        state: RuleSet::State,
+        ply: Option<RuleSet::Ply>,
+        parent: Option<usize>,
+    ) -> usize {
+        let status = self.ruleset.status(&state);
+        let (proof, disproof) = match numbers::terminal_numbers(status, self.attacker) {
+            Some(numbers) => numbers,
+            None => match self.solved.get(&state) {
+                Some((true, _)) => (0, numbers::INFINITY),
+                Some((false, _)) => (numbers::INFINITY, 0),
+                None => (1, 1),
+            },
+        };
+        let or_node = self.ruleset.current_player(&state) == self.attacker;
+        self.nodes.push(Node {
+            state,
+            ply,
+            parent,
+            children: Vec::new(),
+            proof,
+            disproof,
+            or_node,
+        });
+        self.nodes.len() - 1
+    }
+
+    fn select_most_proving(&self) -> usize {
+        let mut index = 0;
+        while !self.nodes[index].children.is_empty() {
+            let node = &self.nodes[index];
+            index = if node.or_node {
+                *node
+                    .children
+                    .iter()
+                    .min_by_key(|child| self.nodes[**child].proof)
+                    .unwrap()
+            } else {
+                *node
+                    .children
+                    .iter()
+                    .min_by_key(|child| self.nodes[**child].disproof)
+                    .unwrap()
+            };
+        }
+        index
+    }
+
+    fn expand(&mut self, index: usize) {
+        let state = self.nodes[index].state.clone();
+        let children = plies::BasicIterator::new(self.ruleset, &state)
+            .map(|ply| {
+                let child_state = self.ruleset.play(&state, &ply).unwrap();
+                self.add_node(child_state, Some(ply), Some(index))
+            })
+            .collect();
+        self.nodes[index].children = children;
+    }
+
+    fn update_ancestors(&mut self, index: usize) {
+        let mut current = Some(index);
+        while let Some(index) = current {
+            let node = &self.nodes[index];
+            let proofs = node.children.iter().map(|child| self.nodes[*child].proof);
+            let disproofs = node
+                .children
+                .iter()
+                .map(|child| self.nodes[*child].disproof);
+            let (proof, disproof) = if node.or_node {
+                (
+                    proofs.min().unwrap_or(numbers::INFINITY),
+                    numbers::saturating_sum(disproofs),
+                )
+            } else {
+                (
+                    numbers::saturating_sum(proofs),
+                    disproofs.min().unwrap_or(numbers::INFINITY),
+                )
+            };
+            let node = &mut self.nodes[index];
+            node.proof = proof;
+            node.disproof = disproof;
+            if proof == 0 || disproof == 0 {
+                self.solved.insert(node.state.clone(), (proof == 0, index));
+            }
+            current = node.parent;
+        }
+    }
+
+    /// Follows the proven children, jumping to the expanded copy of the states solved through
+    /// another path.
+    fn winning_line(&self) -> Vec<RuleSet::Ply> {
+        let mut line = Vec::new();
+        let mut index = 0;
+        loop {
+            if self.nodes[index].children.is_empty() {
+                match self.solved.get(&self.nodes[index].state) {
+                    Some((_, expanded)) => index = *expanded,
+                    None => break,
+                }
+            }
+            match self.nodes[index]
+                .children
+                .iter()
+                .find(|child| self.nodes[**child].proof == 0)
+            {
+                Some(child) => {
+                    index = *child;
+                    line.push(self.nodes[index].ply.unwrap());
+                }
+                None => break,
+            }
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::rulesets::Deterministic;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::rulesets::connectn;
+
+    macro_rules! tictactoe_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (p1_indices, p2_indices, current_player, expected_proof) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let state = connectn::TicTacToeState::from_indices(&p1_indices, &p2_indices, current_player);
+                    let mut search = PnSearch::new(&ruleset);
+                    let result = search.search(&state);
+                    assert_eq!(result.proof, expected_proof);
+                    if expected_proof == results::Proof::Proven {
+                        let mut final_state = state;
+                        for ply in &result.line {
+                            final_state = ruleset.play(&final_state, ply).unwrap();
+                        }
+                        assert_eq!(ruleset.status(&final_state), rulesets::Status::Win { player: current_player });
+                    } else {
+                        assert!(result.line.is_empty());
+                    }
+                }
+            )*
+        }
+    }
+
+    tictactoe_tests! {
+        initial_state: ([], [], 0, results::Proof::Disproven),
+        immediate_win: ([4, 1, 0], [5, 7, 8], 0, results::Proof::Proven),
+        double_threat: ([4, 0], [8, 1], 0, results::Proof::Proven),
+        second_player: ([4, 0], [8], 1, results::Proof::Disproven),
+        lost_game: ([4, 1, 0], [5, 7], 1, results::Proof::Disproven),
+    }
+
+    #[test]
+    fn test_node_limit() {
+        let ruleset = connectn::TicTacToe::new();
+        let state = connectn::TicTacToeState::from_indices(&[], &[], 0);
+        let mut search = PnSearch::new(&ruleset);
+        search.set_node_limit(20);
+        let result = search.search(&state);
+        assert_eq!(result.proof, results::Proof::Unknown);
+        assert!(result.line.is_empty());
+    }
+}
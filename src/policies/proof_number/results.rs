@@ -0,0 +1,19 @@
+/// Status of the current player of the searched state, the attacker.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Proof {
+    /// The attacker wins whatever the defence
+    Proven,
+    /// The attacker cannot force a win, the game being a loss or a draw
+    Disproven,
+    /// The search ran out of nodes
+    Unknown,
+}
+
+#[derive(Debug)]
+pub struct ProofResult<Ply> {
+    pub proof: Proof,
+    /// Plies of a winning line for proven states, empty otherwise
+    pub line: Vec<Ply>,
+    /// Number of searched nodes
+    pub nodes: u64,
+}
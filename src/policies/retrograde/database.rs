@@ -0,0 +1,277 @@
+//! Perfect-play databases
+//!
+//! The file starts with a 4-byte magic number and the number of entries as a little-endian `u64`.
+//! Entries follow, sorted by key, in 10 bytes each: the key as a little-endian `u64`, then the
+//! outcome in the two highest bits of a little-endian `u16`, the distance to the end of the game
+//! filling the other ones.
+
+use crate::interface::rulesets;
+use crate::tools::symmetries;
+use crate::utils::hashing;
+use std::error;
+use std::hash;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+
+const MAGIC: &[u8; 4] = b"AIRD";
+const ENTRY_SIZE: usize = 10;
+const DISTANCE_MASK: u16 = 0x3fff;
+const OUTCOME_SHIFT: u32 = 14;
+
+/// Outcome of a state under perfect play, from the point of view of its current player.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Outcome {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Outcome {
+    pub fn value(self) -> f32 {
+        match self {
+            Outcome::Loss => -1.0,
+            Outcome::Draw => 0.0,
+            Outcome::Win => 1.0,
+        }
+    }
+
+    pub fn reverse(self) -> Outcome {
+        match self {
+            Outcome::Loss => Outcome::Win,
+            Outcome::Draw => Outcome::Draw,
+            Outcome::Win => Outcome::Loss,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Entry {
+    pub outcome: Outcome,
+    /// Number of plies until the end of the game, the winner hastening it and the loser delaying
+    /// it
+    pub distance: u16,
+}
+
+impl Entry {
+    /// Returns the entry of a state leading to this one, from the point of view of its player.
+    pub fn parent(&self) -> Entry {
+        Entry {
+            outcome: self.outcome.reverse(),
+            distance: self.distance + 1,
+        }
+    }
+
+    /// Ranks the entries from the point of view of the player choosing among them: wins first,
+    /// the fastest ones ahead, then draws, then losses, the slowest ones ahead.
+    pub fn preference(&self) -> (i8, i32) {
+        let distance = self.distance as i32;
+        match self.outcome {
+            Outcome::Win => (1, -distance),
+            Outcome::Draw => (0, -distance),
+            Outcome::Loss => (-1, distance),
+        }
+    }
+
+    fn pack(&self) -> u16 {
+        let outcome = match self.outcome {
+            Outcome::Loss => 0,
+            Outcome::Draw => 1,
+            Outcome::Win => 2,
+        };
+        outcome << OUTCOME_SHIFT | self.distance.min(DISTANCE_MASK)
+    }
+
+    fn unpack(data: u16) -> Result<Entry, Box<dyn error::Error>> {
+        let outcome = match data >> OUTCOME_SHIFT {
+            0 => Outcome::Loss,
+            1 => Outcome::Draw,
+            2 => Outcome::Win,
+            value => return Err(format!("invalid outcome {}", value).into()),
+        };
+        Ok(Entry {
+            outcome,
+            distance: data & DISTANCE_MASK,
+        })
+    }
+}
+
+/// Outcomes of all the states reachable in a game.
+///
+/// States are stored under the FNV hash of their canonical form among their symmetries, so that
+/// symmetric states share their entry.
+pub struct Database<RuleSet: rulesets::HasStatesWithSymmetries>
+where
+    RuleSet::State: hash::Hash + Ord,
+{
+    ruleset: RuleSet,
+    keys: Vec<u64>,
+    entries: Vec<Entry>,
+}
+
+impl<RuleSet: rulesets::HasStatesWithSymmetries> Database<RuleSet>
+where
+    RuleSet::State: hash::Hash + Ord,
+{
+    pub(super) fn new(
+        ruleset: &RuleSet,
+        entries: Vec<(RuleSet::State, Entry)>,
+    ) -> Result<Database<RuleSet>, Box<dyn error::Error>> {
+        let mut entries = entries
+            .into_iter()
+            .map(|(state, entry)| {
+                let (canonical_state, _) = symmetries::canonicalize(ruleset, &state);
+                (hashing::hash(&canonical_state), entry)
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(key, _)| *key);
+        if entries.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err("hash collision between two states".into());
+        }
+        let (keys, entries) = entries.into_iter().unzip();
+        Ok(Database {
+            ruleset: ruleset.clone(),
+            keys,
+            entries,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn get(&self, state: &RuleSet::State) -> Option<Entry> {
+        let (canonical_state, _) = symmetries::canonicalize(&self.ruleset, state);
+        let index = self
+            .keys
+            .binary_search(&hashing::hash(&canonical_state))
+            .ok()?;
+        Some(self.entries[index])
+    }
+
+    /// Returns the number of states for each outcome, in the order loss, draw, win.
+    pub fn outcome_counts(&self) -> [usize; 3] {
+        let mut result = [0; 3];
+        for entry in &self.entries {
+            match entry.outcome {
+                Outcome::Loss => result[0] += 1,
+                Outcome::Draw => result[1] += 1,
+                Outcome::Win => result[2] += 1,
+            }
+        }
+        result
+    }
+
+    pub fn write<Output: io::Write>(&self, output: &mut Output) -> io::Result<()> {
+        let mut output = io::BufWriter::new(output);
+        output.write_all(MAGIC)?;
+        output.write_all(&(self.len() as u64).to_le_bytes())?;
+        for (key, entry) in self.keys.iter().zip(self.entries.iter()) {
+            output.write_all(&key.to_le_bytes())?;
+            output.write_all(&entry.pack().to_le_bytes())?;
+        }
+        output.flush()
+    }
+
+    pub fn read<Input: io::Read>(
+        ruleset: &RuleSet,
+        input: &mut Input,
+    ) -> Result<Database<RuleSet>, Box<dyn error::Error>> {
+        let mut input = io::BufReader::new(input);
+        let mut header = [0; 12];
+        input.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err("not a perfect-play database".into());
+        }
+        let mut count = [0; 8];
+        count.copy_from_slice(&header[4..]);
+        let count = u64::from_le_bytes(count) as usize;
+        let mut keys = Vec::with_capacity(count);
+        let mut entries = Vec::with_capacity(count);
+        let mut record = [0; ENTRY_SIZE];
+        for _ in 0..count {
+            input.read_exact(&mut record)?;
+            let mut key = [0; 8];
+            key.copy_from_slice(&record[..8]);
+            let key = u64::from_le_bytes(key);
+            if matches!(keys.last(), Some(previous) if *previous >= key) {
+                return Err("database entries are not sorted".into());
+            }
+            keys.push(key);
+            entries.push(Entry::unpack(u16::from_le_bytes([record[8], record[9]]))?);
+        }
+        Ok(Database {
+            ruleset: ruleset.clone(),
+            keys,
+            entries,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rulesets::connectn;
+
+    fn build_database() -> Database<connectn::TicTacToe> {
+        let ruleset = connectn::TicTacToe::new();
+        let entries = vec![
+            (
+                connectn::TicTacToeState::from_indices(&[0], &[], 1),
+                Entry {
+                    outcome: Outcome::Draw,
+                    distance: 8,
+                },
+            ),
+            (
+                connectn::TicTacToeState::from_indices(&[4, 1, 0], &[5, 7], 1),
+                Entry {
+                    outcome: Outcome::Loss,
+                    distance: 2,
+                },
+            ),
+        ];
+        Database::new(&ruleset, entries).unwrap()
+    }
+
+    #[test]
+    fn test_symmetric_states() {
+        let database = build_database();
+        let state = connectn::TicTacToeState::from_indices(&[8], &[], 1);
+        assert_eq!(
+            database.get(&state),
+            Some(Entry {
+                outcome: Outcome::Draw,
+                distance: 8
+            })
+        );
+        let state = connectn::TicTacToeState::from_indices(&[4], &[], 1);
+        assert_eq!(database.get(&state), None);
+    }
+
+    #[test]
+    fn test_write_and_read() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let database = build_database();
+        let mut buffer = Vec::new();
+        database.write(&mut buffer)?;
+        assert_eq!(buffer.len(), 12 + 2 * ENTRY_SIZE);
+        let read = Database::read(&ruleset, &mut buffer.as_slice())?;
+        assert_eq!(read.keys, database.keys);
+        assert_eq!(read.entries, database.entries);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_file() {
+        let ruleset = connectn::TicTacToe::new();
+        let mut input: &[u8] = b"NOPE\x00\x00\x00\x00\x00\x00\x00\x00";
+        assert!(Database::read(&ruleset, &mut input).is_err());
+        let mut truncated: &[u8] = b"AIRD\x01\x00\x00\x00\x00\x00\x00\x00\x00";
+        assert!(Database::read(&ruleset, &mut truncated).is_err());
+    }
+}
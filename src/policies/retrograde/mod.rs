@@ -0,0 +1,9 @@
+mod database;
+mod perfect_play;
+mod solver;
+
+pub use database::Database;
+pub use database::Entry;
+pub use database::Outcome;
+pub use perfect_play::PerfectPlay;
+pub use solver::solve;
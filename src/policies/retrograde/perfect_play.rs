@@ -0,0 +1,153 @@
+use super::database;
+use crate::interface::ai;
+use crate::interface::rulesets;
+use crate::tools::plies;
+use std::error;
+use std::hash;
+
+type PlyEntries<RuleSet> = Vec<(<RuleSet as rulesets::RuleSetTrait>::Ply, database::Entry)>;
+
+/// Plays perfectly by looking the states up in a database.
+///
+/// As a policy, it spreads the probabilities evenly over the optimal plies, which makes it a
+/// reference to measure the accuracy of other agents with.
+pub struct PerfectPlay<'a, RuleSet>
+where
+    RuleSet: rulesets::Deterministic + rulesets::HasStatesWithSymmetries,
+    RuleSet::State: hash::Hash + Ord,
+{
+    ruleset: &'a RuleSet,
+    database: &'a database::Database<RuleSet>,
+}
+
+impl<'a, RuleSet> PerfectPlay<'a, RuleSet>
+where
+    RuleSet: rulesets::Deterministic + rulesets::HasStatesWithSymmetries,
+    RuleSet::State: hash::Hash + Ord,
+{
+    pub fn new(
+        ruleset: &'a RuleSet,
+        database: &'a database::Database<RuleSet>,
+    ) -> PerfectPlay<'a, RuleSet> {
+        PerfectPlay { ruleset, database }
+    }
+
+    /// Returns the available plies along with the entries of the states they lead to, from the
+    /// point of view of the current player.
+    pub fn ply_entries(
+        &self,
+        state: &RuleSet::State,
+    ) -> Result<PlyEntries<RuleSet>, Box<dyn error::Error>> {
+        plies::BasicIterator::new(self.ruleset, state)
+            .map(|ply| {
+                let resulting_state = self.ruleset.play(state, &ply)?;
+                match self.database.get(&resulting_state) {
+                    Some(entry) => Ok((ply, entry.parent())),
+                    None => Err("state missing from the database".into()),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the plies keeping the best outcome, the fastest wins or slowest losses.
+    pub fn optimal_plies(
+        &self,
+        state: &RuleSet::State,
+    ) -> Result<Vec<RuleSet::Ply>, Box<dyn error::Error>> {
+        let ply_entries = self.ply_entries(state)?;
+        let best = match ply_entries
+            .iter()
+            .map(|(_, entry)| entry.preference())
+            .max()
+        {
+            Some(best) => best,
+            None => return Err("no ply to play on a finished game".into()),
+        };
+        Ok(ply_entries
+            .into_iter()
+            .filter(|(_, entry)| entry.preference() == best)
+            .map(|(ply, _)| ply)
+            .collect())
+    }
+}
+
+impl<'a, RuleSet> ai::Agent<RuleSet> for PerfectPlay<'a, RuleSet>
+where
+    RuleSet: rulesets::Deterministic + rulesets::HasStatesWithSymmetries,
+    RuleSet::State: hash::Hash + Ord,
+{
+    fn play(&mut self, state: &RuleSet::State) -> Result<RuleSet::Ply, Box<dyn error::Error>> {
+        Ok(self.optimal_plies(state)?[0])
+    }
+}
+
+impl<'a, RuleSet> ai::Policy<RuleSet> for PerfectPlay<'a, RuleSet>
+where
+    RuleSet: rulesets::Deterministic + rulesets::HasStatesWithSymmetries,
+    RuleSet::State: hash::Hash + Ord,
+{
+    fn predict(
+        &mut self,
+        state: &RuleSet::State,
+    ) -> Result<ai::Prediction<RuleSet>, Box<dyn error::Error>> {
+        let entry = self
+            .database
+            .get(state)
+            .ok_or("state missing from the database")?;
+        let optimal_plies = self.optimal_plies(state)?;
+        let probability = 1.0 / optimal_plies.len() as f32;
+        Ok(ai::Prediction {
+            value: entry.outcome.value(),
+            probabilities: optimal_plies
+                .into_iter()
+                .map(|ply| (ply, probability))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policies::retrograde;
+    use crate::rulesets::connectn;
+
+    macro_rules! optimal_plies_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<(), Box<dyn error::Error>> {
+                    let (p1_indices, p2_indices, current_player, expected_value, expected_plies) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let database = retrograde::solve(&ruleset)?;
+                    let mut policy = PerfectPlay::new(&ruleset, &database);
+                    let state = connectn::TicTacToeState::from_indices(&p1_indices, &p2_indices, current_player);
+                    let prediction = ai::Policy::predict(&mut policy, &state)?;
+                    assert_eq!(prediction.value, expected_value);
+                    let mut plies = prediction.probabilities.iter().map(|(ply, _)| *ply).collect::<Vec<_>>();
+                    plies.sort();
+                    let expected_plies = expected_plies.iter().cloned().map(connectn::Ply::new).collect::<Vec<_>>();
+                    assert_eq!(plies, expected_plies);
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    optimal_plies_tests! {
+        fastest_win: ([4, 1, 0], [5, 7, 8], 0, 1.0, [2]),
+        forced_block: ([4], [0, 2], 0, 0.0, [1]),
+        hopeless: ([4, 1, 0], [5, 7], 1, -1.0, [2, 3, 6, 8]),
+        first_ply: ([], [], 0, 0.0, [0, 1, 2, 3, 4, 5, 6, 7, 8]),
+    }
+
+    #[test]
+    fn test_finished_game() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let database = retrograde::solve(&ruleset)?;
+        let mut policy = PerfectPlay::new(&ruleset, &database);
+        let state = connectn::TicTacToeState::from_indices(&[4, 1, 0, 2], &[5, 7, 8], 1);
+        assert!(ai::Agent::play(&mut policy, &state).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use super::database;
+use crate::interface::rulesets;
+use crate::tools::plies;
+use crate::tools::symmetries;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::error;
+use std::hash;
+
+/// Solves a game by retrograde analysis.
+///
+/// All the states reachable from the initial one are enumerated first, up to symmetries, along
+/// with the links between them. Outcomes are then propagated backwards from the finished games: a
+/// state is solved once all its successors are, its current player picking the best of them.
+///
+/// Games are expected to be finite and without cycles, as the states of a cycle are never solved.
+pub fn solve<RuleSet>(
+    ruleset: &RuleSet,
+) -> Result<database::Database<RuleSet>, Box<dyn error::Error>>
+where
+    RuleSet: rulesets::Deterministic + rulesets::HasStatesWithSymmetries + rulesets::TurnByTurn,
+    RuleSet::State: Eq + hash::Hash + Ord,
+{
+    let Graph { states, successors } = enumerate(ruleset)?;
+    let mut predecessors = vec![Vec::new(); states.len()];
+    for (index, children) in successors.iter().enumerate() {
+        for child in children {
+            predecessors[*child].push(index);
+        }
+    }
+    let mut remaining = successors.iter().map(Vec::len).collect::<Vec<_>>();
+    let mut entries: Vec<Option<database::Entry>> = vec![None; states.len()];
+    let mut queue = VecDeque::new();
+    for (index, state) in states.iter().enumerate() {
+        let outcome = match ruleset.status(state) {
+            rulesets::Status::Ongoing => continue,
+            rulesets::Status::Draw => database::Outcome::Draw,
+            rulesets::Status::Win { player } if player == ruleset.current_player(state) => {
+                database::Outcome::Win
+            }
+            rulesets::Status::Win { .. } => database::Outcome::Loss,
+        };
+        entries[index] = Some(database::Entry {
+            outcome,
+            distance: 0,
+        });
+        queue.push_back(index);
+    }
+    while let Some(index) = queue.pop_front() {
+        for predecessor in &predecessors[index] {
+            remaining[*predecessor] -= 1;
+            if remaining[*predecessor] > 0 {
+                continue;
+            }
+            entries[*predecessor] = successors[*predecessor]
+                .iter()
+                .map(|child| entries[*child].unwrap().parent())
+                .max_by_key(|entry| entry.preference());
+            queue.push_back(*predecessor);
+        }
+    }
+    let entries = states
+        .into_iter()
+        .zip(entries)
+        .map(|(state, entry)| entry.map(|entry| (state, entry)))
+        .collect::<Option<Vec<_>>>()
+        .ok_or("the game has cycles, whose states cannot be solved")?;
+    database::Database::new(ruleset, entries)
+}
+
+struct Graph<State> {
+    /// Canonical reachable states
+    states: Vec<State>,
+    /// Indices of the distinct successors of each state
+    successors: Vec<Vec<usize>>,
+}
+
+fn enumerate<RuleSet>(ruleset: &RuleSet) -> Result<Graph<RuleSet::State>, Box<dyn error::Error>>
+where
+    RuleSet: rulesets::Deterministic + rulesets::HasStatesWithSymmetries + rulesets::TurnByTurn,
+    RuleSet::State: Eq + hash::Hash + Ord,
+{
+    let mut indices = HashMap::new();
+    let mut states = Vec::new();
+    let mut successors = Vec::new();
+    let (initial_state, _) = symmetries::canonicalize(ruleset, &ruleset.initial_state());
+    indices.insert(initial_state.clone(), 0);
+    states.push(initial_state);
+    let mut index = 0;
+    while index < states.len() {
+        let state = states[index].clone();
+        let mut children = Vec::new();
+        if ruleset.status(&state) == rulesets::Status::Ongoing {
+            for ply in plies::BasicIterator::new(ruleset, &state) {
+                let child = ruleset.play(&state, &ply)?;
+                let (child, _) = symmetries::canonicalize(ruleset, &child);
+                let child_index = match indices.get(&child) {
+                    Some(child_index) => *child_index,
+                    None => {
+                        indices.insert(child.clone(), states.len());
+                        states.push(child);
+                        states.len() - 1
+                    }
+                };
+                children.push(child_index);
+            }
+            children.sort_unstable();
+            children.dedup();
+        }
+        successors.push(children);
+        index += 1;
+    }
+    Ok(Graph { states, successors })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::rulesets::connectn;
+    use crate::rulesets::reversi;
+
+    #[test]
+    fn test_tictactoe() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let database = solve(&ruleset)?;
+        assert_eq!(database.len(), 765);
+        let entry = database.get(&ruleset.initial_state()).unwrap();
+        assert_eq!(entry.outcome, database::Outcome::Draw);
+        // Draws are called as soon as no run can be completed anymore
+        assert_eq!(entry.distance, 8);
+        let state = connectn::TicTacToeState::from_indices(&[4, 1, 0], &[5, 7], 1);
+        assert_eq!(
+            database.get(&state),
+            Some(database::Entry {
+                outcome: database::Outcome::Loss,
+                distance: 2,
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_reversi_micro() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = reversi::Reversi::<reversi::Micro>::new();
+        let database = solve(&ruleset)?;
+        let entry = database.get(&ruleset.initial_state()).unwrap();
+        assert_eq!(entry.outcome, database::Outcome::Loss);
+        Ok(())
+    }
+}
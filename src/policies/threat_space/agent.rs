@@ -0,0 +1,81 @@
+use super::search;
+use crate::interface::ai;
+use crate::rulesets::connectn;
+use std::error;
+
+/// Plays the ply a threat-space search calls for, and lets another agent play quiet positions.
+pub struct TacticalAgent<Variant, Fallback>
+where
+    Variant: connectn::BaseVariant,
+    Fallback: ai::Agent<connectn::RuleSet<Variant>>,
+{
+    search: search::ThreatSpaceSearch<Variant>,
+    fallback: Fallback,
+}
+
+impl<Variant, Fallback> TacticalAgent<Variant, Fallback>
+where
+    Variant: connectn::BaseVariant,
+    Fallback: ai::Agent<connectn::RuleSet<Variant>>,
+{
+    pub fn new(
+        ruleset: &connectn::RuleSet<Variant>,
+        fallback: Fallback,
+    ) -> TacticalAgent<Variant, Fallback> {
+        TacticalAgent {
+            search: search::ThreatSpaceSearch::new(ruleset),
+            fallback,
+        }
+    }
+
+    pub fn search(&mut self) -> &mut search::ThreatSpaceSearch<Variant> {
+        &mut self.search
+    }
+}
+
+impl<Variant, Fallback> ai::Agent<connectn::RuleSet<Variant>> for TacticalAgent<Variant, Fallback>
+where
+    Variant: connectn::BaseVariant,
+    Fallback: ai::Agent<connectn::RuleSet<Variant>>,
+{
+    fn play(
+        &mut self,
+        state: &connectn::State<Variant>,
+    ) -> Result<connectn::Ply<Variant>, Box<dyn error::Error>> {
+        match self.search.tactical_ply(state) {
+            Some(ply) => Ok(ply),
+            None => self.fallback.play(state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents;
+
+    macro_rules! play_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() -> Result<(), Box<dyn error::Error>> {
+                    let (p1_indices, p2_indices, current_player, expected_plies) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let state = connectn::TicTacToeState::from_indices(&p1_indices, &p2_indices, current_player);
+                    let mut agent = TacticalAgent::new(&ruleset, agents::Random::new(&ruleset));
+                    let ply = ai::Agent::play(&mut agent, &state)?;
+                    let expected_plies = expected_plies.iter().cloned().map(connectn::Ply::new).collect::<Vec<_>>();
+                    assert!(expected_plies.contains(&ply));
+                    Ok(())
+                }
+            )*
+        }
+    }
+
+    play_tests! {
+        immediate_win: ([4, 1, 0], [5, 7, 8], 0, [2]),
+        block: ([4, 1], [0, 2], 1, [7]),
+        double_threat: ([4, 0], [8, 7], 0, [2, 3, 6]),
+        fallback: ([], [], 0, [0, 1, 2, 3, 4, 5, 6, 7, 8]),
+    }
+}
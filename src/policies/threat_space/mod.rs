@@ -0,0 +1,5 @@
+mod agent;
+mod search;
+
+pub use agent::TacticalAgent;
+pub use search::ThreatSpaceSearch;
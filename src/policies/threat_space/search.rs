@@ -0,0 +1,412 @@
+use crate::rulesets::connectn;
+use crate::utils::bitarray;
+use std::collections::HashMap;
+
+/// Default maximum number of fours in a row of a VCF
+const DEFAULT_VCF_DEPTH: u8 = 20;
+/// Default maximum number of threes in a row of a VCT, fours not counting
+const DEFAULT_VCT_DEPTH: u8 = 2;
+
+/// Threat-space search: looks for a win through a sequence of threats, each of which the
+/// opponent must answer.
+///
+/// A four is a run missing a single peg of the attacker, without any peg of the defender: it
+/// must be blocked at once. A victory by continuous fours (VCF) chains fours until the defender
+/// faces two of them. A victory by continuous threats (VCT) also uses threes, moves after which
+/// the attacker would win by a VCF if the defender did not answer. The defender then has to
+/// play on one of the cells of that VCF, or to counter with a four of their own.
+///
+/// VCF wins are sound, as every defender ply is forced. VCT wins are not: the defender plies
+/// tried against a three are chosen heuristically, and a defence outside of them may refute the
+/// win. Both searches are partial: wins longer than their depth are not found.
+pub struct ThreatSpaceSearch<Variant: connectn::BaseVariant> {
+    strips: Vec<bitarray::BitArray<Variant::ArraySettings>>,
+    strip_cells: Vec<Vec<usize>>,
+    vcf_depth: u8,
+    vct_depth: u8,
+    /// Whether `tactical_ply` follows VCT lines, which may be refuted
+    tactical_vct: bool,
+    /// Largest depth at which a state failed, from the point of view of its current player
+    vcf_failures: HashMap<connectn::State<Variant>, u8>,
+}
+
+impl<Variant: connectn::BaseVariant> ThreatSpaceSearch<Variant> {
+    pub fn new(ruleset: &connectn::RuleSet<Variant>) -> ThreatSpaceSearch<Variant> {
+        let strips = ruleset.strips().to_vec();
+        let strip_cells = strips
+            .iter()
+            .map(|strip| {
+                (0..Variant::CELL_COUNT)
+                    .filter(|index| strip.isset(*index))
+                    .collect()
+            })
+            .collect();
+        ThreatSpaceSearch {
+            strips,
+            strip_cells,
+            vcf_depth: DEFAULT_VCF_DEPTH,
+            vct_depth: DEFAULT_VCT_DEPTH,
+            tactical_vct: false,
+            vcf_failures: HashMap::new(),
+        }
+    }
+
+    pub fn set_vcf_depth(&mut self, depth: u8) {
+        self.vcf_depth = depth;
+    }
+
+    pub fn set_vct_depth(&mut self, depth: u8) {
+        self.vct_depth = depth;
+    }
+
+    /// Lets `tactical_ply` play the first ply of a VCT when no VCF is found, at the risk of an
+    /// unsound attack. Disabled by default.
+    pub fn set_tactical_vct(&mut self, enabled: bool) {
+        self.tactical_vct = enabled;
+    }
+
+    /// Looks for a victory by continuous fours of the current player.
+    ///
+    /// Returns the forced line, alternating attacker and defender plies, down to the attacker ply
+    /// after which the defender cannot prevent the win.
+    pub fn vcf(&mut self, state: &connectn::State<Variant>) -> Option<Vec<connectn::Ply<Variant>>> {
+        self.vcf_failures.clear();
+        let line = self.search_vcf(state, self.vcf_depth)?;
+        Some(Self::plies(&line))
+    }
+
+    /// Looks for a victory by continuous threats of the current player.
+    ///
+    /// Returns a winning line, following the first defence at each defender turn.
+    pub fn vct(&mut self, state: &connectn::State<Variant>) -> Option<Vec<connectn::Ply<Variant>>> {
+        self.vcf_failures.clear();
+        let line = self.search_vct(state, self.vct_depth)?;
+        Some(Self::plies(&line))
+    }
+
+    /// Returns the ply a tactical situation calls for: a winning ply, the block of a winning ply
+    /// of the opponent, or the first ply of a VCF, in that order, and then the first ply of a VCT
+    /// if enabled.
+    ///
+    /// It is meant to be called before a regular search, which only has to run when it returns
+    /// `None`.
+    pub fn tactical_ply(
+        &mut self,
+        state: &connectn::State<Variant>,
+    ) -> Option<connectn::Ply<Variant>> {
+        let player = state.current_player as usize;
+        let cell = self
+            .winning_cells(state, player)
+            .first()
+            .cloned()
+            .or_else(|| self.winning_cells(state, 1 - player).first().cloned());
+        if let Some(cell) = cell {
+            return Some(connectn::Ply::new(cell as u8));
+        }
+        if let Some(line) = self.vcf(state) {
+            return Some(line[0]);
+        }
+        if !self.tactical_vct {
+            return None;
+        }
+        self.vct(state).map(|line| line[0])
+    }
+
+    fn plies(line: &[usize]) -> Vec<connectn::Ply<Variant>> {
+        line.iter()
+            .map(|cell| connectn::Ply::new(*cell as u8))
+            .collect()
+    }
+
+    fn counts(&self, state: &connectn::State<Variant>, strip: usize) -> [u32; 2] {
+        [
+            (&state.grids[0] & &self.strips[strip]).count_ones(),
+            (&state.grids[1] & &self.strips[strip]).count_ones(),
+        ]
+    }
+
+    /// Returns the sorted empty cells of the runs of the player missing the given number of pegs.
+    fn run_cells(
+        &self,
+        state: &connectn::State<Variant>,
+        player: usize,
+        missing: usize,
+    ) -> Vec<usize> {
+        let mut result = Vec::new();
+        for strip in 0..self.strips.len() {
+            let counts = self.counts(state, strip);
+            if counts[1 - player] == 0 && counts[player] as usize + missing == Variant::RUN_COUNT {
+                result.extend(
+                    self.strip_cells[strip]
+                        .iter()
+                        .filter(|cell| state.is_empty(**cell)),
+                );
+            }
+        }
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+
+    /// Returns the cells completing a run of the player.
+    fn winning_cells(&self, state: &connectn::State<Variant>, player: usize) -> Vec<usize> {
+        self.run_cells(state, player, 1)
+    }
+
+    fn play(state: &connectn::State<Variant>, cell: usize) -> connectn::State<Variant> {
+        let mut result = state.clone();
+        result.play(&connectn::Ply::new(cell as u8)).unwrap();
+        result
+    }
+
+    fn search_vcf(&mut self, state: &connectn::State<Variant>, depth: u8) -> Option<Vec<usize>> {
+        let attacker = state.current_player as usize;
+        let defender = 1 - attacker;
+        if let Some(cell) = self.winning_cells(state, attacker).first() {
+            return Some(vec![*cell]);
+        }
+        if depth == 0 || matches!(self.vcf_failures.get(state), Some(failed) if *failed >= depth) {
+            return None;
+        }
+        // A four of the defender has to be blocked, which is only part of a VCF if it is a four
+        let threats = self.winning_cells(state, defender);
+        let candidates = match threats.len() {
+            0 => self.run_cells(state, attacker, 2),
+            1 => threats,
+            _ => Vec::new(),
+        };
+        for cell in candidates {
+            let next_state = Self::play(state, cell);
+            let fours = self.winning_cells(&next_state, attacker);
+            if fours.is_empty() || !self.winning_cells(&next_state, defender).is_empty() {
+                continue;
+            }
+            if fours.len() > 1 {
+                return Some(vec![cell]);
+            }
+            let reply_state = Self::play(&next_state, fours[0]);
+            if let Some(line) = self.search_vcf(&reply_state, depth - 1) {
+                let mut result = vec![cell, fours[0]];
+                result.extend(line);
+                return Some(result);
+            }
+        }
+        self.vcf_failures.insert(state.clone(), depth);
+        None
+    }
+
+    fn search_vct(&mut self, state: &connectn::State<Variant>, depth: u8) -> Option<Vec<usize>> {
+        if let Some(line) = self.search_vcf(state, self.vcf_depth) {
+            return Some(line);
+        }
+        if depth == 0 {
+            return None;
+        }
+        let attacker = state.current_player as usize;
+        let defender = 1 - attacker;
+        let threats = self.winning_cells(state, defender);
+        let candidates = match threats.len() {
+            0 => {
+                let mut cells = self.run_cells(state, attacker, 3);
+                cells.extend(self.run_cells(state, attacker, 2));
+                cells.sort_unstable();
+                cells.dedup();
+                cells
+            }
+            1 => threats,
+            _ => Vec::new(),
+        };
+        for cell in candidates {
+            let next_state = Self::play(state, cell);
+            if !self.winning_cells(&next_state, defender).is_empty() {
+                continue;
+            }
+            // The ply is a threat if the attacker would win by a VCF, were they to play again
+            let mut passed_state = next_state.clone();
+            passed_state.current_player = attacker as u8;
+            let threat_line = match self.search_vcf(&passed_state, self.vcf_depth) {
+                Some(line) => line,
+                None => continue,
+            };
+            let mut first_defence = None;
+            let mut refuted = false;
+            for defence in self.defence_cells(&passed_state, &threat_line) {
+                let defended_state = Self::play(&next_state, defence);
+                if self.search_vcf(&defended_state, self.vcf_depth).is_some() {
+                    continue;
+                }
+                match self.search_vct(&defended_state, depth - 1) {
+                    Some(line) if first_defence.is_none() => first_defence = Some((defence, line)),
+                    Some(_) => (),
+                    None => {
+                        refuted = true;
+                        break;
+                    }
+                }
+            }
+            if refuted {
+                continue;
+            }
+            let mut result = vec![cell];
+            if let Some((defence, line)) = first_defence {
+                result.push(defence);
+                result.extend(line);
+            }
+            return Some(result);
+        }
+        None
+    }
+
+    /// Returns the empty cells where a defender ply may break the VCF line: the cells of the
+    /// line and of the final runs, the cells giving the defender a four, and the cells sharing
+    /// a run free of attacker pegs with a forced defender ply, which could turn it into a four.
+    fn defence_cells(&self, state: &connectn::State<Variant>, line: &[usize]) -> Vec<usize> {
+        let attacker = state.current_player as usize;
+        let defender = 1 - attacker;
+        let mut final_state = state.clone();
+        for cell in line {
+            final_state = Self::play(&final_state, *cell);
+        }
+        let mut result = line.to_vec();
+        final_state.current_player = attacker as u8;
+        result.extend(self.winning_cells(&final_state, attacker));
+        result.extend(self.run_cells(state, defender, 2));
+        let defender_plies = line.iter().skip(1).step_by(2).cloned().collect::<Vec<_>>();
+        for strip in 0..self.strips.len() {
+            if self.counts(state, strip)[attacker] == 0
+                && defender_plies
+                    .iter()
+                    .any(|cell| self.strips[strip].isset(*cell))
+            {
+                result.extend(self.strip_cells[strip].iter().cloned());
+            }
+        }
+        result.sort_unstable();
+        result.dedup();
+        result.retain(|cell| state.is_empty(*cell));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::rulesets;
+    use crate::interface::rulesets::Deterministic;
+    use crate::interface::rulesets::RuleSetTrait;
+
+    macro_rules! vcf_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (p1_indices, p2_indices, expected_first_ply) = $value;
+                    let ruleset = connectn::Gomoku::new();
+                    let state = connectn::GomokuState::from_indices(&p1_indices, &p2_indices, 0);
+                    let mut search = ThreatSpaceSearch::new(&ruleset);
+                    let line = search.vcf(&state);
+                    let expected_first_ply: Option<u8> = expected_first_ply;
+                    assert_eq!(line.map(|line| line[0]), expected_first_ply.map(connectn::Ply::new));
+                }
+            )*
+        }
+    }
+
+    vcf_tests! {
+        five: ([110, 111, 112, 113], [], Some(109)),
+        open_three: ([111, 112, 113], [], Some(110)),
+        double_four: ([110, 111, 112, 68, 83, 98], [109, 53], Some(113)),
+        blocked_three: ([111, 112, 113], [110, 114], None),
+        lone_pegs: ([0, 112], [], None),
+    }
+
+    #[test]
+    fn test_forced_line() {
+        let ruleset = connectn::Gomoku::new();
+        // A four on the middle row forces a block, after which a second four on the column
+        // makes a double four with the diagonal
+        let state =
+            connectn::GomokuState::from_indices(&[110, 111, 112, 83, 98, 82, 96], &[109, 53, 0], 0);
+        let mut search = ThreatSpaceSearch::new(&ruleset);
+        let line = search.vcf(&state).unwrap();
+        let mut current = state;
+        for ply in &line {
+            current = ruleset.play(&current, ply).unwrap();
+        }
+        // Whatever the defender blocks, the attacker completes a run
+        let search = ThreatSpaceSearch::new(&ruleset);
+        let cells = search.winning_cells(&current, 0);
+        for cell in cells.iter().cloned() {
+            let defended = ruleset
+                .play(&current, &connectn::Ply::new(cell as u8))
+                .unwrap();
+            let remaining = search.winning_cells(&defended, 0);
+            assert!(!remaining.is_empty());
+            let finished = ruleset
+                .play(&defended, &connectn::Ply::new(remaining[0] as u8))
+                .unwrap();
+            assert_eq!(
+                ruleset.status(&finished),
+                rulesets::Status::Win { player: 0 }
+            );
+        }
+    }
+
+    #[test]
+    fn test_double_three() {
+        let ruleset = connectn::Gomoku::new();
+        // Crossing open twos on the middle row and column make a double open three
+        let state = connectn::GomokuState::from_indices(&[111, 112, 83, 98], &[0], 0);
+        let mut search = ThreatSpaceSearch::new(&ruleset);
+        search.set_vct_depth(1);
+        assert!(search.vcf(&state).is_none());
+        assert!(search.vct(&state).is_some());
+        let lone = connectn::GomokuState::from_indices(&[112], &[0], 0);
+        assert!(search.vct(&lone).is_none());
+    }
+
+    macro_rules! tactical_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (p1_indices, p2_indices, expected_ply) = $value;
+                    let ruleset = connectn::Gomoku::new();
+                    let state = connectn::GomokuState::from_indices(&p1_indices, &p2_indices, 0);
+                    let mut search = ThreatSpaceSearch::new(&ruleset);
+                    let expected_ply: Option<u8> = expected_ply;
+                    assert_eq!(search.tactical_ply(&state), expected_ply.map(connectn::Ply::new));
+                }
+            )*
+        }
+    }
+
+    tactical_tests! {
+        win: ([110, 111, 112, 113, 0], [1, 2, 3, 4], Some(109)),
+        block: ([0, 20, 40], [110, 111, 112, 113], Some(109)),
+        quiet: ([0], [224], None),
+    }
+
+    #[test]
+    fn test_tactical_vct() {
+        let ruleset = connectn::Gomoku::new();
+        let state = connectn::GomokuState::from_indices(&[111, 112, 83, 98], &[0], 0);
+        let mut search = ThreatSpaceSearch::new(&ruleset);
+        search.set_vct_depth(1);
+        // The double three only wins through a VCT, which is not trusted by default
+        assert_eq!(search.tactical_ply(&state), None);
+        search.set_tactical_vct(true);
+        let line = search.vct(&state).unwrap();
+        assert_eq!(search.tactical_ply(&state), Some(line[0]));
+    }
+
+    #[test]
+    fn test_tictactoe_fork() {
+        let ruleset = connectn::TicTacToe::new();
+        let state = connectn::TicTacToeState::from_indices(&[4, 0], &[8, 1], 0);
+        let mut search = ThreatSpaceSearch::new(&ruleset);
+        let line = search.vcf(&state).unwrap();
+        assert!([3, 6].contains(&line[0].index));
+        assert!(search.vcf(&ruleset.initial_state()).is_none());
+    }
+}
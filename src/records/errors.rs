@@ -0,0 +1,71 @@
+use std::error;
+use std::fmt;
+
+#[derive(Debug)]
+pub struct RecordError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl RecordError {
+    pub fn new<Message: Into<String>>(line: usize, message: Message) -> RecordError {
+        RecordError {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl error::Error for RecordError {}
+
+#[derive(Debug)]
+pub struct ReplayError {
+    pub ply_index: usize,
+    pub message: String,
+}
+
+impl ReplayError {
+    pub fn new<Message: Into<String>>(ply_index: usize, message: Message) -> ReplayError {
+        ReplayError {
+            ply_index,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "ply #{}: {}", self.ply_index, self.message)
+    }
+}
+
+impl error::Error for ReplayError {}
+
+#[derive(Debug)]
+pub struct GameError {
+    pub game_index: usize,
+    pub message: String,
+}
+
+impl GameError {
+    pub fn new<Message: Into<String>>(game_index: usize, message: Message) -> GameError {
+        GameError {
+            game_index,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "game #{}: {}", self.game_index, self.message)
+    }
+}
+
+impl error::Error for GameError {}
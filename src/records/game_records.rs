@@ -0,0 +1,32 @@
+use crate::interface::ai;
+use crate::interface::rulesets;
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct GameRecord<RuleSet: rulesets::RuleSetTrait> {
+    pub ruleset: String,
+    pub agents: Vec<String>,
+    pub status: rulesets::Status,
+    pub plies: Vec<RuleSet::Ply>,
+}
+
+impl<RuleSet: rulesets::RuleSetTrait> GameRecord<RuleSet> {
+    pub fn new(ruleset: &str, agents: &[&str]) -> GameRecord<RuleSet> {
+        GameRecord {
+            ruleset: ruleset.to_string(),
+            agents: agents.iter().map(|agent| agent.to_string()).collect(),
+            status: rulesets::Status::Ongoing,
+            plies: Vec::new(),
+        }
+    }
+
+    pub fn from_game_log(
+        ruleset: &str,
+        agents: &[&str],
+        game_log: &ai::GameLog<RuleSet>,
+    ) -> GameRecord<RuleSet> {
+        let mut record = GameRecord::new(ruleset, agents);
+        record.plies = game_log.history.iter().map(|(_, ply)| *ply).collect();
+        record.status = game_log.status;
+        record
+    }
+}
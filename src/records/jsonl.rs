@@ -0,0 +1,122 @@
+use super::errors;
+use super::game_records;
+use crate::interface::ai;
+use crate::interface::rulesets;
+use std::error;
+use std::io;
+use std::marker;
+
+pub struct Writer<Output: io::Write> {
+    output: Output,
+}
+
+impl<Output: io::Write> Writer<Output> {
+    pub fn new(output: Output) -> Writer<Output> {
+        Writer { output }
+    }
+
+    pub fn write<RuleSet: rulesets::RuleSetTrait>(
+        &mut self,
+        record: &game_records::GameRecord<RuleSet>,
+    ) -> Result<(), Box<dyn error::Error>> {
+        serde_json::to_writer(&mut self.output, record)?;
+        self.output.write_all(b"\n")?;
+        Ok(())
+    }
+
+    pub fn write_game_log<RuleSet: rulesets::RuleSetTrait>(
+        &mut self,
+        ruleset: &str,
+        agents: &[&str],
+        game_log: &ai::GameLog<RuleSet>,
+    ) -> Result<(), Box<dyn error::Error>> {
+        let record = game_records::GameRecord::from_game_log(ruleset, agents, game_log);
+        self.write(&record)
+    }
+
+    pub fn flush(&mut self) -> Result<(), Box<dyn error::Error>> {
+        self.output.flush()?;
+        Ok(())
+    }
+}
+
+pub struct Reader<Input: io::BufRead, RuleSet: rulesets::RuleSetTrait> {
+    lines: io::Lines<Input>,
+    line: usize,
+    ruleset: marker::PhantomData<RuleSet>,
+}
+
+impl<Input: io::BufRead, RuleSet: rulesets::RuleSetTrait> Reader<Input, RuleSet> {
+    pub fn new(input: Input) -> Reader<Input, RuleSet> {
+        Reader {
+            lines: input.lines(),
+            line: 0,
+            ruleset: marker::PhantomData,
+        }
+    }
+}
+
+impl<Input: io::BufRead, RuleSet: rulesets::RuleSetTrait> Iterator for Reader<Input, RuleSet> {
+    type Item = Result<game_records::GameRecord<RuleSet>, Box<dyn error::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line += 1;
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(Box::new(error))),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_str(&line).map_err(|error| {
+                let error = errors::RecordError::new(self.line, error.to_string());
+                Box::new(error) as Box<dyn error::Error>
+            });
+            return Some(record);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playground;
+    use crate::policies::minimax;
+    use crate::rulesets::connectn;
+
+    #[test]
+    fn test_write_read() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let mut agent = minimax::Negamax::new(&ruleset);
+        let game_log = playground::self_play(&ruleset, &mut agent)?;
+        let mut buffer = Vec::new();
+        let mut writer = Writer::new(&mut buffer);
+        writer.write_game_log("tictactoe", &["negamax", "negamax"], &game_log)?;
+        writer.write_game_log("tictactoe", &["negamax", "negamax"], &game_log)?;
+        let reader = Reader::<_, connectn::TicTacToe>::new(&buffer[..]);
+        let records = reader.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(records.len(), 2);
+        for record in records {
+            assert_eq!(record.ruleset, "tictactoe");
+            assert_eq!(record.agents, vec!["negamax", "negamax"]);
+            assert_eq!(record.status, game_log.status);
+            let plies = game_log
+                .history
+                .iter()
+                .map(|(_, ply)| *ply)
+                .collect::<Vec<_>>();
+            assert_eq!(record.plies, plies);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed_line() {
+        let input = "\n{\"ruleset\": \"tictactoe\"\n";
+        let mut reader = Reader::<_, connectn::TicTacToe>::new(input.as_bytes());
+        let error = reader.next().unwrap().err().unwrap();
+        assert!(error.to_string().starts_with("line 2:"));
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,45 @@
+//! Persistent game records
+//!
+//! Games are stored as JSON Lines, one game per line, so that collections can be appended to
+//! and streamed without loading them in memory. Human game collections can be imported from,
+//! and exported to, the SGF, Piskvork and RenjuNet formats, and Reversi games from move
+//! transcripts and the WTHOR database.
+
+/// Checks the message of the error returned by the reading function for each input, defined
+/// before the format modules so that their tests can use it.
+#[cfg(test)]
+macro_rules! error_tests {
+    ($read:expr, $($name:ident: $value:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let (input, expected_message) = $value;
+                let error = $read(input).err().unwrap();
+                let message = error.to_string();
+                assert!(message.starts_with(expected_message), "{}", message);
+            }
+        )*
+    }
+}
+
+mod errors;
+mod game_records;
+mod jsonl;
+mod replay;
+
+pub mod psq;
+pub mod renjunet;
+pub mod sgf;
+pub mod transcripts;
+pub mod wthor;
+
+pub use errors::GameError;
+pub use errors::RecordError;
+pub use errors::ReplayError;
+pub use game_records::GameRecord;
+pub use jsonl::Reader;
+pub use jsonl::Writer;
+pub use replay::import_plies;
+pub use replay::replay;
+pub use replay::replay_plies;
+pub use replay::validate;
@@ -0,0 +1,116 @@
+//! Piskvork game files, as used by the Gomocup tournament
+//!
+//! The first line holds the board size, followed by one `x,y,time` line per move with 1-based
+//! coordinates. The moves end at the first line without a comma, and anything after them, such
+//! as the engine names, is ignored. The files do not record the result of the game.
+
+use super::errors;
+use super::replay;
+use crate::interface::ai;
+use crate::rulesets::connectn;
+use std::error;
+
+fn parse_header<Variant: connectn::BaseVariant>(line: &str) -> Result<(), errors::RecordError> {
+    let size = line
+        .strip_prefix("Piskvorky ")
+        .and_then(|rest| rest.split(',').next())
+        .ok_or_else(|| errors::RecordError::new(1, "missing Piskvorky header"))?;
+    let expected = format!("{0}x{0}", Variant::GRID_SIZE);
+    if size.trim() != expected {
+        let message = format!("expected board size {}, found {}", expected, size.trim());
+        return Err(errors::RecordError::new(1, message));
+    }
+    Ok(())
+}
+
+fn parse_move<Variant: connectn::BaseVariant>(
+    line: &str,
+) -> Result<connectn::Ply<Variant>, String> {
+    let fields = line
+        .trim()
+        .split(',')
+        .map(|field| field.trim().parse::<i32>())
+        .collect::<Result<Vec<_>, _>>();
+    let (column, row) = match fields.as_deref() {
+        Ok([column, row, _]) => (*column, *row),
+        _ => return Err(format!("invalid move {}", line.trim())),
+    };
+    let size = Variant::GRID_SIZE as i32;
+    if column < 1 || column > size || row < 1 || row > size {
+        return Err(format!("move {} is out of the board", line.trim()));
+    }
+    Ok(connectn::Ply::new(((row - 1) * size + column - 1) as u8))
+}
+
+/// Reads a game from a Piskvork file.
+pub fn read<Variant: connectn::BaseVariant>(
+    ruleset: &connectn::RuleSet<Variant>,
+    input: &str,
+) -> Result<ai::GameLog<connectn::RuleSet<Variant>>, Box<dyn error::Error>> {
+    let mut lines = input.lines();
+    parse_header::<Variant>(lines.next().unwrap_or(""))?;
+    let mut plies = Vec::new();
+    for (index, line) in lines.enumerate() {
+        if !line.contains(',') {
+            break;
+        }
+        let ply = parse_move::<Variant>(line)
+            .map_err(|message| errors::RecordError::new(index + 2, message))?;
+        plies.push(ply);
+    }
+    replay::import_plies(ruleset, &plies, None).map_err(|error| {
+        let line = error.ply_index + 2;
+        Box::new(errors::RecordError::new(line, error.to_string())) as Box<dyn error::Error>
+    })
+}
+
+/// Writes a game as a Piskvork file.
+pub fn write<Variant: connectn::BaseVariant>(
+    game_log: &ai::GameLog<connectn::RuleSet<Variant>>,
+) -> String {
+    let mut result = format!("Piskvorky {0}x{0}, 11:11, 0\n", Variant::GRID_SIZE);
+    for (_, ply) in &game_log.history {
+        let row = ply.index as usize / Variant::GRID_SIZE;
+        let column = ply.index as usize % Variant::GRID_SIZE;
+        result.push_str(&format!("{},{},0\n", column + 1, row + 1));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::Gomoku::new();
+        let input = "Piskvorky 15x15, 11:11, 0\n8,8,0\n8,9,1250\n9,8,830\n-1\nengine.exe\n";
+        let game = read(&ruleset, input)?;
+        let indices = game
+            .history
+            .iter()
+            .map(|(_, ply)| ply.index)
+            .collect::<Vec<_>>();
+        assert_eq!(indices, vec![112, 127, 113]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_read() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::Gomoku::new();
+        let input = "Piskvorky 15x15, 11:11, 0\n1,1,0\n15,15,0\n2,1,0\n";
+        let game = read(&ruleset, input)?;
+        assert_eq!(write(&game), input);
+        Ok(())
+    }
+
+    error_tests! {
+        |input: &str| read(&connectn::Gomoku::new(), input),
+        empty: ("", "line 1: missing Piskvorky header"),
+        other_size: ("Piskvorky 20x20, 11:11, 0\n", "line 1: expected board size 15x15, found 20x20"),
+        out_of_board: ("Piskvorky 15x15, 11:11, 0\n8,8,0\n16,8,0\n", "line 3: move 16,8,0 is out of the board"),
+        malformed_move: ("Piskvorky 15x15, 11:11, 0\n8,8,0\n9,x,0\n10,10,0\n", "line 3: invalid move 9,x,0"),
+        missing_field: ("Piskvorky 15x15, 11:11, 0\n8,8\n", "line 2: invalid move 8,8"),
+        occupied: ("Piskvorky 15x15, 11:11, 0\n8,8,0\n8,8,0\n", "line 3: ply #1:"),
+    }
+}
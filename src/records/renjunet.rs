@@ -0,0 +1,189 @@
+//! RenjuNet XML database
+//!
+//! Each `<game>` element holds the result for black in its `bresult` attribute and the moves in
+//! its `<move>` child, as space separated coordinates such as `h8`, with columns as letters and
+//! rows numbered from the bottom of the board.
+//!
+//! # References
+//!
+//! * [RenjuNet database](https://www.renju.net/game/)
+
+use super::errors;
+use super::replay;
+use crate::interface::ai;
+use crate::interface::rulesets;
+use crate::rulesets::connectn;
+use std::error;
+
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!(" {}=\"", name);
+    let start = tag.find(&pattern)? + pattern.len();
+    let length = tag[start..].find('"')?;
+    Some(&tag[start..start + length])
+}
+
+fn parse_result(value: &str) -> Option<rulesets::Status> {
+    match value {
+        "1" => Some(rulesets::Status::Win { player: 0 }),
+        "0" => Some(rulesets::Status::Win { player: 1 }),
+        "0.5" => Some(rulesets::Status::Draw),
+        _ => None,
+    }
+}
+
+fn parse_move<Variant: connectn::BaseVariant>(value: &str) -> Option<connectn::Ply<Variant>> {
+    let mut characters = value.chars();
+    let column = (characters.next()? as usize).checked_sub('a' as usize)?;
+    let row = characters.as_str().parse::<usize>().ok()?;
+    if column >= Variant::GRID_SIZE || row < 1 || row > Variant::GRID_SIZE {
+        return None;
+    }
+    Some(connectn::Ply::new(
+        ((Variant::GRID_SIZE - row) * Variant::GRID_SIZE + column) as u8,
+    ))
+}
+
+fn format_move<Variant: connectn::BaseVariant>(ply: &connectn::Ply<Variant>) -> String {
+    let row = ply.index as usize / Variant::GRID_SIZE;
+    let column = ply.index as usize % Variant::GRID_SIZE;
+    format!(
+        "{}{}",
+        (b'a' + column as u8) as char,
+        Variant::GRID_SIZE - row
+    )
+}
+
+/// Reads the game element starting on the given line.
+fn read_game<Variant: connectn::BaseVariant>(
+    ruleset: &connectn::RuleSet<Variant>,
+    game: &str,
+    line: usize,
+) -> Result<ai::GameLog<connectn::RuleSet<Variant>>, errors::RecordError> {
+    let tag_end = game
+        .find('>')
+        .ok_or_else(|| errors::RecordError::new(line, "unterminated game tag"))?;
+    let result = attribute(&game[..tag_end], "bresult").and_then(parse_result);
+    let mut plies = Vec::new();
+    if let Some(moves_start) = game.find("<move>") {
+        let moves_start = moves_start + "<move>".len();
+        let moves_length = game[moves_start..]
+            .find("</move>")
+            .ok_or_else(|| errors::RecordError::new(line, "unterminated move element"))?;
+        for value in game[moves_start..moves_start + moves_length].split_whitespace() {
+            match parse_move(value) {
+                Some(ply) => plies.push(ply),
+                None => {
+                    let message = format!("invalid move {}", value);
+                    return Err(errors::RecordError::new(line, message));
+                }
+            }
+        }
+    }
+    replay::import_plies(ruleset, &plies, result)
+        .map_err(|error| errors::RecordError::new(line, error.to_string()))
+}
+
+/// Reads every game of a RenjuNet database.
+pub fn read<Variant: connectn::BaseVariant>(
+    ruleset: &connectn::RuleSet<Variant>,
+    input: &str,
+) -> Result<Vec<ai::GameLog<connectn::RuleSet<Variant>>>, Box<dyn error::Error>> {
+    let mut result = Vec::new();
+    let mut position = 0;
+    // Lines are counted as the input is scanned, up to the current position
+    let mut line = 1;
+    while let Some(offset) = input[position..].find("<game ") {
+        let start = position + offset;
+        line += input[position..start].matches('\n').count();
+        let end = match input[start..].find("</game>") {
+            Some(length) => start + length,
+            None => {
+                return Err(Box::new(errors::RecordError::new(
+                    line,
+                    "unterminated game element",
+                )));
+            }
+        };
+        result.push(read_game(ruleset, &input[start..end], line)?);
+        line += input[start..end].matches('\n').count();
+        position = end;
+    }
+    Ok(result)
+}
+
+/// Writes games as a RenjuNet database.
+pub fn write<Variant: connectn::BaseVariant>(
+    game_logs: &[ai::GameLog<connectn::RuleSet<Variant>>],
+) -> String {
+    let mut result =
+        String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<database>\n<games>\n");
+    for (index, game_log) in game_logs.iter().enumerate() {
+        result.push_str(&format!("<game id=\"{}\"", index + 1));
+        match game_log.status {
+            rulesets::Status::Win { player: 0 } => result.push_str(" bresult=\"1\""),
+            rulesets::Status::Win { .. } => result.push_str(" bresult=\"0\""),
+            rulesets::Status::Draw => result.push_str(" bresult=\"0.5\""),
+            rulesets::Status::Ongoing => (),
+        }
+        let moves = game_log
+            .history
+            .iter()
+            .map(|(_, ply)| format_move(ply))
+            .collect::<Vec<_>>();
+        result.push_str(&format!("><move>{}</move></game>\n", moves.join(" ")));
+    }
+    result.push_str("</games>\n</database>\n");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::Gomoku::new();
+        let input = "<database>\n<games>\n\
+            <game id=\"1\" bresult=\"0\" rule=\"1\">\n<move>h8 h9 i8</move>\n</game>\n\
+            <game id=\"2\" bresult=\"0.5\"><move>a1 o15</move></game>\n\
+            </games>\n</database>\n";
+        let games = read(&ruleset, input)?;
+        assert_eq!(games.len(), 2);
+        let indices = games[0]
+            .history
+            .iter()
+            .map(|(_, ply)| ply.index)
+            .collect::<Vec<_>>();
+        assert_eq!(indices, vec![112, 97, 113]);
+        assert_eq!(games[0].status, rulesets::Status::Win { player: 1 });
+        let indices = games[1]
+            .history
+            .iter()
+            .map(|(_, ply)| ply.index)
+            .collect::<Vec<_>>();
+        assert_eq!(indices, vec![210, 14]);
+        assert_eq!(games[1].status, rulesets::Status::Draw);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_read() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::Gomoku::new();
+        let input = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<database>\n<games>\n\
+            <game id=\"1\" bresult=\"1\"><move>h8 h9 i8</move></game>\n\
+            <game id=\"2\"><move>a1</move></game>\n\
+            </games>\n</database>\n";
+        let games = read(&ruleset, input)?;
+        assert_eq!(write(&games), input);
+        Ok(())
+    }
+
+    error_tests! {
+        |input: &str| read(&connectn::Gomoku::new(), input),
+        unterminated_game: ("<games>\n<game id=\"1\"><move>h8</move>", "line 2: unterminated game element"),
+        unterminated_move: ("<game id=\"1\"><move>h8</game>", "line 1: unterminated move element"),
+        invalid_move: ("\n<game id=\"1\"><move>h8 z3</move></game>", "line 2: invalid move z3"),
+        later_game: ("<game id=\"1\">\n<move>h8</move></game>\n\n<game id=\"2\"><move>z3</move></game>", "line 4: invalid move z3"),
+        occupied: ("<game id=\"1\"><move>h8 h8</move></game>", "line 1: ply #1:"),
+    }
+}
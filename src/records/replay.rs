@@ -0,0 +1,170 @@
+use super::errors;
+use super::game_records;
+use crate::interface::ai;
+use crate::interface::rulesets;
+use std::error;
+
+/// Rebuilds the game log of a record by playing its plies from the initial state.
+pub fn replay<RuleSet: rulesets::Deterministic>(
+    ruleset: &RuleSet,
+    record: &game_records::GameRecord<RuleSet>,
+) -> Result<ai::GameLog<RuleSet>, Box<dyn error::Error>> {
+    let game_log = replay_plies(ruleset, &record.plies)?;
+    Ok(game_log)
+}
+
+pub fn replay_plies<RuleSet: rulesets::Deterministic>(
+    ruleset: &RuleSet,
+    plies: &[RuleSet::Ply],
+) -> Result<ai::GameLog<RuleSet>, errors::ReplayError> {
+    let mut game_log = ai::GameLog::new();
+    let mut state = ruleset.initial_state();
+    for (index, ply) in plies.iter().enumerate() {
+        let resulting_state = ruleset
+            .play(&state, ply)
+            .map_err(|error| errors::ReplayError::new(index, format!("{:?}: {}", ply, error)))?;
+        game_log.history.push((state, *ply));
+        state = resulting_state;
+    }
+    game_log.status = ruleset.status(&state);
+    Ok(game_log)
+}
+
+/// Builds the game log of an imported game.
+///
+/// Human games often stop before the end of the game, on a resignation or a time loss: in that
+/// case the recorded result, if any, becomes the status of the game log. A recorded result
+/// contradicting the final position is an error, as are plies played after the end of the game.
+pub fn import_plies<RuleSet: rulesets::Deterministic>(
+    ruleset: &RuleSet,
+    plies: &[RuleSet::Ply],
+    result: Option<rulesets::Status>,
+) -> Result<ai::GameLog<RuleSet>, errors::ReplayError> {
+    let mut game_log = replay_plies(ruleset, plies)?;
+    check_ongoing(ruleset, &game_log)?;
+    match (game_log.status, result) {
+        (_, None) => (),
+        (rulesets::Status::Ongoing, Some(result)) => game_log.status = result,
+        (status, Some(result)) if status == result => (),
+        (status, Some(result)) => {
+            let message = format!(
+                "recorded result {:?} differs from final status {:?}",
+                result, status
+            );
+            return Err(errors::ReplayError::new(plies.len(), message));
+        }
+    }
+    Ok(game_log)
+}
+
+/// Replays a record and checks that the game stays ongoing until its last ply, and ends with the
+/// recorded status.
+pub fn validate<RuleSet: rulesets::Deterministic>(
+    ruleset: &RuleSet,
+    record: &game_records::GameRecord<RuleSet>,
+) -> Result<ai::GameLog<RuleSet>, Box<dyn error::Error>> {
+    let game_log = replay(ruleset, record)?;
+    check_ongoing(ruleset, &game_log)?;
+    if game_log.status != record.status {
+        let message = format!(
+            "recorded status {:?} differs from replayed status {:?}",
+            record.status, game_log.status
+        );
+        let error = errors::ReplayError::new(record.plies.len(), message);
+        return Err(Box::new(error));
+    }
+    Ok(game_log)
+}
+
+fn check_ongoing<RuleSet: rulesets::Deterministic>(
+    ruleset: &RuleSet,
+    game_log: &ai::GameLog<RuleSet>,
+) -> Result<(), errors::ReplayError> {
+    for (index, (state, _)) in game_log.history.iter().enumerate() {
+        let status = ruleset.status(state);
+        if status != rulesets::Status::Ongoing {
+            let message = format!("game already ended with {:?}", status);
+            return Err(errors::ReplayError::new(index, message));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rulesets::connectn;
+
+    fn build_plies(indices: &[u8]) -> Vec<connectn::TicTacToePly> {
+        indices
+            .iter()
+            .map(|index| connectn::Ply::new(*index))
+            .collect()
+    }
+
+    fn build_record(
+        indices: &[u8],
+        status: rulesets::Status,
+    ) -> game_records::GameRecord<connectn::TicTacToe> {
+        let mut record = game_records::GameRecord::new("tictactoe", &["p1", "p2"]);
+        record.plies = build_plies(indices);
+        record.status = status;
+        record
+    }
+
+    #[test]
+    fn test_valid_record() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::TicTacToe::new();
+        let record = build_record(&[4, 0, 3, 1, 5], rulesets::Status::Win { player: 0 });
+        let game_log = validate(&ruleset, &record)?;
+        assert_eq!(game_log.history.len(), 5);
+        assert_eq!(game_log.status, record.status);
+        Ok(())
+    }
+
+    #[test]
+    fn test_status_mismatch() {
+        let ruleset = connectn::TicTacToe::new();
+        let record = build_record(&[4, 0, 3, 1], rulesets::Status::Win { player: 0 });
+        assert!(validate(&ruleset, &record).is_err());
+    }
+
+    #[test]
+    fn test_plies_after_end() {
+        let ruleset = connectn::TicTacToe::new();
+        let record = build_record(&[4, 0, 3, 1, 5, 2], rulesets::Status::Win { player: 0 });
+        assert!(validate(&ruleset, &record).is_err());
+    }
+
+    #[test]
+    fn test_illegal_ply() {
+        let ruleset = connectn::TicTacToe::new();
+        let record = build_record(&[4, 4], rulesets::Status::Ongoing);
+        let error = replay(&ruleset, &record).err().unwrap();
+        assert!(error.to_string().starts_with("ply #1:"));
+    }
+
+    macro_rules! import_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (indices, result, expected) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let plies = build_plies(&indices);
+                    let status = import_plies(&ruleset, &plies, result).ok().map(|log| log.status);
+                    assert_eq!(status, expected);
+                }
+            )*
+        }
+    }
+
+    import_tests! {
+        finished: ([4, 0, 3, 1, 5], None, Some(rulesets::Status::Win { player: 0 })),
+        resigned: ([4, 0, 3], Some(rulesets::Status::Win { player: 0 }), Some(rulesets::Status::Win { player: 0 })),
+        unfinished: ([4, 0, 3], None, Some(rulesets::Status::Ongoing)),
+        matching_result: ([4, 0, 3, 1, 5], Some(rulesets::Status::Win { player: 0 }), Some(rulesets::Status::Win { player: 0 })),
+        contradicting_result: ([4, 0, 3, 1, 5], Some(rulesets::Status::Win { player: 1 }), None),
+        after_end: ([4, 0, 3, 1, 5, 2], None, None),
+    }
+}
@@ -0,0 +1,374 @@
+//! Smart Game Format
+//!
+//! Only the main line of each game tree is read, variations are ignored.
+//!
+//! # References
+//!
+//! * [SGF FF[4] specification](https://www.red-bean.com/sgf/sgf4.html)
+
+use super::errors;
+use super::replay;
+use crate::interface::ai;
+use crate::interface::rulesets;
+use crate::rulesets::connectn;
+use std::error;
+
+const GOMOKU_GAME_TYPE: &str = "4";
+
+pub struct Property {
+    pub identifier: String,
+    pub values: Vec<String>,
+}
+
+pub struct Node {
+    pub line: usize,
+    pub properties: Vec<Property>,
+}
+
+impl Node {
+    pub fn get(&self, identifier: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|property| property.identifier == identifier)
+            .and_then(|property| property.values.first())
+            .map(|value| value.as_str())
+    }
+}
+
+pub struct GameTree {
+    pub nodes: Vec<Node>,
+    pub variations: Vec<GameTree>,
+}
+
+impl GameTree {
+    pub fn main_line(&self) -> Vec<&Node> {
+        let mut result = self.nodes.iter().collect::<Vec<_>>();
+        if let Some(variation) = self.variations.first() {
+            result.extend(variation.main_line());
+        }
+        result
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Parser<'a> {
+        Parser {
+            input: input.as_bytes(),
+            position: 0,
+            line: 1,
+        }
+    }
+
+    fn error(&self, message: &str) -> errors::RecordError {
+        errors::RecordError::new(self.line, message)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).cloned()
+    }
+
+    fn advance(&mut self) -> Option<u8> {
+        let character = self.peek()?;
+        if character == b'\n' {
+            self.line += 1;
+        }
+        self.position += 1;
+        Some(character)
+    }
+
+    fn skip_whitespaces(&mut self) {
+        while let Some(character) = self.peek() {
+            if !character.is_ascii_whitespace() {
+                break;
+            }
+            self.advance();
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), errors::RecordError> {
+        self.skip_whitespaces();
+        match self.advance() {
+            Some(character) if character == expected => Ok(()),
+            Some(character) => Err(self.error(&format!(
+                "expected '{}', found '{}'",
+                expected as char, character as char
+            ))),
+            None => Err(self.error(&format!(
+                "expected '{}', found end of file",
+                expected as char
+            ))),
+        }
+    }
+
+    fn parse_collection(&mut self) -> Result<Vec<GameTree>, errors::RecordError> {
+        let mut result = Vec::new();
+        loop {
+            self.skip_whitespaces();
+            match self.peek() {
+                Some(b'(') => result.push(self.parse_game_tree()?),
+                Some(character) => {
+                    return Err(self.error(&format!("unexpected '{}'", character as char)))
+                }
+                None => break,
+            }
+        }
+        if result.is_empty() {
+            return Err(self.error("no game found"));
+        }
+        Ok(result)
+    }
+
+    fn parse_game_tree(&mut self) -> Result<GameTree, errors::RecordError> {
+        self.expect(b'(')?;
+        let mut nodes = Vec::new();
+        let mut variations = Vec::new();
+        loop {
+            self.skip_whitespaces();
+            match self.peek() {
+                Some(b';') if variations.is_empty() => nodes.push(self.parse_node()?),
+                Some(b'(') => variations.push(self.parse_game_tree()?),
+                Some(b')') => {
+                    self.advance();
+                    break;
+                }
+                Some(character) => {
+                    return Err(self.error(&format!("unexpected '{}'", character as char)))
+                }
+                None => return Err(self.error("unterminated game tree")),
+            }
+        }
+        if nodes.is_empty() {
+            return Err(self.error("empty game tree"));
+        }
+        Ok(GameTree { nodes, variations })
+    }
+
+    fn parse_node(&mut self) -> Result<Node, errors::RecordError> {
+        self.expect(b';')?;
+        let line = self.line;
+        let mut properties = Vec::new();
+        loop {
+            self.skip_whitespaces();
+            match self.peek() {
+                Some(character) if character.is_ascii_uppercase() => {
+                    properties.push(self.parse_property()?)
+                }
+                _ => break,
+            }
+        }
+        Ok(Node { line, properties })
+    }
+
+    fn parse_property(&mut self) -> Result<Property, errors::RecordError> {
+        let mut identifier = String::new();
+        while let Some(character) = self.peek() {
+            if !character.is_ascii_uppercase() {
+                break;
+            }
+            identifier.push(character as char);
+            self.advance();
+        }
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespaces();
+            if self.peek() != Some(b'[') {
+                break;
+            }
+            values.push(self.parse_value()?);
+        }
+        if values.is_empty() {
+            return Err(self.error(&format!("property {} has no value", identifier)));
+        }
+        Ok(Property { identifier, values })
+    }
+
+    fn parse_value(&mut self) -> Result<String, errors::RecordError> {
+        self.expect(b'[')?;
+        let mut value = Vec::new();
+        loop {
+            match self.advance() {
+                Some(b']') => break,
+                Some(b'\\') => match self.advance() {
+                    Some(character) => value.push(character),
+                    None => return Err(self.error("unterminated property value")),
+                },
+                Some(character) => value.push(character),
+                None => return Err(self.error("unterminated property value")),
+            }
+        }
+        String::from_utf8(value).map_err(|_| self.error("property value is not valid UTF-8"))
+    }
+}
+
+/// Parses a SGF collection into its game trees.
+pub fn parse(input: &str) -> Result<Vec<GameTree>, Box<dyn error::Error>> {
+    let mut parser = Parser::new(input);
+    let collection = parser.parse_collection()?;
+    Ok(collection)
+}
+
+fn parse_result(value: &str) -> Option<rulesets::Status> {
+    match value.chars().next() {
+        Some('B') | Some('b') => Some(rulesets::Status::Win { player: 0 }),
+        Some('W') | Some('w') => Some(rulesets::Status::Win { player: 1 }),
+        Some('0') | Some('D') | Some('d') => Some(rulesets::Status::Draw),
+        _ => None,
+    }
+}
+
+fn parse_point<Variant: connectn::BaseVariant>(value: &str) -> Option<connectn::Ply<Variant>> {
+    let coordinates = value.as_bytes();
+    if coordinates.len() != 2 {
+        return None;
+    }
+    let column = coordinates[0].checked_sub(b'a')? as usize;
+    let row = coordinates[1].checked_sub(b'a')? as usize;
+    if column >= Variant::GRID_SIZE || row >= Variant::GRID_SIZE {
+        return None;
+    }
+    Some(connectn::Ply::new(
+        (row * Variant::GRID_SIZE + column) as u8,
+    ))
+}
+
+fn convert_game<Variant: connectn::BaseVariant>(
+    ruleset: &connectn::RuleSet<Variant>,
+    game: &GameTree,
+) -> Result<ai::GameLog<connectn::RuleSet<Variant>>, Box<dyn error::Error>> {
+    let nodes = game.main_line();
+    let root = nodes[0];
+    if let Some(game_type) = root.get("GM") {
+        if game_type != GOMOKU_GAME_TYPE {
+            let message = format!("unsupported game type {}", game_type);
+            return Err(Box::new(errors::RecordError::new(root.line, message)));
+        }
+    }
+    if let Some(size) = root.get("SZ") {
+        if size.parse::<usize>().ok() != Some(Variant::GRID_SIZE) {
+            let message = format!("expected board size {}, found {}", Variant::GRID_SIZE, size);
+            return Err(Box::new(errors::RecordError::new(root.line, message)));
+        }
+    }
+    let result = root.get("RE").and_then(parse_result);
+    let mut plies = Vec::new();
+    for node in nodes {
+        for property in &node.properties {
+            let player = match property.identifier.as_str() {
+                "B" => 0,
+                "W" => 1,
+                "AB" | "AW" | "AE" => {
+                    let message = "setup properties are not supported";
+                    return Err(Box::new(errors::RecordError::new(node.line, message)));
+                }
+                _ => continue,
+            };
+            if plies.len() % 2 != player {
+                let message = format!("unexpected {} move", property.identifier);
+                return Err(Box::new(errors::RecordError::new(node.line, message)));
+            }
+            let ply = match parse_point::<Variant>(&property.values[0]) {
+                Some(ply) => ply,
+                None => {
+                    let message = format!("invalid point [{}]", property.values[0]);
+                    return Err(Box::new(errors::RecordError::new(node.line, message)));
+                }
+            };
+            plies.push(ply);
+        }
+    }
+    replay::import_plies(ruleset, &plies, result).map_err(|error| {
+        let message = error.to_string();
+        Box::new(errors::RecordError::new(root.line, message)) as Box<dyn error::Error>
+    })
+}
+
+/// Reads every game of a SGF collection.
+pub fn read<Variant: connectn::BaseVariant>(
+    ruleset: &connectn::RuleSet<Variant>,
+    input: &str,
+) -> Result<Vec<ai::GameLog<connectn::RuleSet<Variant>>>, Box<dyn error::Error>> {
+    parse(input)?
+        .iter()
+        .map(|game| convert_game(ruleset, game))
+        .collect()
+}
+
+/// Writes a game as a SGF game tree.
+pub fn write<Variant: connectn::BaseVariant>(
+    game_log: &ai::GameLog<connectn::RuleSet<Variant>>,
+) -> String {
+    let mut result = format!("(;GM[{}]FF[4]SZ[{}]", GOMOKU_GAME_TYPE, Variant::GRID_SIZE);
+    match game_log.status {
+        rulesets::Status::Win { player: 0 } => result.push_str("RE[B+]"),
+        rulesets::Status::Win { .. } => result.push_str("RE[W+]"),
+        rulesets::Status::Draw => result.push_str("RE[0]"),
+        rulesets::Status::Ongoing => (),
+    }
+    for (state, ply) in &game_log.history {
+        let color = if state.current_player == 0 { 'B' } else { 'W' };
+        let row = ply.index as usize / Variant::GRID_SIZE;
+        let column = ply.index as usize % Variant::GRID_SIZE;
+        result.push_str(&format!(
+            ";{}[{}{}]",
+            color,
+            (b'a' + column as u8) as char,
+            (b'a' + row as u8) as char
+        ));
+    }
+    result.push_str(")\n");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::Gomoku::new();
+        let input =
+            "(;GM[4]FF[4]SZ[15]PB[Black \\] player]RE[B+]\n;B[hh];W[hi](;B[ih];W[ii])(;B[aa]))";
+        let games = read(&ruleset, input)?;
+        assert_eq!(games.len(), 1);
+        let indices = games[0]
+            .history
+            .iter()
+            .map(|(_, ply)| ply.index)
+            .collect::<Vec<_>>();
+        assert_eq!(indices, vec![112, 127, 113, 128]);
+        assert_eq!(games[0].status, rulesets::Status::Win { player: 0 });
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_read() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = connectn::Gomoku::new();
+        let input = "(;GM[4]FF[4]SZ[15];B[hh];W[hi];B[ih];W[ii])(;GM[4]FF[4]SZ[15];B[aa])";
+        let games = read(&ruleset, input)?;
+        assert_eq!(games.len(), 2);
+        let output = games.iter().map(write).collect::<String>();
+        assert_eq!(
+            output,
+            "(;GM[4]FF[4]SZ[15];B[hh];W[hi];B[ih];W[ii])\n(;GM[4]FF[4]SZ[15];B[aa])\n"
+        );
+        Ok(())
+    }
+
+    error_tests! {
+        |input: &str| read(&connectn::Gomoku::new(), input),
+        empty: ("", "line 1: no game found"),
+        unterminated: ("(;GM[4]\n;B[hh]", "line 2: unterminated game tree"),
+        unterminated_value: ("(;GM[4];B[hh", "line 1: unterminated property value"),
+        other_game: ("(;GM[1];B[hh])", "line 1: unsupported game type 1"),
+        other_size: ("(;GM[4]SZ[19];B[hh])", "line 1: expected board size 15, found 19"),
+        invalid_point: ("(;GM[4]\n;B[hz])", "line 2: invalid point [hz]"),
+        wrong_color: ("(;GM[4];B[hh];B[hi])", "line 1: unexpected B move"),
+        occupied: ("(;GM[4];B[hh];W[hh])", "line 1: ply #1:"),
+    }
+}
@@ -0,0 +1,164 @@
+//! Reversi move transcripts
+//!
+//! Transcripts list the placements as coordinates such as `f5d6c3`, with columns as letters and
+//! rows as numbers. The grid of the ruleset stores the standard opening position upside down, so
+//! the rows of the notation are numbered from the bottom of the grid. Passes are omitted, and
+//! inserted back when replaying whenever the current player cannot place a peg.
+
+use super::errors;
+use super::replay;
+use crate::interface::ai;
+use crate::interface::rulesets;
+use crate::interface::rulesets::Deterministic;
+use crate::interface::rulesets::PlyIteratorTrait;
+use crate::interface::rulesets::RuleSetTrait;
+use crate::rulesets::reversi;
+use std::error;
+
+/// Converts 1-based notation coordinates to a cell index.
+pub fn cell_index<Variant: reversi::BaseVariant>(row: usize, column: usize) -> usize {
+    (Variant::GRID_SIZE - row) * Variant::GRID_SIZE + column - 1
+}
+
+fn must_pass<Variant: reversi::BaseVariant>(
+    ruleset: &reversi::Reversi<Variant>,
+    state: &reversi::State<Variant>,
+) -> bool {
+    let mut iterator =
+        <reversi::Reversi<Variant> as RuleSetTrait>::PlyIterator::new(ruleset, state);
+    iterator.iterate(ruleset, state) == Some(reversi::Ply::Pass)
+}
+
+/// Builds the game log of a sequence of placements, inserting the missing passes.
+pub fn import_placements<Variant: reversi::BaseVariant>(
+    ruleset: &reversi::Reversi<Variant>,
+    placements: &[usize],
+    result: Option<rulesets::Status>,
+) -> Result<ai::GameLog<reversi::Reversi<Variant>>, errors::ReplayError> {
+    let mut plies = Vec::new();
+    let mut state = ruleset.initial_state();
+    for index in placements {
+        if must_pass(ruleset, &state) {
+            plies.push(reversi::Ply::Pass);
+            state = ruleset.play(&state, &reversi::Ply::Pass).unwrap();
+        }
+        let ply = reversi::Ply::Place(*index);
+        plies.push(ply);
+        state = match ruleset.play(&state, &ply) {
+            Ok(state) => state,
+            // The replay reports the illegal ply
+            Err(_) => break,
+        };
+    }
+    replay::import_plies(ruleset, &plies, result)
+}
+
+/// Parses the placements of a transcript into cell indices.
+pub fn parse_placements<Variant: reversi::BaseVariant>(
+    transcript: &str,
+) -> Result<Vec<usize>, errors::ReplayError> {
+    let mut result = Vec::new();
+    let mut characters = transcript
+        .chars()
+        .filter(|character| !character.is_whitespace())
+        .peekable();
+    while let Some(letter) = characters.next() {
+        let mut digits = String::new();
+        while let Some(digit) = characters
+            .peek()
+            .filter(|character| character.is_ascii_digit())
+        {
+            digits.push(*digit);
+            characters.next();
+        }
+        let column = (letter.to_ascii_lowercase() as usize).wrapping_sub('a' as usize);
+        let row = digits.parse::<usize>().unwrap_or(0);
+        if column >= Variant::GRID_SIZE || row < 1 || row > Variant::GRID_SIZE {
+            let message = format!("invalid placement {}{}", letter, digits);
+            return Err(errors::ReplayError::new(result.len(), message));
+        }
+        result.push(cell_index::<Variant>(row, column + 1));
+    }
+    Ok(result)
+}
+
+/// Reads a game from its transcript.
+pub fn parse<Variant: reversi::BaseVariant>(
+    ruleset: &reversi::Reversi<Variant>,
+    transcript: &str,
+) -> Result<ai::GameLog<reversi::Reversi<Variant>>, Box<dyn error::Error>> {
+    let placements = parse_placements::<Variant>(transcript)?;
+    let game_log = import_placements(ruleset, &placements, None)?;
+    Ok(game_log)
+}
+
+/// Writes the transcript of a game, omitting its passes.
+pub fn format<Variant: reversi::BaseVariant>(
+    game_log: &ai::GameLog<reversi::Reversi<Variant>>,
+) -> String {
+    game_log
+        .history
+        .iter()
+        .filter_map(|(_, ply)| match ply {
+            reversi::Ply::Place(index) => Some(format!(
+                "{}{}",
+                (b'a' + (index % Variant::GRID_SIZE) as u8) as char,
+                Variant::GRID_SIZE - index / Variant::GRID_SIZE
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_format() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = reversi::Reversi::<reversi::Classic>::new();
+        let game_log = parse(&ruleset, "F5d6 c3d3C4")?;
+        let plies = game_log
+            .history
+            .iter()
+            .map(|(_, ply)| *ply)
+            .collect::<Vec<_>>();
+        let expected = [29, 19, 42, 43, 34]
+            .iter()
+            .map(|index| reversi::Ply::Place(*index))
+            .collect::<Vec<_>>();
+        assert_eq!(plies, expected);
+        assert_eq!(game_log.status, rulesets::Status::Ongoing);
+        assert_eq!(format(&game_log), "f5d6c3d3c4");
+        Ok(())
+    }
+
+    #[test]
+    fn test_wipeout() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = reversi::Reversi::<reversi::Classic>::new();
+        let game_log = parse(&ruleset, "f5f4c3e6f7c5b5f6f3")?;
+        assert_eq!(game_log.status, rulesets::Status::Win { player: 0 });
+        Ok(())
+    }
+
+    #[test]
+    fn test_inserted_passes() -> Result<(), Box<dyn error::Error>> {
+        let ruleset = reversi::Reversi::<reversi::Micro>::new();
+        let transcript = "a2a3d4b1a1c1a4b4d1d2c4d3";
+        let game_log = parse(&ruleset, transcript)?;
+        assert!(game_log
+            .history
+            .iter()
+            .any(|(_, ply)| *ply == reversi::Ply::Pass));
+        assert_ne!(game_log.status, rulesets::Status::Ongoing);
+        assert_eq!(format(&game_log), transcript);
+        Ok(())
+    }
+
+    error_tests! {
+        |transcript: &str| parse(&reversi::Reversi::<reversi::Classic>::new(), transcript),
+        out_of_board: ("f5d6i3", "ply #2: invalid placement i3"),
+        missing_row: ("f5d", "ply #1: invalid placement d"),
+        illegal_placement: ("f5a1", "ply #1:"),
+    }
+}
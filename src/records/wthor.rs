@@ -0,0 +1,163 @@
+//! WTHOR Othello database
+//!
+//! A 16-byte header holding the number of games is followed by one 68-byte record per game: the
+//! tournament and player identifiers, the final number of black pegs and the theoretical score,
+//! then 60 placements encoded as `10 * row + column` with 1-based coordinates, 0 marking the end
+//! of the game.
+
+use super::errors;
+use super::transcripts;
+use crate::interface::ai;
+use crate::interface::rulesets;
+use crate::rulesets::reversi;
+use std::cmp;
+use std::error;
+use std::io;
+
+const HEADER_SIZE: usize = 16;
+const RECORD_SIZE: usize = 68;
+const MOVES_OFFSET: usize = 8;
+const BLACK_PEGS_OFFSET: usize = 6;
+
+pub type ClassicGameLog = ai::GameLog<reversi::Reversi<reversi::Classic>>;
+
+pub struct Header {
+    pub game_count: usize,
+    pub year: u16,
+}
+
+pub struct Reader<Input: io::Read> {
+    input: Input,
+    ruleset: reversi::Reversi<reversi::Classic>,
+    pub header: Header,
+    game_index: usize,
+}
+
+impl<Input: io::Read> Reader<Input> {
+    pub fn new(mut input: Input) -> Result<Reader<Input>, Box<dyn error::Error>> {
+        let mut header = [0; HEADER_SIZE];
+        input.read_exact(&mut header)?;
+        let board_size = header[12];
+        if board_size != 0 && board_size != 8 {
+            let message = format!("unsupported board size {}", board_size);
+            return Err(Box::new(errors::GameError::new(0, message)));
+        }
+        let game_count = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let year = u16::from_le_bytes([header[10], header[11]]);
+        Ok(Reader {
+            input,
+            ruleset: reversi::Reversi::new(),
+            header: Header {
+                game_count: game_count as usize,
+                year,
+            },
+            game_index: 0,
+        })
+    }
+
+    fn read_game(&mut self) -> Result<ClassicGameLog, Box<dyn error::Error>> {
+        let mut record = [0; RECORD_SIZE];
+        self.input.read_exact(&mut record).map_err(|error| {
+            errors::GameError::new(self.game_index, format!("truncated record: {}", error))
+        })?;
+        let mut placements = Vec::new();
+        for value in record[MOVES_OFFSET..].iter().cloned() {
+            if value == 0 {
+                break;
+            }
+            let row = (value / 10) as usize;
+            let column = (value % 10) as usize;
+            if !(1..=8).contains(&row) || !(1..=8).contains(&column) {
+                let message = format!("invalid placement {}", value);
+                return Err(Box::new(errors::GameError::new(self.game_index, message)));
+            }
+            placements.push(transcripts::cell_index::<reversi::Classic>(row, column));
+        }
+        // Empty cells are awarded to the winner, so the pegs of black only tell the winner apart
+        let result = match record[BLACK_PEGS_OFFSET].cmp(&32) {
+            cmp::Ordering::Greater => rulesets::Status::Win { player: 0 },
+            cmp::Ordering::Equal => rulesets::Status::Draw,
+            cmp::Ordering::Less => rulesets::Status::Win { player: 1 },
+        };
+        transcripts::import_placements(&self.ruleset, &placements, Some(result)).map_err(|error| {
+            let error = errors::GameError::new(self.game_index, error.to_string());
+            Box::new(error) as Box<dyn error::Error>
+        })
+    }
+}
+
+impl<Input: io::Read> Iterator for Reader<Input> {
+    type Item = Result<ClassicGameLog, Box<dyn error::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.game_index >= self.header.game_count {
+            return None;
+        }
+        let result = self.read_game();
+        self.game_index += 1;
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_database(games: &[(u8, &[u8])]) -> Vec<u8> {
+        let mut result = vec![
+            20,
+            19,
+            1,
+            1,
+            games.len() as u8,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0xcf,
+            0x07,
+            8,
+            0,
+            0,
+            0,
+        ];
+        for (black_pegs, moves) in games {
+            let mut record = vec![0; RECORD_SIZE];
+            record[BLACK_PEGS_OFFSET] = *black_pegs;
+            record[MOVES_OFFSET..MOVES_OFFSET + moves.len()].copy_from_slice(moves);
+            result.extend(record);
+        }
+        result
+    }
+
+    #[test]
+    fn test_read() -> Result<(), Box<dyn error::Error>> {
+        let database = build_database(&[
+            (64, &[56, 64, 33, 34]),
+            (40, &[56, 46, 33, 65, 76, 53, 52, 66, 36]),
+        ]);
+        let reader = Reader::new(&database[..])?;
+        assert_eq!(reader.header.game_count, 2);
+        assert_eq!(reader.header.year, 1999);
+        let games = reader.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(transcripts::format(&games[0]), "f5d6c3d3");
+        assert_eq!(games[0].status, rulesets::Status::Win { player: 0 });
+        assert_eq!(transcripts::format(&games[1]), "f5f4c3e6f7c5b5f6f3");
+        assert_eq!(games[1].status, rulesets::Status::Win { player: 0 });
+        Ok(())
+    }
+
+    error_tests! {
+        |mut database: Vec<u8>| {
+            // Announcing one more game than recorded leaves the last record truncated
+            database[4] += 1;
+            let mut reader = Reader::new(&database[..]).unwrap();
+            reader.find_map(|game| game.err()).map_or(Ok(()), Err)
+        },
+        truncated: (build_database(&[(32, &[56])]), "game #1: truncated record"),
+        invalid_placement: (build_database(&[(32, &[56, 69])]), "game #0: invalid placement 69"),
+        illegal_placement: (build_database(&[(32, &[56, 11])]), "game #0: ply #1:"),
+        contradicting_result: (build_database(&[(20, &[56, 46, 33, 65, 76, 53, 52, 66, 36])]), "game #0: ply #9: recorded result"),
+    }
+}
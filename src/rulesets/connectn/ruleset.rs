@@ -28,6 +28,11 @@ impl<Variant: variants::BaseVariant> RuleSet<Variant> {
     pub fn grid_symmetry_count(&self) -> usize {
         self.symmetries.permutations.len()
     }
+
+    /// Masks of the runs of cells a player must fill to win.
+    pub(crate) fn strips(&self) -> &[bitarray::BitArray<Variant::ArraySettings>] {
+        &self.strips
+    }
 }
 
 impl<Variant: variants::BaseVariant> Default for RuleSet<Variant> {
@@ -149,6 +154,7 @@ mod tests {
     use crate::interface::rulesets::HasStatesWithSymmetries;
     use crate::interface::rulesets::RuleSetTrait;
     use crate::interface::rulesets::SymmetryIteratorTrait;
+    use crate::tests::conformance;
     use std::collections;
 
     pub type TicTacToe = RuleSet<variants::TicTacToe>;
@@ -201,4 +207,9 @@ mod tests {
         }
         assert_eq!(permutation_set.len(), 16);
     }
+
+    conformance::conformance_tests! {
+        tictactoe_conformance: TicTacToe::new() => [legal_plies, symmetries, swap_ply, encoding, status],
+        gomoku_conformance: RuleSet::<variants::Gomoku>::new() => [legal_plies, symmetries, swap_ply, encoding, status],
+    }
 }
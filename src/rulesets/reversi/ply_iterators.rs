@@ -21,6 +21,8 @@ pub struct PlyIterator<Variant: variants::BaseVariant> {
     current_strip: strips::Indices,
     strip_state: (CellState, CellState),
     seen: collections::HashSet<usize>,
+    opponent_can_place: bool,
+    done: bool,
     variant: marker::PhantomData<Variant>,
 }
 
@@ -87,6 +89,8 @@ impl<Variant: variants::BaseVariant> rulesets::PlyIteratorTrait<ruleset::Reversi
             current_index: -1,
             strip_state: (CellState::Empty { index: 0 }, CellState::Empty { index: 0 }),
             seen: collections::HashSet::new(),
+            opponent_can_place: false,
+            done: false,
             variant: marker::PhantomData,
         }
     }
@@ -96,13 +100,25 @@ impl<Variant: variants::BaseVariant> rulesets::PlyIteratorTrait<ruleset::Reversi
         _ruleset: &ruleset::Reversi<Variant>,
         state: &state::State<Variant>,
     ) -> Option<plies::Ply<Variant>> {
+        if self.done {
+            return None;
+        }
         while let Some((player, index)) = self.iterate_grid(state) {
-            if player != state.current_player || self.seen.contains(&index) {
+            if player != state.current_player {
+                self.opponent_can_place = true;
+                continue;
+            }
+            if self.seen.contains(&index) {
                 continue;
             }
             self.seen.insert(index);
             return Some(plies::Ply::<Variant>::Place(index));
         }
+        self.done = true;
+        // The current player must pass when they cannot place a peg while their opponent can
+        if self.seen.is_empty() && self.opponent_can_place {
+            return Some(plies::Ply::<Variant>::Pass);
+        }
         None
     }
 }
@@ -172,4 +188,14 @@ mod tests {
         duplicates: ([2, 16], [1, 8], 0, [0]),
         none: ([0, 1, 2, 3], [4, 5, 6, 7], 0, []),
     }
+
+    #[test]
+    fn test_pass() {
+        let ruleset = ClassicReversi::default();
+        let state = state::State::from_indices(&[0], &[1, 2], 1);
+        let mut iterator = PlyIterator::new(&ruleset, &state);
+        assert_eq!(iterator.iterate(&ruleset, &state), Some(ClassicPly::Pass));
+        assert_eq!(iterator.iterate(&ruleset, &state), None);
+        assert_eq!(ruleset.status(&state), rulesets::Status::Ongoing);
+    }
 }
@@ -160,6 +160,13 @@ impl<Variant: variants::BaseVariant> rulesets::Deterministic for Reversi<Variant
                 Ok(result)
             }
             plies::Ply::Pass => {
+                let mut ply_iterator = Self::PlyIterator::new(self, state);
+                if ply_iterator.iterate(self, state) != Some(plies::Ply::Pass) {
+                    return Err(rulesets::PlayError {
+                        message: "Passing is only allowed when no peg can be placed",
+                        field: "ply",
+                    });
+                }
                 let mut result = state.clone();
                 result.current_player = 1 - result.current_player;
                 Ok(result)
@@ -196,6 +203,42 @@ impl<Variant: variants::BaseVariant> rulesets::HasStatesWithSymmetries for Rever
     }
 }
 
+impl<Variant: variants::BaseVariant> rulesets::EncodableState for Reversi<Variant> {
+    const STATE_SIZE: usize = Variant::CELL_COUNT * 3;
+    const PLY_COUNT: usize = Variant::CELL_COUNT + 1;
+
+    fn encode_state(&self, state: &Self::State) -> Vec<f32> {
+        let mut result = vec![0.0; Variant::CELL_COUNT * 3];
+        let player = state.current_player as usize;
+        let opponent = 1 - player;
+        for index in 0..Variant::CELL_COUNT {
+            if state.grids[player].isset(index) {
+                result[index] = 1.0;
+            } else if state.grids[opponent].isset(index) {
+                result[index + Variant::CELL_COUNT] = 1.0;
+            } else {
+                result[index + Variant::CELL_COUNT * 2] = 1.0;
+            }
+        }
+        result
+    }
+
+    fn decode_ply(&self, ply_index: usize) -> Self::Ply {
+        if ply_index == Variant::CELL_COUNT {
+            return plies::Ply::Pass;
+        }
+        plies::Ply::Place(ply_index)
+    }
+
+    fn encode_ply(&self, ply: &Self::Ply) -> usize {
+        match *ply {
+            plies::Ply::Place(index) => index,
+            plies::Ply::Pass => Variant::CELL_COUNT,
+            plies::Ply::Unused(_) => unreachable!(),
+        }
+    }
+}
+
 impl<Variant: variants::BaseVariant> rulesets::TurnByTurn for Reversi<Variant> {
     fn current_player(&self, state: &Self::State) -> rulesets::Player {
         state.current_player
@@ -209,9 +252,11 @@ mod tests {
     use super::*;
     use crate::interface::rulesets;
     use crate::interface::rulesets::Deterministic;
+    use crate::interface::rulesets::EncodableState;
     use crate::interface::rulesets::HasStatesWithSymmetries;
     use crate::interface::rulesets::RuleSetTrait;
     use crate::interface::rulesets::SymmetryIteratorTrait;
+    use crate::tests::conformance;
     use std::collections;
 
     type MicroPly = plies::Ply<instances::Micro>;
@@ -225,6 +270,23 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_pass_with_available_placement() {
+        let game = Reversi::<instances::Micro>::default();
+        let state = state::State::from_indices(&[5, 10], &[6, 9], 0);
+        let result = game.play(&state, &MicroPly::Pass);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_forced_pass() {
+        let game = Reversi::<instances::Micro>::default();
+        let state = state::State::from_indices(&[0], &[1, 2], 1);
+        let resulting_state = game.play(&state, &MicroPly::Pass).unwrap();
+        let expected = state::State::from_indices(&[0], &[1, 2], 0);
+        assert_eq!(resulting_state, expected);
+    }
+
     #[test]
     fn test_play_on_non_reversing_cell() {
         let game = Reversi::<instances::Micro>::default();
@@ -283,6 +345,9 @@ mod tests {
         p1_win: ([0, 1, 2, 4, 4, 5, 7, 8], [13, 14, 15], 0, rulesets::Status::Win{player: 0}),
         p2_win: ([0, 1, 4], [3, 6, 7, 9, 11, 12, 13, 14], 0, rulesets::Status::Win{player: 1}),
         draw: ([1, 2, 3, 4, 6], [9, 11, 12, 13, 14], 0, rulesets::Status::Draw),
+        forced_pass: ([0], [1, 2], 1, rulesets::Status::Ongoing),
+        no_placement_win: ([0, 1], [15], 0, rulesets::Status::Win{player: 0}),
+        no_placement_draw: ([0], [15], 1, rulesets::Status::Draw),
     }
 
     #[test]
@@ -302,4 +367,33 @@ mod tests {
         }
         assert_eq!(symmetry_set.len(), 16);
     }
+
+    #[test]
+    fn test_encode_ply() {
+        let game = Reversi::<instances::Micro>::default();
+        for index in 0..16 {
+            let ply = MicroPly::Place(index);
+            assert_eq!(game.decode_ply(game.encode_ply(&ply)), ply);
+        }
+        assert_eq!(game.encode_ply(&MicroPly::Pass), 16);
+        assert_eq!(game.decode_ply(16), MicroPly::Pass);
+    }
+
+    #[test]
+    fn test_encode_state() {
+        let game = Reversi::<instances::Micro>::default();
+        let state = state::State::from_indices(&[5], &[6], 1);
+        let encoded = game.encode_state(&state);
+        assert_eq!(encoded.len(), 48);
+        assert_eq!(encoded[6], 1.0);
+        assert_eq!(encoded[16 + 5], 1.0);
+        assert_eq!(encoded[32], 1.0);
+        assert_eq!(encoded.iter().sum::<f32>(), 16.0);
+    }
+
+    conformance::conformance_tests! {
+        micro_conformance: Reversi::<instances::Micro>::new() => [legal_plies, symmetries, swap_ply, encoding, status],
+        mini_conformance: Reversi::<instances::Mini>::new() => [legal_plies, symmetries, swap_ply, encoding, status],
+        classic_conformance: Reversi::<instances::Classic>::new() => [legal_plies, symmetries, swap_ply, encoding, status],
+    }
 }
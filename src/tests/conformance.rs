@@ -0,0 +1,215 @@
+//! Conformance checks shared by every ruleset
+//!
+//! The checks run on the states met along random playouts. Rulesets instantiate them with
+//! `conformance_tests!`, naming the checks matching the traits they implement:
+//!
+//! ```ignore
+//! conformance_tests! {
+//!     tictactoe: connectn::TicTacToe::new() => [legal_plies, symmetries, encoding],
+//! }
+//! ```
+
+use crate::interface::rulesets;
+use crate::interface::rulesets::SymmetryIteratorTrait;
+use crate::tools::plies;
+use rand::seq::IteratorRandom;
+
+const PLAYOUT_COUNT: usize = 10;
+
+macro_rules! conformance_tests {
+    ($($name:ident: $ruleset:expr => [$($check:ident),* $(,)?],)*) => {
+        $(
+            mod $name {
+                use super::*;
+
+                $(
+                    #[test]
+                    fn $check() {
+                        let ruleset = $ruleset;
+                        crate::tests::conformance::$check(&ruleset);
+                    }
+                )*
+            }
+        )*
+    }
+}
+
+pub(crate) use conformance_tests;
+
+/// Maximum number of plies of a playout, games running longer being deemed never to end
+const MAX_PLAYOUT_LENGTH: usize = 10_000;
+
+/// A game played at random, `states[i + 1]` resulting from playing `plies[i]` on `states[i]`.
+pub struct Playout<RuleSet: rulesets::RuleSetTrait> {
+    pub plies: Vec<RuleSet::Ply>,
+    pub states: Vec<RuleSet::State>,
+}
+
+/// Plays random games until they reach a terminal state.
+pub fn playouts<RuleSet: rulesets::Deterministic>(ruleset: &RuleSet) -> Vec<Playout<RuleSet>> {
+    let mut rng = rand::thread_rng();
+    let mut result = Vec::new();
+    for _ in 0..PLAYOUT_COUNT {
+        let mut state = ruleset.initial_state();
+        let mut plies = Vec::new();
+        let mut states = Vec::new();
+        while let rulesets::Status::Ongoing = ruleset.status(&state) {
+            assert!(plies.len() < MAX_PLAYOUT_LENGTH, "playout never ends");
+            let ply = plies::BasicIterator::new(ruleset, &state)
+                .choose(&mut rng)
+                .expect("ongoing state without any ply");
+            let next_state = ruleset.play(&state, &ply).unwrap();
+            plies.push(ply);
+            states.push(state);
+            state = next_state;
+        }
+        states.push(state);
+        result.push(Playout { plies, states });
+    }
+    result
+}
+
+/// Returns the states met along random playouts, each game ending on a terminal state.
+pub fn playout_states<RuleSet: rulesets::Deterministic>(ruleset: &RuleSet) -> Vec<RuleSet::State> {
+    playouts(ruleset)
+        .into_iter()
+        .flat_map(|playout| playout.states)
+        .collect()
+}
+
+/// Checks that `play` accepts exactly the plies yielded by the ply iterator.
+pub fn legal_plies<RuleSet>(ruleset: &RuleSet)
+where
+    RuleSet: rulesets::Deterministic + rulesets::EncodableState,
+    RuleSet::Ply: PartialEq,
+{
+    for state in playout_states(ruleset) {
+        if ruleset.status(&state) != rulesets::Status::Ongoing {
+            continue;
+        }
+        let legal_plies = plies::BasicIterator::new(ruleset, &state).collect::<Vec<_>>();
+        for ply in &legal_plies {
+            assert!(
+                ruleset.play(&state, ply).is_ok(),
+                "{:?} rejected on {:?}",
+                ply,
+                state
+            );
+        }
+        for ply_index in 0..RuleSet::PLY_COUNT {
+            let ply = ruleset.decode_ply(ply_index);
+            if ruleset.play(&state, &ply).is_ok() {
+                assert!(
+                    legal_plies.contains(&ply),
+                    "{:?} accepted on {:?}",
+                    ply,
+                    state
+                );
+            }
+        }
+    }
+}
+
+/// Checks that `reverse_state` undoes `swap_state` for every symmetry.
+pub fn symmetries<RuleSet>(ruleset: &RuleSet)
+where
+    RuleSet: rulesets::Deterministic + rulesets::HasStatesWithSymmetries,
+    RuleSet::State: PartialEq,
+{
+    for state in playout_states(ruleset) {
+        for symmetry in RuleSet::SymmetryIterator::new(ruleset) {
+            let swapped = ruleset.swap_state(&state, &symmetry);
+            assert!(ruleset.reverse_state(&swapped, &symmetry) == state);
+        }
+    }
+}
+
+/// Checks that swapping a ply and its state before or after playing it gives the same state.
+pub fn swap_ply<RuleSet>(ruleset: &RuleSet)
+where
+    RuleSet: rulesets::Deterministic + rulesets::HasStatesWithSymmetries,
+    RuleSet::State: PartialEq,
+{
+    let mut rng = rand::thread_rng();
+    for state in playout_states(ruleset) {
+        let ply = match plies::BasicIterator::new(ruleset, &state).choose(&mut rng) {
+            Some(ply) if ruleset.status(&state) == rulesets::Status::Ongoing => ply,
+            _ => continue,
+        };
+        let played = ruleset.play(&state, &ply).unwrap();
+        for symmetry in RuleSet::SymmetryIterator::new(ruleset) {
+            let swapped = ruleset.swap_state(&state, &symmetry);
+            let swapped_ply = ruleset.swap_ply(&ply, &symmetry);
+            let swapped_played = ruleset.play(&swapped, &swapped_ply).unwrap();
+            assert!(swapped_played == ruleset.swap_state(&played, &symmetry));
+        }
+    }
+}
+
+/// Checks that plies are encoded and decoded back, and that encoded states have the expected
+/// size.
+pub fn encoding<RuleSet>(ruleset: &RuleSet)
+where
+    RuleSet: rulesets::Deterministic + rulesets::EncodableState,
+    RuleSet::Ply: PartialEq,
+{
+    for ply_index in 0..RuleSet::PLY_COUNT {
+        let ply = ruleset.decode_ply(ply_index);
+        assert_eq!(ruleset.encode_ply(&ply), ply_index);
+    }
+    for state in playout_states(ruleset) {
+        assert_eq!(ruleset.encode_state(&state).len(), RuleSet::STATE_SIZE);
+        for ply in plies::BasicIterator::new(ruleset, &state) {
+            let ply_index = ruleset.encode_ply(&ply);
+            assert!(ply_index < RuleSet::PLY_COUNT);
+            assert!(ruleset.decode_ply(ply_index) == ply);
+        }
+    }
+}
+
+/// Checks that the status is consistent along played games: every state but the last is ongoing
+/// and has a ply to play, the last one is terminal, replaying the game from the initial state
+/// meets the same states and statuses, and the status does not depend on the symmetry the state
+/// is seen through.
+pub fn status<RuleSet>(ruleset: &RuleSet)
+where
+    RuleSet: rulesets::Deterministic + rulesets::HasStatesWithSymmetries + rulesets::TurnByTurn,
+    RuleSet::State: PartialEq,
+{
+    // Winners are compared relatively to the current player, as symmetries may switch players
+    let relative_status = |state: &RuleSet::State| match ruleset.status(state) {
+        rulesets::Status::Win { player } => rulesets::Status::Win {
+            player: (player != ruleset.current_player(state)) as rulesets::Player,
+        },
+        status => status,
+    };
+    let mut rng = rand::thread_rng();
+    for playout in playouts(ruleset) {
+        let (last_state, states) = playout.states.split_last().unwrap();
+        for state in states {
+            assert_eq!(ruleset.status(state), rulesets::Status::Ongoing);
+            assert!(plies::BasicIterator::new(ruleset, state).next().is_some());
+        }
+        match ruleset.status(last_state) {
+            rulesets::Status::Ongoing => panic!("playout ended on {:?}", last_state),
+            rulesets::Status::Win { player } => assert!(player < 2),
+            rulesets::Status::Draw => (),
+        }
+
+        let mut state = ruleset.initial_state();
+        for (ply, expected) in playout.plies.iter().zip(&playout.states) {
+            assert!(state == *expected);
+            state = ruleset.play(&state, ply).unwrap();
+        }
+        assert!(state == *last_state);
+        assert_eq!(ruleset.status(&state), ruleset.status(last_state));
+
+        for state in &playout.states {
+            let symmetry = RuleSet::SymmetryIterator::new(ruleset)
+                .choose(&mut rng)
+                .unwrap();
+            let swapped = ruleset.swap_state(state, &symmetry);
+            assert_eq!(relative_status(&swapped), relative_status(state));
+        }
+    }
+}
@@ -1,3 +1,5 @@
+#[cfg(test)]
+pub mod conformance;
 mod empty_state;
 
 pub use empty_state::EmptyState;
@@ -1 +1,3 @@
+pub mod perft;
 pub mod plies;
+pub mod symmetries;
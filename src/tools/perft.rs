@@ -0,0 +1,157 @@
+//! Move generation verification
+//!
+//! Perft counts the plies sequences of a given length from a state, games ending earlier being
+//! left out. Comparing the counts with known values catches move generation bugs, and their
+//! timing measures the move generation throughput.
+
+use super::plies;
+use crate::interface::rulesets;
+use std::hash;
+use std::time;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Enumeration {
+    /// Every ply yielded by the ply iterator
+    Basic,
+    /// A single ply among those equivalent through the symmetries of the state
+    Symmetries,
+}
+
+pub struct PerftResult<RuleSet: rulesets::RuleSetTrait> {
+    pub nodes: u64,
+    pub divide: Vec<(RuleSet::Ply, u64)>,
+    pub elapsed: time::Duration,
+}
+
+impl<RuleSet: rulesets::RuleSetTrait> PerftResult<RuleSet> {
+    pub fn nodes_per_second(&self) -> f64 {
+        self.nodes as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+fn root_plies<RuleSet>(
+    ruleset: &RuleSet,
+    state: &RuleSet::State,
+    enumeration: Enumeration,
+) -> Vec<RuleSet::Ply>
+where
+    RuleSet: rulesets::HasStatesWithSymmetries,
+    RuleSet::Ply: Eq + Ord + hash::Hash,
+    RuleSet::State: Eq,
+{
+    match enumeration {
+        Enumeration::Basic => plies::BasicIterator::new(ruleset, state).collect(),
+        Enumeration::Symmetries => plies::SymmetriesIterator::new(ruleset, state).collect(),
+    }
+}
+
+/// Counts the leaf nodes at the given depth on the current thread.
+pub fn count<RuleSet>(
+    ruleset: &RuleSet,
+    state: &RuleSet::State,
+    depth: usize,
+    enumeration: Enumeration,
+) -> u64
+where
+    RuleSet: rulesets::Deterministic + rulesets::HasStatesWithSymmetries,
+    RuleSet::Ply: Eq + Ord + hash::Hash,
+    RuleSet::State: Eq,
+{
+    if depth == 0 {
+        return 1;
+    }
+    if ruleset.status(state) != rulesets::Status::Ongoing {
+        return 0;
+    }
+    root_plies(ruleset, state, enumeration)
+        .iter()
+        .map(|ply| {
+            let child = ruleset.play(state, ply).unwrap();
+            count(ruleset, &child, depth - 1, enumeration)
+        })
+        .sum()
+}
+
+/// Counts the leaf nodes at the given depth, exploring each root ply on its own thread.
+pub fn perft<RuleSet>(
+    ruleset: &RuleSet,
+    state: &RuleSet::State,
+    depth: usize,
+    enumeration: Enumeration,
+) -> PerftResult<RuleSet>
+where
+    RuleSet: rulesets::Deterministic + rulesets::HasStatesWithSymmetries,
+    RuleSet::Ply: Eq + Ord + hash::Hash,
+    RuleSet::State: Eq,
+{
+    let start = time::Instant::now();
+    let plies = if depth == 0 || ruleset.status(state) != rulesets::Status::Ongoing {
+        Vec::new()
+    } else {
+        root_plies(ruleset, state, enumeration)
+    };
+    let divide = crossbeam::scope(|scope| {
+        let handles = plies
+            .iter()
+            .map(|ply| {
+                let ruleset = ruleset.clone();
+                let child = ruleset.play(state, ply).unwrap();
+                scope.spawn(move |_| count(&ruleset, &child, depth - 1, enumeration))
+            })
+            .collect::<Vec<_>>();
+        plies
+            .iter()
+            .cloned()
+            .zip(handles.into_iter().map(|handle| handle.join().unwrap()))
+            .collect::<Vec<_>>()
+    })
+    .unwrap();
+    let nodes = if depth == 0 {
+        1
+    } else {
+        divide.iter().map(|(_, nodes)| nodes).sum()
+    };
+    PerftResult {
+        nodes,
+        divide,
+        elapsed: start.elapsed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::rulesets::RuleSetTrait;
+    use crate::rulesets::connectn;
+    use crate::rulesets::reversi;
+
+    macro_rules! perft_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (ruleset, enumeration, expected) = $value;
+                    let state = ruleset.initial_state();
+                    for (depth, expected_nodes) in expected.iter().enumerate() {
+                        let result = perft(&ruleset, &state, depth + 1, enumeration);
+                        assert_eq!(result.nodes, *expected_nodes, "depth {}", depth + 1);
+                    }
+                }
+            )*
+        }
+    }
+
+    perft_tests! {
+        tictactoe: (connectn::TicTacToe::new(), Enumeration::Basic, [9, 72, 504, 3024, 15120, 54720]),
+        tictactoe_symmetries: (connectn::TicTacToe::new(), Enumeration::Symmetries, [3, 12]),
+        reversi_classic: (reversi::Reversi::<reversi::Classic>::new(), Enumeration::Basic, [4, 12, 56, 244, 1396, 8200, 55092]),
+    }
+
+    #[test]
+    fn test_divide() {
+        let ruleset = connectn::TicTacToe::new();
+        let result = perft(&ruleset, &ruleset.initial_state(), 2, Enumeration::Basic);
+        assert_eq!(result.divide.len(), 9);
+        assert!(result.divide.iter().all(|(_, nodes)| *nodes == 8));
+    }
+}
@@ -0,0 +1,109 @@
+use crate::interface::rulesets;
+use crate::interface::rulesets::SymmetryIteratorTrait;
+
+/// Returns the smallest state among the symmetries of the given one, along with the symmetry
+/// leading to it.
+///
+/// Symmetric states share their canonical form, which makes it a key for tables of positions.
+pub fn canonicalize<RuleSet: rulesets::HasStatesWithSymmetries>(
+    ruleset: &RuleSet,
+    state: &RuleSet::State,
+) -> (RuleSet::State, RuleSet::Symmetry)
+where
+    RuleSet::State: Ord,
+{
+    RuleSet::SymmetryIterator::new(ruleset)
+        .map(|symmetry| (ruleset.swap_state(state, &symmetry), symmetry))
+        .min_by(|(left, _), (right, _)| left.cmp(right))
+        .unwrap()
+}
+
+/// Returns the symmetries leaving the state unchanged.
+pub fn invariant_symmetries<RuleSet: rulesets::HasStatesWithSymmetries>(
+    ruleset: &RuleSet,
+    state: &RuleSet::State,
+) -> Vec<RuleSet::Symmetry>
+where
+    RuleSet::State: Eq,
+{
+    RuleSet::SymmetryIterator::new(ruleset)
+        .filter(|symmetry| ruleset.swap_state(state, symmetry) == *state)
+        .collect()
+}
+
+/// Returns the distinct plies the ply is mapped to by the invariant symmetries of a state, the
+/// first one being the smallest, which is the one `SymmetriesIterator` yields.
+pub fn equivalent_plies<RuleSet: rulesets::HasStatesWithSymmetries>(
+    ruleset: &RuleSet,
+    symmetries: &[RuleSet::Symmetry],
+    ply: &RuleSet::Ply,
+) -> Vec<RuleSet::Ply>
+where
+    RuleSet::Ply: Ord,
+{
+    let mut plies = symmetries
+        .iter()
+        .map(|symmetry| ruleset.swap_ply(ply, symmetry))
+        .collect::<Vec<_>>();
+    plies.sort();
+    plies.dedup();
+    plies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::rulesets::HasStatesWithSymmetries;
+    use crate::rulesets::connectn;
+
+    macro_rules! canonicalize_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (left, right, same) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let (left_p1, left_p2, left_player) = left;
+                    let (right_p1, right_p2, right_player) = right;
+                    let left = connectn::TicTacToeState::from_indices(&left_p1, &left_p2, left_player);
+                    let right = connectn::TicTacToeState::from_indices(&right_p1, &right_p2, right_player);
+                    let (left_canonical, symmetry) = canonicalize(&ruleset, &left);
+                    assert_eq!(ruleset.swap_state(&left, &symmetry), left_canonical);
+                    let (right_canonical, _) = canonicalize(&ruleset, &right);
+                    assert_eq!(left_canonical == right_canonical, same);
+                }
+            )*
+        }
+    }
+
+    canonicalize_tests! {
+        mirrored: (([0], [1], 1), ([2], [1], 1), true),
+        rotated: (([0, 4], [1], 1), ([2, 4], [5], 1), true),
+        swapped_players: (([0], [4], 0), ([4], [0], 1), true),
+        distinct: (([0], [1], 1), ([0], [4], 1), false),
+    }
+
+    macro_rules! equivalent_plies_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (p1_indices, p2_indices, ply, expected) = $value;
+                    let ruleset = connectn::TicTacToe::new();
+                    let state = connectn::TicTacToeState::from_indices(&p1_indices, &p2_indices, 0);
+                    let symmetries = invariant_symmetries(&ruleset, &state);
+                    let plies = equivalent_plies(&ruleset, &symmetries, &connectn::Ply::new(ply));
+                    let expected = expected.iter().map(|index| connectn::Ply::new(*index)).collect::<Vec<_>>();
+                    assert_eq!(plies, expected);
+                }
+            )*
+        }
+    }
+
+    equivalent_plies_tests! {
+        corner: ([], [], 2, [0, 2, 6, 8]),
+        center: ([], [], 4, [4]),
+        mirrored_edge: ([4, 1], [0, 2], 3, [3, 5]),
+        diagonal: ([4], [0], 5, [5, 7]),
+    }
+}
@@ -0,0 +1,62 @@
+use std::hash;
+use std::hash::Hasher;
+
+const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const PRIME: u64 = 0x0100_0000_01b3;
+
+/// 64-bit FNV-1a hasher.
+///
+/// Unlike the standard library hasher, its results do not depend on the process nor on the
+/// compiler version, so they can be shared between threads and stored on disk.
+#[derive(Clone, Copy, Debug)]
+pub struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> FnvHasher {
+        FnvHasher(OFFSET_BASIS)
+    }
+}
+
+impl hash::Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(PRIME);
+        }
+    }
+}
+
+pub fn hash<Value: hash::Hash + ?Sized>(value: &Value) -> u64 {
+    let mut hasher = FnvHasher::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! fnv_tests {
+        ($($name:ident: $value:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (bytes, expected) = $value;
+                    let mut hasher = FnvHasher::default();
+                    hasher.write(bytes);
+                    assert_eq!(hasher.finish(), expected);
+                }
+            )*
+        }
+    }
+
+    fnv_tests! {
+        empty: (b"", 0xcbf2_9ce4_8422_2325),
+        single_byte: (b"a", 0xaf63_dc4c_8601_ec8c),
+        word: (b"foobar", 0x8594_4171_f739_67e8),
+    }
+}
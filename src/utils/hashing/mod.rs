@@ -0,0 +1,3 @@
+mod fnv;
+
+pub use fnv::hash;
@@ -1,3 +1,4 @@
 pub mod bitarray;
 pub mod grids;
+pub mod hashing;
 pub mod vectors;